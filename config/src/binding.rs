@@ -0,0 +1,108 @@
+// This file is part of the soulmemory-rs distribution (https://github.com/FrankvdStam/soulmemory-rs).
+// Copyright (c) 2022 Frank van der Stam.
+// https://github.com/FrankvdStam/soulmemory-rs/blob/main/LICENSE
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+
+use crate::keys::{key_name, parse_key};
+use soulmemory_tas::script::BUTTONS;
+
+///A keyboard combo, "ctrl+shift+1", or a chord of gamepad buttons, "pad:lb+rb+up"
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Binding
+{
+    Keyboard { key: u16, ctrl: bool, shift: bool, alt: bool },
+    Gamepad { buttons: u16 },
+}
+
+impl Binding
+{
+    ///An empty string is no binding
+    pub fn parse(text: &str) -> Result<Option<Binding>, String>
+    {
+        let text = text.trim().to_lowercase();
+        if text.is_empty()
+        {
+            return Ok(None);
+        }
+
+        if let Some(chord) = text.strip_prefix("pad:")
+        {
+            let mut buttons = 0;
+            for name in chord.split('+')
+            {
+                buttons |= BUTTONS.iter().find(|(button, _)| *button == name.trim()).map(|(_, bit)| *bit).ok_or(format!("unknown button {}", name))?;
+            }
+            return Ok(Some(Binding::Gamepad { buttons }));
+        }
+
+        let (mut ctrl, mut shift, mut alt) = (false, false, false);
+        let mut parts: Vec<&str> = text.split('+').map(|part| part.trim()).collect();
+        let key = parts.pop().unwrap_or_default();
+        for part in parts
+        {
+            match part
+            {
+                "ctrl" => ctrl = true,
+                "shift" => shift = true,
+                "alt" => alt = true,
+                _ => return Err(format!("unknown modifier {}", part)),
+            }
+        }
+        let key = parse_key(key).ok_or(format!("unknown key {}", key))?;
+        return Ok(Some(Binding::Keyboard { key, ctrl, shift, alt }));
+    }
+
+    pub fn to_text(&self) -> String
+    {
+        return match self
+        {
+            Binding::Keyboard { key, ctrl, shift, alt } =>
+            {
+                let mut parts = Vec::new();
+                if *ctrl { parts.push(String::from("ctrl")); }
+                if *shift { parts.push(String::from("shift")); }
+                if *alt { parts.push(String::from("alt")); }
+                parts.push(key_name(*key).unwrap_or_default());
+                parts.join("+")
+            }
+            Binding::Gamepad { buttons } =>
+            {
+                let names: Vec<&str> = BUTTONS.iter().filter(|(_, bit)| buttons & bit != 0).map(|(name, _)| *name).collect();
+                format!("pad:{}", names.join("+"))
+            }
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use crate::binding::Binding;
+
+    #[test]
+    fn parse_bindings()
+    {
+        assert_eq!(Some(Binding::Keyboard { key: 0x31, ctrl: true, shift: true, alt: false }), Binding::parse("Ctrl+Shift+1").unwrap());
+        assert_eq!(Some(Binding::Gamepad { buttons: 0x0301 }), Binding::parse("pad:lb+rb+up").unwrap());
+        assert_eq!(None, Binding::parse("").unwrap());
+        assert!(Binding::parse("hyper+1").is_err());
+        assert!(Binding::parse("pad:z").is_err());
+
+        for text in ["ctrl+shift+1", "alt+f5", "oem_5", "numpad3", "pad:up+lb+rb"]
+        {
+            assert_eq!(text, Binding::parse(text).unwrap().unwrap().to_text());
+        }
+    }
+}
@@ -0,0 +1,370 @@
+// This file is part of the soulmemory-rs distribution (https://github.com/FrankvdStam/soulmemory-rs).
+// Copyright (c) 2022 Frank van der Stam.
+// https://github.com/FrankvdStam/soulmemory-rs/blob/main/LICENSE
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+use toml_edit::DocumentMut;
+use crate::binding::Binding;
+
+pub const CONFIG_FILE: &str = "soulmemory.toml";
+
+///soulmemory.toml next to the dll. Every section and key is optional:
+///
+///[server]
+///enabled = true
+///address = "127.0.0.1:54345"
+///
+///[log]
+///path = "soulmemory.log"
+///level = "info"
+///
+///[recording]
+///enabled = true
+///directory = "D:/recordings"
+///max_sessions = 10
+///
+///[hotkeys]
+///quitout = "oem_5"
+///save_position_1 = "ctrl+shift+1"
+///toggle_no_death = "pad:back+up"
+///
+///[widgets]
+///tas = false
+///
+///[event_flags]
+///log_mode = "unique"
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config
+{
+    pub server: ServerConfig,
+    pub log: LogConfig,
+    pub recording: RecordingConfig,
+    ///Action id to binding, see Binding. Actions that aren't listed keep their default binding, an
+    ///empty string unbinds the action.
+    pub hotkeys: BTreeMap<String, String>,
+    pub widgets: WidgetConfig,
+    pub event_flags: EventFlagConfig,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerConfig
+{
+    pub enabled: bool,
+    pub address: String,
+}
+
+impl Default for ServerConfig
+{
+    fn default() -> Self
+    {
+        ServerConfig { enabled: true, address: String::from("127.0.0.1:54345") }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LogLevel
+{
+    Off,
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl LogLevel
+{
+    pub fn to_level_filter(self) -> log::LevelFilter
+    {
+        return match self
+        {
+            LogLevel::Off => log::LevelFilter::Off,
+            LogLevel::Error => log::LevelFilter::Error,
+            LogLevel::Warn => log::LevelFilter::Warn,
+            LogLevel::Info => log::LevelFilter::Info,
+            LogLevel::Debug => log::LevelFilter::Debug,
+            LogLevel::Trace => log::LevelFilter::Trace,
+        };
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LogConfig
+{
+    ///Relative to the dll
+    pub path: String,
+    pub level: LogLevel,
+}
+
+impl Default for LogConfig
+{
+    fn default() -> Self
+    {
+        LogConfig { path: String::from("soulmemory.log"), level: LogLevel::Info }
+    }
+}
+
+impl LogConfig
+{
+    pub fn resolve_path(&self, module_directory: Option<&Path>) -> PathBuf
+    {
+        return resolve_path(module_directory, &self.path);
+    }
+}
+
+///Event flag recording, off unless enabled
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RecordingConfig
+{
+    pub enabled: bool,
+    ///Relative to the dll, the recordings directory next to it when empty
+    pub directory: String,
+    ///Sessions of a game that are kept, older ones are removed when a new session starts
+    pub max_sessions: usize,
+}
+
+impl Default for RecordingConfig
+{
+    fn default() -> Self
+    {
+        RecordingConfig { enabled: false, directory: String::new(), max_sessions: 10 }
+    }
+}
+
+///Which widgets are shown, keyed by the name the widget is registered under. Widgets that aren't listed are shown,
+///widgets that don't apply to the game are hidden regardless
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct WidgetConfig
+{
+    enabled: BTreeMap<String, bool>,
+}
+
+impl WidgetConfig
+{
+    pub fn is_enabled(&self, name: &str) -> bool
+    {
+        return self.enabled.get(name).copied().unwrap_or(true);
+    }
+
+    ///Names in the config that aren't in the given widget names, most likely a typo
+    pub fn unknown<'a>(&'a self, names: &[&str]) -> Vec<&'a str>
+    {
+        return self.enabled.keys().map(|k| k.as_str()).filter(|k| !names.contains(k)).collect();
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LogMode
+{
+    All,
+    Unique,
+    Blacklist,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct EventFlagConfig
+{
+    ///What the event flag widget shows when it opens
+    pub log_mode: LogMode,
+}
+
+impl Default for EventFlagConfig
+{
+    fn default() -> Self
+    {
+        EventFlagConfig { log_mode: LogMode::Unique }
+    }
+}
+
+///Paths in the config are relative to the dll, absolute ones are used as they are
+pub fn resolve_path(module_directory: Option<&Path>, path: &str) -> PathBuf
+{
+    return match module_directory
+    {
+        Some(module_directory) => module_directory.join(path),
+        None => PathBuf::from(path),
+    };
+}
+
+impl Config
+{
+    ///The defaults when the file doesn't exist
+    pub fn load(path: &Path) -> Result<Config, String>
+    {
+        if !path.exists()
+        {
+            return Ok(Config::default());
+        }
+
+        let text = fs::read_to_string(path).map_err(|e| format!("failed to read {}: {}", path.display(), e))?;
+        return Config::parse(&text).map_err(|e| format!("{}: {}", path.display(), e));
+    }
+
+    pub fn parse(text: &str) -> Result<Config, String>
+    {
+        let config = toml::from_str::<Config>(text).map_err(|e| e.to_string())?;
+        config.validate()?;
+        return Ok(config);
+    }
+
+    pub fn validate(&self) -> Result<(), String>
+    {
+        if self.server.enabled
+        {
+            self.server.address.parse::<SocketAddr>().map_err(|e| format!("server.address {}: {}", self.server.address, e))?;
+        }
+
+        if self.log.path.trim().is_empty()
+        {
+            return Err(String::from("log.path can't be empty"));
+        }
+
+        if self.recording.max_sessions == 0
+        {
+            return Err(String::from("recording.max_sessions must be at least 1"));
+        }
+
+        for (action, binding) in &self.hotkeys
+        {
+            Binding::parse(binding).map_err(|e| format!("hotkeys.{}: {}", action, e))?;
+        }
+        return Ok(());
+    }
+
+    ///Writes the bindings into the [hotkeys] table, the rest of the file, comments included, is left as it is
+    pub fn save_hotkeys(path: &Path, hotkeys: &BTreeMap<String, String>) -> Result<(), String>
+    {
+        let text = match path.exists()
+        {
+            true => fs::read_to_string(path).map_err(|e| format!("failed to read {}: {}", path.display(), e))?,
+            false => String::new(),
+        };
+        let mut document = text.parse::<DocumentMut>().map_err(|e| format!("{}: {}", path.display(), e))?;
+        let table = document.entry("hotkeys").or_insert(toml_edit::table()).as_table_mut().ok_or(format!("{}: hotkeys is not a table", path.display()))?;
+        for (action, binding) in hotkeys
+        {
+            table[action.as_str()] = toml_edit::value(binding.as_str());
+        }
+        return fs::write(path, document.to_string()).map_err(|e| format!("failed to write {}: {}", path.display(), e));
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use crate::config::*;
+
+    #[test]
+    fn defaults()
+    {
+        assert_eq!(Config::default(), Config::parse("").unwrap());
+        assert_eq!(Config::default(), Config::load(Path::new("does/not/exist.toml")).unwrap());
+        assert!(Config::default().hotkeys.is_empty());
+
+        let directory = Path::new("game");
+        assert_eq!(directory.join("soulmemory.log"), LogConfig::default().resolve_path(Some(directory)));
+        assert_eq!(PathBuf::from("soulmemory.log"), LogConfig::default().resolve_path(None));
+    }
+
+    #[test]
+    fn parse_config()
+    {
+        let config = Config::parse(r#"
+            [server]
+            address = "0.0.0.0:1234"
+
+            [log]
+            level = "debug"
+
+            [recording]
+            enabled = true
+            max_sessions = 3
+
+            [hotkeys]
+            quitout = "F5"
+            restore_position_1 = "pad:lb+up"
+
+            [widgets]
+            tas = false
+
+            [event_flags]
+            log_mode = "all"
+        "#).unwrap();
+
+        assert!(config.server.enabled);
+        assert_eq!("0.0.0.0:1234", config.server.address);
+        assert_eq!(LogLevel::Debug, config.log.level);
+        assert_eq!(LogConfig::default().path, config.log.path);
+        assert!(config.recording.enabled && config.recording.directory.is_empty());
+        assert_eq!(3, config.recording.max_sessions);
+        assert_eq!("F5", config.hotkeys["quitout"]);
+        assert_eq!(2, config.hotkeys.len());
+        assert!(!config.widgets.is_enabled("tas") && config.widgets.is_enabled("splits") && config.widgets.is_enabled("third_party"));
+        assert!(config.widgets.unknown(&["tas", "splits"]).is_empty());
+        assert_eq!(vec!["tas"], config.widgets.unknown(&["splits"]));
+        assert_eq!(LogMode::All, config.event_flags.log_mode);
+    }
+
+    #[test]
+    fn save_hotkeys_only()
+    {
+        let path = std::env::temp_dir().join(format!("soulmemory-config-test-{}.toml", std::process::id()));
+        fs::write(&path, "# my config\n[log]\nlevel = \"debug\" # keep\n\n[hotkeys]\n# quitting\nquitout = \"f5\"\n").unwrap();
+
+        let mut hotkeys = BTreeMap::new();
+        hotkeys.insert(String::from("quitout"), String::from("f6"));
+        hotkeys.insert(String::from("save_position_1"), String::new());
+        let result = Config::save_hotkeys(&path, &hotkeys);
+        let text = fs::read_to_string(&path);
+        fs::remove_file(&path).unwrap();
+        result.unwrap();
+        let text = text.unwrap();
+
+        assert!(text.contains("# my config") && text.contains("level = \"debug\" # keep") && text.contains("# quitting"));
+        assert!(!text.contains("[server]"), "defaults aren't written");
+        let config = Config::parse(&text).unwrap();
+        assert_eq!(hotkeys, config.hotkeys);
+        assert_eq!(LogLevel::Debug, config.log.level);
+    }
+
+    #[test]
+    fn validation_errors()
+    {
+        assert!(Config::parse("[server]\naddress = \"localhost\"").unwrap_err().contains("server.address"));
+        assert!(Config::parse("[server]\nenabled = false\naddress = \"localhost\"").is_ok());
+        assert!(Config::parse("[hotkeys]\nquitout = \"ctrl\"").unwrap_err().contains("hotkeys.quitout"));
+        assert!(Config::parse("[hotkeys]\nquitout = \"pad:lb+z\"").unwrap_err().contains("hotkeys.quitout"));
+        assert!(Config::parse("[hotkeys]\nquitout = \"\"").is_ok());
+        assert!(Config::parse("[recording]\nmax_sessions = 0").unwrap_err().contains("recording.max_sessions"));
+        assert!(!Config::default().recording.enabled);
+        assert!(Config::parse("[log]\nlevel = \"loud\"").is_err());
+        assert!(Config::parse("[widget]\ntas = false").is_err());
+
+        //toml errors point at the line
+        assert!(Config::parse("[server]\n\nenabled = 1").unwrap_err().contains("line 3"));
+    }
+}
@@ -0,0 +1,77 @@
+// This file is part of the soulmemory-rs distribution (https://github.com/FrankvdStam/soulmemory-rs).
+// Copyright (c) 2022 Frank van der Stam.
+// https://github.com/FrankvdStam/soulmemory-rs/blob/main/LICENSE
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+///Virtual key codes by name, letters and digits are matched separately
+const KEYS: &[(&str, u16)] = &[
+    ("backspace", 0x08),
+    ("tab",       0x09),
+    ("enter",     0x0d),
+    ("escape",    0x1b),
+    ("space",     0x20),
+    ("pageup",    0x21),
+    ("pagedown",  0x22),
+    ("end",       0x23),
+    ("home",      0x24),
+    ("insert",    0x2d),
+    ("delete",    0x2e),
+    ("oem_1",     0xba),
+    ("oem_plus",  0xbb),
+    ("oem_comma", 0xbc),
+    ("oem_minus", 0xbd),
+    ("oem_period",0xbe),
+    ("oem_2",     0xbf),
+    ("oem_3",     0xc0),
+    ("oem_4",     0xdb),
+    ("oem_5",     0xdc),
+    ("oem_6",     0xdd),
+    ("oem_7",     0xde),
+    ("oem_8",     0xdf),
+];
+
+///Maps a key name to its virtual key code: a-z, 0-9, f1-f24, numpad0-numpad9 and the names in KEYS
+pub fn parse_key(key: &str) -> Option<u16>
+{
+    let key = key.trim().to_lowercase();
+    if key.len() == 1
+    {
+        let c = key.chars().next()?;
+        return c.is_ascii_alphanumeric().then_some(c.to_ascii_uppercase() as u16);
+    }
+
+    if let Some(n) = key.strip_prefix("numpad").and_then(|n| n.parse::<u16>().ok()).filter(|n| *n <= 9)
+    {
+        return Some(0x60 + n);//VK_NUMPAD0
+    }
+
+    if let Some(n) = key.strip_prefix('f').and_then(|n| n.parse::<u16>().ok()).filter(|n| (1..=24).contains(n))
+    {
+        return Some(0x70 + n - 1);//VK_F1
+    }
+
+    return KEYS.iter().find(|(name, _)| *name == key).map(|(_, code)| *code);
+}
+
+///The name parse_key maps to the virtual key code, if there is one
+pub fn key_name(code: u16) -> Option<String>
+{
+    return match code
+    {
+        0x30..=0x39 | 0x41..=0x5a => Some((code as u8 as char).to_ascii_lowercase().to_string()),
+        0x60..=0x69 => Some(format!("numpad{}", code - 0x60)),
+        0x70..=0x87 => Some(format!("f{}", code - 0x70 + 1)),
+        _ => KEYS.iter().find(|(_, c)| *c == code).map(|(name, _)| String::from(*name)),
+    };
+}
@@ -0,0 +1,30 @@
+// This file is part of the soulmemory-rs distribution (https://github.com/FrankvdStam/soulmemory-rs).
+// Copyright (c) 2022 Frank van der Stam.
+// https://github.com/FrankvdStam/soulmemory-rs/blob/main/LICENSE
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+
+
+#![allow(clippy::needless_return)]
+
+//! soulmemory.toml and the hotkey bindings in it. Kept free of windows dependencies so the config can be parsed
+//! and validated in tests on any platform.
+
+pub mod config;
+pub mod keys;
+pub mod binding;
+
+pub use config::*;
+pub use keys::{key_name, parse_key};
+pub use binding::Binding;
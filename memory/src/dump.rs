@@ -0,0 +1,230 @@
+// This file is part of the soulmemory-rs distribution (https://github.com/FrankvdStam/soulmemory-rs).
+// Copyright (c) 2022 Frank van der Stam.
+// https://github.com/FrankvdStam/soulmemory-rs/blob/main/LICENSE
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+
+use std::cell::RefCell;
+use std::fs;
+use std::path::Path;
+use soulmemory_patterns::Symbol;
+use crate::minidump;
+use crate::source::MemorySource;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Region
+{
+    pub base: usize,
+    pub bytes: Vec<u8>,
+}
+
+///Memory loaded from a file instead of a running game. Writes change the copy in memory, never the file.
+#[derive(Debug)]
+pub struct MemoryDump
+{
+    is_64_bit: bool,
+    module_base: usize,
+    module_size: usize,
+    regions: RefCell<Vec<Region>>,
+}
+
+impl MemoryDump
+{
+    ///A dump of just the main module, as it is laid out in memory
+    pub fn new(is_64_bit: bool, module_base: usize, module: Vec<u8>) -> Self
+    {
+        let module_size = module.len();
+        return MemoryDump { is_64_bit, module_base, module_size, regions: RefCell::new(vec![Region { base: module_base, bytes: module }]) };
+    }
+
+    ///Maps more memory, for the heap objects pointer chains lead to
+    pub fn add_region(&mut self, base: usize, bytes: Vec<u8>)
+    {
+        self.regions.get_mut().push(Region { base, bytes });
+    }
+
+    pub fn module_base(&self) -> usize
+    {
+        return self.module_base;
+    }
+
+    ///Loads a minidump, or a raw dump of the main module which is mapped at the image base from its PE header
+    pub fn load(path: &Path) -> Result<MemoryDump, String>
+    {
+        let bytes = fs::read(path).map_err(|e| format!("failed to read {}: {}", path.display(), e))?;
+        return MemoryDump::parse(bytes).map_err(|e| format!("{}: {}", path.display(), e));
+    }
+
+    pub fn parse(bytes: Vec<u8>) -> Result<MemoryDump, String>
+    {
+        if bytes.starts_with(minidump::SIGNATURE)
+        {
+            let dump = minidump::parse(&bytes)?;
+            return Ok(MemoryDump { is_64_bit: dump.is_64_bit, module_base: dump.module_base, module_size: dump.module_size, regions: RefCell::new(dump.regions) });
+        }
+
+        let (is_64_bit, image_base) = image_base(&bytes).ok_or("neither a minidump nor a module dump")?;
+        return Ok(MemoryDump::new(is_64_bit, image_base, bytes));
+    }
+
+    ///The main module's bytes, unmapped pages are zeroes
+    pub fn module(&self) -> Vec<u8>
+    {
+        let mut module = vec![0u8; self.module_size];
+        for region in self.regions.borrow().iter()
+        {
+            let start = region.base.max(self.module_base);
+            let end = (region.base + region.bytes.len()).min(self.module_base + self.module_size);
+            if start < end
+            {
+                module[start - self.module_base..end - self.module_base].copy_from_slice(&region.bytes[start - region.base..end - region.base]);
+            }
+        }
+        return module;
+    }
+}
+
+#[cfg(test)]
+impl MemoryDump
+{
+    ///Writes a pointer the size of the dump's pointers, for building pointer chains in tests
+    pub fn write_pointer(&self, address: usize, value: usize)
+    {
+        let written = if self.is_64_bit { self.write(address, &(value as u64).to_le_bytes()) } else { self.write(address, &(value as u32).to_le_bytes()) };
+        assert!(written, "0x{:x} isn't mapped", address);
+    }
+}
+
+///Bitness and preferred base address from the PE header at the start of a module
+fn image_base(module: &[u8]) -> Option<(bool, usize)>
+{
+    if !module.starts_with(b"MZ")
+    {
+        return None;
+    }
+    let pe = u32::from_le_bytes(module.get(0x3c..0x40)?.try_into().ok()?) as usize;
+    if module.get(pe..pe + 4)? != b"PE\0\0"
+    {
+        return None;
+    }
+
+    let optional = pe + 24;
+    return match u16::from_le_bytes(module.get(optional..optional + 2)?.try_into().ok()?)
+    {
+        0x10b => Some((false, u32::from_le_bytes(module.get(optional + 28..optional + 32)?.try_into().ok()?) as usize)),
+        0x20b => Some((true, u64::from_le_bytes(module.get(optional + 24..optional + 32)?.try_into().ok()?) as usize)),
+        _ => None,
+    };
+}
+
+impl MemorySource for MemoryDump
+{
+    fn is_64_bit(&self) -> bool
+    {
+        return self.is_64_bit;
+    }
+
+    fn read(&self, address: usize, buffer: &mut [u8]) -> bool
+    {
+        //A garbage pointer can be close enough to the end of the address space to wrap
+        let end = match address.checked_add(buffer.len())
+        {
+            Some(end) => end,
+            None => return false,
+        };
+        for region in self.regions.borrow().iter()
+        {
+            if address >= region.base && end <= region.base + region.bytes.len()
+            {
+                let start = address - region.base;
+                buffer.copy_from_slice(&region.bytes[start..start + buffer.len()]);
+                return true;
+            }
+        }
+        return false;
+    }
+
+    fn write(&self, address: usize, buffer: &[u8]) -> bool
+    {
+        let end = match address.checked_add(buffer.len())
+        {
+            Some(end) => end,
+            None => return false,
+        };
+        for region in self.regions.borrow_mut().iter_mut()
+        {
+            if address >= region.base && end <= region.base + region.bytes.len()
+            {
+                let start = address - region.base;
+                region.bytes[start..start + buffer.len()].copy_from_slice(buffer);
+                return true;
+            }
+        }
+        return false;
+    }
+
+    fn scan(&self, name: &str, symbol: &Symbol) -> Result<usize, String>
+    {
+        let resolution = symbol.resolve(&self.module()).map_err(|e| format!("Scan failed: {}: {}", name, e))?;
+        return Ok(self.module_base + resolution.address);
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use soulmemory_patterns::{Pattern, Symbol};
+    use crate::dump::MemoryDump;
+    use crate::error::MemoryError;
+    use crate::pointer::MemoryPointer;
+    use crate::source::MemorySource;
+
+    #[test]
+    fn pointer_chains()
+    {
+        //mov rax, [rip + 0x100] at 0x10, the global at 0x117 points at an object on the heap
+        let mut module = vec![0u8; 0x200];
+        module[0x10..0x17].copy_from_slice(&[0x48, 0x8b, 0x05, 0x00, 0x01, 0x00, 0x00]);
+        module[0x117..0x11f].copy_from_slice(&0x7ff0_0000_0000u64.to_le_bytes());
+        let mut dump = MemoryDump::new(true, 0x1_4000_0000, module);
+
+        let mut heap = vec![0u8; 0x100];
+        heap[0x28..0x2c].copy_from_slice(&1234u32.to_le_bytes());
+        dump.add_region(0x7ff0_0000_0000, heap);
+
+        let symbol = Symbol { pattern: Pattern::parse("48 8b 05 ? ? ? ?").unwrap(), offset: 3, instruction_length: Some(7), chain: vec![0] };
+        let pointer = dump.scan_pointer("GameDataMan", &symbol).unwrap();
+        assert_eq!(MemoryPointer::new(0x1_4000_0117, vec![0]), pointer);
+        assert_eq!(Ok(1234), pointer.read_u32(&dump, Some(0x28)));
+
+        pointer.write_u32(&dump, Some(0x28), 5678).unwrap();
+        assert_eq!(Some(5678), dump.read_u32(0x7ff0_0000_0028));
+        assert!(pointer.read_u32(&dump, Some(0x100)).is_err());
+        assert!(MemoryPointer::new(0x1_4000_0000, vec![0, 0]).read_u32(&dump, None).is_err());
+        assert!(dump.scan("missing", &Symbol { pattern: Pattern::parse("c3 c3").unwrap(), offset: 0, instruction_length: None, chain: Vec::new() }).is_err());
+    }
+
+    #[test]
+    fn garbage_pointers()
+    {
+        let mut module = vec![0u8; 0x100];
+        module[0x8..0x10].copy_from_slice(&(usize::MAX as u64 - 2).to_le_bytes());
+        let dump = MemoryDump::new(true, 0x1000, module);
+
+        assert_eq!(None, dump.read_u32(usize::MAX - 2));
+        assert!(!dump.write_u32(usize::MAX - 2, 1));
+        assert_eq!(Err(MemoryError::Unreadable(usize::MAX - 2)), MemoryPointer::new(0x1000, vec![0x8, 0x10]).resolve(&dump, None));
+        assert_eq!(Err(MemoryError::Unreadable(usize::MAX - 2)), MemoryPointer::new(0x1000, vec![0x8, 0]).read_u32(&dump, Some(0)));
+    }
+}
@@ -0,0 +1,42 @@
+// This file is part of the soulmemory-rs distribution (https://github.com/FrankvdStam/soulmemory-rs).
+// Copyright (c) 2022 Frank van der Stam.
+// https://github.com/FrankvdStam/soulmemory-rs/blob/main/LICENSE
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+
+use std::fmt;
+use std::fmt::Display;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MemoryError
+{
+    ///The event flag id doesn't map onto the game's flag storage
+    InvalidEventFlag(u32),
+    ///Nothing is mapped at the address, or a pointer along the chain was null
+    Unreadable(usize),
+}
+
+impl Display for MemoryError
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        match self
+        {
+            MemoryError::InvalidEventFlag(flag) => write!(f, "invalid event flag {}", flag),
+            MemoryError::Unreadable(address) => write!(f, "failed to access memory at 0x{:x}", address),
+        }
+    }
+}
+
+impl std::error::Error for MemoryError {}
@@ -0,0 +1,206 @@
+// This file is part of the soulmemory-rs distribution (https://github.com/FrankvdStam/soulmemory-rs).
+// Copyright (c) 2022 Frank van der Stam.
+// https://github.com/FrankvdStam/soulmemory-rs/blob/main/LICENSE
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+
+//Credit to JKAnderson for the event flag reading code, https://github.com/JKAnderson/DS-Gadget
+//Dark Souls: Prepare to Die Edition and Remastered share the flag layout, a bitfield indexed by the digits of the flag id.
+
+use crate::error::MemoryError;
+use crate::pointer::MemoryPointer;
+use crate::source::MemorySource;
+use crate::event_flags::snapshot::EventFlagSnapshot;
+
+///The first digit of a flag id and where its flags start in the storage
+const GROUPS: [(&str, usize); 5] = [("0", 0x00000), ("1", 0x00500), ("5", 0x05F00), ("6", 0x0B900), ("7", 0x11300)];
+
+///The next three digits, each area has 0x500 bytes of flags within its group
+const AREAS: [&str; 18] = ["000", "100", "101", "102", "110", "120", "121", "130", "131", "132", "140", "141", "150", "151", "160", "170", "180", "181"];
+
+///The storage ends with the last group's areas
+const STORAGE_SIZE: usize = 0x11300 + AREAS.len() * 0x500;
+
+fn get_event_flag_group(group: &str) -> Option<usize>
+{
+    return GROUPS.iter().find(|(g, _)| *g == group).map(|(_, offset)| *offset);
+}
+
+fn get_event_flag_area(area: &str) -> Option<usize>
+{
+    return AREAS.iter().position(|a| *a == area);
+}
+
+///Byte offset of the u32 holding the flag from the start of the flag storage, and the flag's mask within it
+pub fn get_event_flag_offset(event_flag_id: u32) -> Result<(usize, u32), MemoryError>
+{
+    let flag_str = format!("{:0>8}", event_flag_id);
+    if flag_str.len() != 8
+    {
+        return Err(MemoryError::InvalidEventFlag(event_flag_id));
+    }
+
+    let group = get_event_flag_group(&flag_str[..1]).ok_or(MemoryError::InvalidEventFlag(event_flag_id))?;
+    let area = get_event_flag_area(&flag_str[1..4]).ok_or(MemoryError::InvalidEventFlag(event_flag_id))?;
+    let section = flag_str[4..5].parse::<usize>().map_err(|_| MemoryError::InvalidEventFlag(event_flag_id))?;
+    let number = flag_str[5..].parse::<usize>().map_err(|_| MemoryError::InvalidEventFlag(event_flag_id))?;
+
+    let mut offset = group;
+    offset += area * 0x500;
+    offset += section * 128;
+    offset += (number - (number % 32)) / 8;
+
+    let mask = 0x80000000 >> (number % 32);
+    return Ok((offset, mask));
+}
+
+///event_flag_man is the pointer the backend scans for, resolving to the start of the flag storage
+pub fn get_event_flag(memory: &dyn MemorySource, event_flag_man: &MemoryPointer, event_flag_id: u32) -> Result<bool, MemoryError>
+{
+    let (offset, mask) = get_event_flag_offset(event_flag_id)?;
+    let value = event_flag_man.read_u32(memory, Some(offset))?;
+    return Ok((value & mask) != 0);
+}
+
+pub fn set_event_flag(memory: &dyn MemorySource, event_flag_man: &MemoryPointer, event_flag_id: u32, state: bool) -> Result<(), MemoryError>
+{
+    let (offset, mask) = get_event_flag_offset(event_flag_id)?;
+    let value = event_flag_man.read_u32(memory, Some(offset))?;
+    let value = if state { value | mask } else { value & !mask };
+    return event_flag_man.write_u32(memory, Some(offset), value);
+}
+
+///Remastered is a 64 bit build with 32 bit wide pointers, event_flag_man resolves to a u32 holding the flag manager's
+///address and the manager starts with a u32 holding the storage's address
+pub fn remastered_storage(memory: &dyn MemorySource, event_flag_man: &MemoryPointer) -> Result<MemoryPointer, MemoryError>
+{
+    let manager = event_flag_man.read_u32(memory, None)? as usize;
+    let storage = memory.read_u32(manager).ok_or(MemoryError::Unreadable(manager))?;
+    return Ok(MemoryPointer::new(storage as usize, Vec::new()));
+}
+
+///Reads the whole flag storage at once and decodes every flag id that maps onto it
+pub fn snapshot(memory: &dyn MemorySource, event_flag_man: &MemoryPointer) -> Result<EventFlagSnapshot, MemoryError>
+{
+    let address = event_flag_man.resolve(memory, Some(0))?;
+    let mut storage = vec![0u8; STORAGE_SIZE];
+    if !memory.read(address, &mut storage)
+    {
+        return Err(MemoryError::Unreadable(address));
+    }
+
+    let mut snapshot = EventFlagSnapshot::new();
+    for (group, group_offset) in GROUPS
+    {
+        //Group 0 is only 0x500 bytes long, the next group starts where its second area would
+        let areas = if group == "0" { &AREAS[..1] } else { &AREAS[..] };
+        for (area_index, area) in areas.iter().enumerate()
+        {
+            let first_flag = format!("{}{}0000", group, area).parse::<u32>().unwrap();
+            let area_offset = group_offset + area_index * 0x500;
+            for (word_index, word) in storage[area_offset..area_offset + 0x500].chunks(4).enumerate()
+            {
+                //32 flags per u32, a section's 128 bytes end with 24 bits past flag 999
+                let (section, number) = (word_index / 32, (word_index % 32) * 32);
+                let value = u32::from_le_bytes(word.try_into().unwrap());
+                for bit in 0..32
+                {
+                    if value & (0x80000000 >> bit) != 0 && number + bit < 1000
+                    {
+                        snapshot.set(first_flag + (section * 1000 + number + bit) as u32, true);
+                    }
+                }
+            }
+        }
+    }
+    return Ok(snapshot);
+}
+
+#[cfg(test)]
+mod tests
+{
+    use soulmemory_patterns::PatternFile;
+    use crate::dump::MemoryDump;
+    use crate::event_flags::dark_souls_1::*;
+
+    #[test]
+    fn event_flag_offsets()
+    {
+        assert_eq!(Ok((0, 0x80000000)), get_event_flag_offset(0));
+        assert_eq!(Ok((0x500 + 0x500 + 16, 0x80000000 >> 8)), get_event_flag_offset(11000136));
+        assert_eq!(Ok((0x11300 + 17 * 0x500 + 9 * 128 + 12, 0x80000000 >> 3)), get_event_flag_offset(71819099));
+        assert_eq!(Err(MemoryError::InvalidEventFlag(11990000)), get_event_flag_offset(11990000));
+        assert_eq!(Err(MemoryError::InvalidEventFlag(31000000)), get_event_flag_offset(31000000));
+        assert_eq!(Err(MemoryError::InvalidEventFlag(100000000)), get_event_flag_offset(100000000));
+    }
+
+    #[test]
+    fn read_and_write_flags()
+    {
+        //The backend's own pattern, its operand points at a global that leads to the flag storage through one object
+        let patterns = PatternFile::parse(include_str!("../../../soulmemory-rs/patterns/DarkSoulsPrepareToDieEdition.toml")).unwrap();
+        let mut module = vec![0u8; 0x1000];
+        module[0x100..0x10e].copy_from_slice(&[0x56, 0x8b, 0xf1, 0x8b, 0x46, 0x1c, 0x50, 0xa1, 0x00, 0x08, 0x40, 0x00, 0x32, 0xc9]);
+        module[0x800..0x804].copy_from_slice(&0x1000000u32.to_le_bytes());
+        let mut dump = MemoryDump::new(false, 0x400000, module);
+        dump.add_region(0x1000000, 0x2000000u32.to_le_bytes().to_vec());
+        let mut flags = vec![0u8; 0x20000];
+        flags[0xa10..0xa14].copy_from_slice(&0x00800000u32.to_le_bytes());
+        dump.add_region(0x2000000, flags);
+
+        let event_flag_man = dump.scan_pointer("event flags", patterns.get("event flags").unwrap()).unwrap();
+        assert!(get_event_flag(&dump, &event_flag_man, 11000136).unwrap());
+        assert!(!get_event_flag(&dump, &event_flag_man, 11000135).unwrap());
+
+        set_event_flag(&dump, &event_flag_man, 11000136, false).unwrap();
+        set_event_flag(&dump, &event_flag_man, 11000135, true).unwrap();
+        assert!(!get_event_flag(&dump, &event_flag_man, 11000136).unwrap());
+        assert!(get_event_flag(&dump, &event_flag_man, 11000135).unwrap());
+        assert!(get_event_flag(&dump, &event_flag_man, 11990000).is_err());
+
+        set_event_flag(&dump, &event_flag_man, 71819099, true).unwrap();
+        set_event_flag(&dump, &event_flag_man, 999, true).unwrap();
+        let snapshot = snapshot(&dump, &event_flag_man).unwrap();
+        assert_eq!(3, snapshot.count());
+        assert!(snapshot.get(999) && snapshot.get(11000135) && snapshot.get(71819099));
+    }
+
+    #[test]
+    fn remastered_flags()
+    {
+        //mov rcx, [rip + 0x100] at 0x10, the global at 0x117 holds a 32 bit pointer to the manager at 0x1000000
+        let patterns = PatternFile::parse(include_str!("../../../soulmemory-rs/patterns/DarkSoulsRemastered.toml")).unwrap();
+        let mut module = vec![0u8; 0x200];
+        module[0x10..0x22].copy_from_slice(&[0x48, 0x8b, 0x0d, 0x00, 0x01, 0x00, 0x00, 0x99, 0x33, 0xc2, 0x45, 0x33, 0xc0, 0x2b, 0xc2, 0x8d, 0x50, 0xf6]);
+        module[0x117..0x11f].copy_from_slice(&0xdead_beef_0100_0000u64.to_le_bytes());
+        let mut dump = MemoryDump::new(true, 0x1_4000_0000, module);
+        dump.add_region(0x1000000, 0x2000000u32.to_le_bytes().to_vec());
+        let mut flags = vec![0u8; 0x20000];
+        flags[0xa10..0xa14].copy_from_slice(&0x00800000u32.to_le_bytes());
+        dump.add_region(0x2000000, flags);
+
+        let event_flag_man = dump.scan_pointer("event flags", patterns.get("event flags").unwrap()).unwrap();
+        let storage = remastered_storage(&dump, &event_flag_man).unwrap();
+        assert_eq!(MemoryPointer::new(0x2000000, Vec::new()), storage);
+        assert!(get_event_flag(&dump, &storage, 11000136).unwrap());
+        set_event_flag(&dump, &storage, 11000135, true).unwrap();
+        assert_eq!(Some(0x01800000), dump.read_u32(0x2000a10));
+
+        dump.write_u32(0x1000000, 0x3000000);
+        assert_eq!(Ok(MemoryPointer::new(0x3000000, Vec::new())), remastered_storage(&dump, &event_flag_man));
+        assert!(get_event_flag(&dump, &remastered_storage(&dump, &event_flag_man).unwrap(), 11000136).is_err());
+        dump.write_u32(0x1_4000_0117, 0x4000000);
+        assert_eq!(Err(MemoryError::Unreadable(0x4000000)), remastered_storage(&dump, &event_flag_man));
+    }
+}
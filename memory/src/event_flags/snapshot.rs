@@ -0,0 +1,145 @@
+// This file is part of the soulmemory-rs distribution (https://github.com/FrankvdStam/soulmemory-rs).
+// Copyright (c) 2022 Frank van der Stam.
+// https://github.com/FrankvdStam/soulmemory-rs/blob/main/LICENSE
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+
+//Every flag in a game's flag storage at one moment. Flag ids are sparse, so the bits are kept in blocks of
+//BLOCK_FLAGS consecutive flags and only blocks that hold a set flag are stored.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+pub const BLOCK_FLAGS: u32 = 1024;
+const BLOCK_WORDS: usize = (BLOCK_FLAGS / 32) as usize;
+
+///A flag that differs between two snapshots, with its state in the later one
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FlagChange
+{
+    pub flag: u32,
+    pub state: bool,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct EventFlagSnapshot
+{
+    blocks: BTreeMap<u32, [u32; BLOCK_WORDS]>,
+}
+
+impl EventFlagSnapshot
+{
+    pub fn new() -> Self
+    {
+        return EventFlagSnapshot::default();
+    }
+
+    ///Block, word within the block and the flag's bit in that word
+    fn position(flag: u32) -> (u32, usize, u32)
+    {
+        let bit = flag % BLOCK_FLAGS;
+        return (flag / BLOCK_FLAGS, (bit / 32) as usize, 1 << (bit % 32));
+    }
+
+    pub fn set(&mut self, flag: u32, state: bool)
+    {
+        let (block, word, mask) = Self::position(flag);
+        if state
+        {
+            self.blocks.entry(block).or_insert([0; BLOCK_WORDS])[word] |= mask;
+        }
+        else if let Some(words) = self.blocks.get_mut(&block)
+        {
+            words[word] &= !mask;
+            if words.iter().all(|w| *w == 0)
+            {
+                self.blocks.remove(&block);
+            }
+        }
+    }
+
+    pub fn get(&self, flag: u32) -> bool
+    {
+        let (block, word, mask) = Self::position(flag);
+        return self.blocks.get(&block).is_some_and(|words| words[word] & mask != 0);
+    }
+
+    ///The number of set flags
+    pub fn count(&self) -> usize
+    {
+        return self.blocks.values().flatten().map(|w| w.count_ones() as usize).sum();
+    }
+
+    ///The stored blocks in ascending order. Bit n of word w in block b is flag b * BLOCK_FLAGS + w * 32 + n.
+    pub fn blocks(&self) -> impl Iterator<Item = (u32, &[u32])>
+    {
+        return self.blocks.iter().map(|(block, words)| (*block, &words[..]));
+    }
+
+    ///Every flag that has a different state in the later snapshot, in ascending order
+    pub fn diff(&self, later: &EventFlagSnapshot) -> Vec<FlagChange>
+    {
+        let empty = [0u32; BLOCK_WORDS];
+        let blocks: BTreeSet<u32> = self.blocks.keys().chain(later.blocks.keys()).copied().collect();
+
+        let mut changes = Vec::new();
+        for block in blocks
+        {
+            let before = self.blocks.get(&block).unwrap_or(&empty);
+            let after = later.blocks.get(&block).unwrap_or(&empty);
+            for word in 0..BLOCK_WORDS
+            {
+                let mut changed = before[word] ^ after[word];
+                while changed != 0
+                {
+                    let bit = changed.trailing_zeros();
+                    changed &= changed - 1;
+                    changes.push(FlagChange { flag: block * BLOCK_FLAGS + word as u32 * 32 + bit, state: after[word] & (1 << bit) != 0 });
+                }
+            }
+        }
+        return changes;
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use crate::event_flags::snapshot::*;
+
+    #[test]
+    fn diff_snapshots()
+    {
+        let mut a = EventFlagSnapshot::new();
+        a.set(0, true);
+        a.set(11010000, true);
+        a.set(11010031, true);
+        a.set(u32::MAX, true);
+        assert!(a.get(11010031) && !a.get(11010032));
+        assert_eq!(4, a.count());
+
+        let mut b = a.clone();
+        b.set(11010031, false);
+        b.set(50000001, true);
+        b.set(u32::MAX, false);
+        assert_eq!(3, b.blocks().count());
+
+        assert_eq!(vec![
+            FlagChange { flag: 11010031, state: false },
+            FlagChange { flag: 50000001, state: true },
+            FlagChange { flag: u32::MAX, state: false },
+        ], a.diff(&b));
+        assert_eq!(vec![FlagChange { flag: 11010031, state: true }], b.diff(&a)[..1].to_vec());
+        assert!(a.diff(&a).is_empty());
+    }
+}
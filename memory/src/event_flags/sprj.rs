@@ -0,0 +1,278 @@
+// This file is part of the soulmemory-rs distribution (https://github.com/FrankvdStam/soulmemory-rs).
+// Copyright (c) 2022 Frank van der Stam.
+// https://github.com/FrankvdStam/soulmemory-rs/blob/main/LICENSE
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+
+//Dark Souls III and Sekiro store flags in a tree indexed by the digits of the flag id: division (1 digit), area (2),
+//block (1), then a bitfield of 1000 flags. Blocks that belong to a world area are numbered by the area's world info,
+//which FieldArea owns, the others use category 0.
+
+use std::collections::HashSet;
+use crate::error::MemoryError;
+use crate::pointer::MemoryPointer;
+use crate::source::MemorySource;
+use crate::event_flags::snapshot::EventFlagSnapshot;
+
+///The parts of the layout that differ between the two games
+pub struct SprjLayout
+{
+    ///Offset of the world info owner in FieldArea
+    pub world_info_owner: usize,
+    ///Size of a world block info entry
+    pub world_block_size: usize,
+}
+
+pub const DARK_SOULS_3: SprjLayout = SprjLayout { world_info_owner: 0x10, world_block_size: 0x70 };
+pub const SEKIRO: SprjLayout = SprjLayout { world_info_owner: 0x18, world_block_size: 0xb0 };
+
+const WORLD_AREA_SIZE: usize = 0x38;
+const DIVISION_SIZE: usize = 0x18;
+const CATEGORY_SIZE: usize = 0xa8;
+
+struct FlagId
+{
+    division: usize,
+    area: usize,
+    block: usize,
+    group: usize,
+    index: usize,
+}
+
+impl FlagId
+{
+    fn new(event_flag_id: u32) -> Self
+    {
+        let id = event_flag_id as usize;
+        return FlagId
+        {
+            division: (id / 10000000) % 10,
+            area: (id / 100000) % 100,
+            block: (id / 10000) % 10,
+            group: (id / 1000) % 10,
+            index: id % 1000,
+        };
+    }
+}
+
+///Visits the world blocks of one area, or of all areas, until f returns true. f gets the area and block digits from
+///the block's id and its category.
+fn visit_world_blocks(memory: &dyn MemorySource, field_area: &MemoryPointer, layout: &SprjLayout, area: Option<usize>, f: &mut dyn FnMut(usize, usize, usize) -> bool) -> Result<bool, MemoryError>
+{
+    let world_info_owner = field_area.read_pointer(memory, Some(layout.world_info_owner))?;
+    let area_count = memory.read_u32(world_info_owner + 0x8).ok_or(MemoryError::Unreadable(world_info_owner + 0x8))? as usize;
+    for i in 0..area_count
+    {
+        let world_area = world_info_owner + 0x10 + i * WORLD_AREA_SIZE;
+        if area.is_some_and(|area| memory.read_u8(world_area + 0xb).map(|a| a as usize) != Some(area))
+        {
+            continue;
+        }
+
+        let block_count = memory.read_u8(world_area + 0x20).ok_or(MemoryError::Unreadable(world_area + 0x20))? as usize;
+        let blocks = memory.read_pointer(world_area + 0x28).ok_or(MemoryError::Unreadable(world_area + 0x28))?;
+        for j in 0..block_count
+        {
+            let block = blocks + j * layout.world_block_size;
+            let id = memory.read_u32(block + 0x8).ok_or(MemoryError::Unreadable(block + 0x8))? as usize;
+            let category = memory.read_u32(block + 0x20).ok_or(MemoryError::Unreadable(block + 0x20))?;
+            if f(id >> 24, (id >> 16) & 0xff, category as usize)
+            {
+                return Ok(true);
+            }
+        }
+    }
+    return Ok(false);
+}
+
+fn get_category(memory: &dyn MemorySource, field_area: &MemoryPointer, layout: &SprjLayout, event_flag_id: u32, flag: &FlagId) -> Result<usize, MemoryError>
+{
+    if flag.area >= 90 || flag.area + flag.block == 0
+    {
+        return Ok(0);
+    }
+
+    let mut category = None;
+    visit_world_blocks(memory, field_area, layout, Some(flag.area), &mut |area, block, c|
+    {
+        if area == flag.area && block == flag.block
+        {
+            category = Some(c + 1);
+        }
+        return category.is_some();
+    })?;
+    return category.ok_or(MemoryError::InvalidEventFlag(event_flag_id));
+}
+
+///The bitfield holding the flag, the offset of the u32 the flag is in and its mask within it
+fn get_flag_word(memory: &dyn MemorySource, event_flag_man: &MemoryPointer, field_area: &MemoryPointer, layout: &SprjLayout, event_flag_id: u32) -> Result<(MemoryPointer, usize, u32), MemoryError>
+{
+    let flag = FlagId::new(event_flag_id);
+    let category = get_category(memory, field_area, layout, event_flag_id, &flag)?;
+    let bits = event_flag_man.append(&[0x218, flag.division * DIVISION_SIZE, 0, flag.group << 4, category * CATEGORY_SIZE]);
+    return Ok((bits, (flag.index >> 5) * 4, 0x80000000 >> (flag.index & 31)));
+}
+
+///event_flag_man resolves to SprjEventFlagMan, field_area to FieldArea
+pub fn get_event_flag(memory: &dyn MemorySource, event_flag_man: &MemoryPointer, field_area: &MemoryPointer, layout: &SprjLayout, event_flag_id: u32) -> Result<bool, MemoryError>
+{
+    let (bits, offset, mask) = get_flag_word(memory, event_flag_man, field_area, layout, event_flag_id)?;
+    let value = bits.read_u32(memory, Some(offset))?;
+    return Ok(value & mask != 0);
+}
+
+pub fn set_event_flag(memory: &dyn MemorySource, event_flag_man: &MemoryPointer, field_area: &MemoryPointer, layout: &SprjLayout, event_flag_id: u32, state: bool) -> Result<(), MemoryError>
+{
+    let (bits, offset, mask) = get_flag_word(memory, event_flag_man, field_area, layout, event_flag_id)?;
+    let value = bits.read_u32(memory, Some(offset))?;
+    let value = if state { value | mask } else { value & !mask };
+    return bits.write_u32(memory, Some(offset), value);
+}
+
+///Decodes every bitfield the flag manager holds. Ids whose digits lead to the same bitfield, like the ones sharing
+///category 0, are reported under the lowest of them.
+pub fn snapshot(memory: &dyn MemorySource, event_flag_man: &MemoryPointer, field_area: &MemoryPointer, layout: &SprjLayout) -> Result<EventFlagSnapshot, MemoryError>
+{
+    //Fails while the flag manager hasn't been allocated yet
+    event_flag_man.append(&[0x218]).resolve(memory, Some(0))?;
+
+    //Area and block digits with their category, in ascending order so the lowest id sees a bitfield first
+    let mut blocks = vec![(0, 0, 0)];
+    blocks.extend((90..100).flat_map(|area| (0..10).map(move |block| (area, block, 0))));
+    visit_world_blocks(memory, field_area, layout, None, &mut |area, block, category|
+    {
+        if area < 90 && area + block != 0 && block < 10
+        {
+            blocks.push((area, block, category + 1));
+        }
+        return false;
+    })?;
+    blocks.sort();
+
+    let mut seen = HashSet::new();
+    let mut snapshot = EventFlagSnapshot::new();
+    for division in 0..10
+    {
+        //Not every division, group and category is allocated. The groups' category arrays are shared by every block,
+        //so they are only looked up once per division.
+        let groups = match event_flag_man.append(&[0x218, division * DIVISION_SIZE, 0]).resolve(memory, Some(0))
+        {
+            Ok(groups) => groups,
+            Err(_) => continue,
+        };
+        let categories: Vec<Option<usize>> = (0..10)
+            .map(|group| groups.checked_add(group << 4).and_then(|address| memory.read_pointer(address)).filter(|c| *c != 0))
+            .collect();
+
+        for &(area, block, category) in &blocks
+        {
+            for (group, category_array) in categories.iter().enumerate()
+            {
+                let bits = match category_array.and_then(|c| c.checked_add(category * CATEGORY_SIZE)).and_then(|address| memory.read_pointer(address))
+                {
+                    Some(0) | None => continue,
+                    Some(bits) => bits,
+                };
+                let mut words = [0u8; 128];
+                if !seen.insert(bits) || !memory.read(bits, &mut words)
+                {
+                    continue;
+                }
+
+                let first_flag = (division * 10000000 + area * 100000 + block * 10000 + group * 1000) as u32;
+                for (word_index, word) in words.chunks(4).enumerate()
+                {
+                    let value = u32::from_le_bytes(word.try_into().unwrap());
+                    for bit in 0..32
+                    {
+                        let index = word_index * 32 + bit;
+                        if index < 1000 && value & (0x80000000 >> bit) != 0
+                        {
+                            snapshot.set(first_flag + index as u32, true);
+                        }
+                    }
+                }
+            }
+        }
+    }
+    return Ok(snapshot);
+}
+
+#[cfg(test)]
+mod tests
+{
+    use crate::dump::MemoryDump;
+    use crate::event_flags::sprj::*;
+
+    #[test]
+    fn read_flags()
+    {
+        let mut dump = MemoryDump::new(true, 0x1_4000_0000, vec![0u8; 0x100]);
+        for base in [0x1000, 0x2000, 0x3000, 0x4000, 0x5000, 0x6000, 0x7000, 0x8000, 0x9000]
+        {
+            dump.add_region(base, vec![0u8; 0x1000]);
+        }
+
+        //SprjEventFlagMan at 0x1000 -> divisions at 0x2000 -> division -> groups -> categories -> bitfields at 0x5000 and 0x6000
+        dump.write_pointer(0x1_4000_0000, 0x1000);
+        dump.write_pointer(0x1000 + 0x218, 0x2000);
+        dump.write_pointer(0x2000 + DIVISION_SIZE, 0x3000);
+        dump.write_pointer(0x3000, 0x4000);
+        dump.write_pointer(0x4000, 0x4800);
+        dump.write_pointer(0x4800 + 3 * CATEGORY_SIZE, 0x5000);
+        dump.write_pointer(0x2000, 0x3100);
+        dump.write_pointer(0x3100, 0x4200);
+        dump.write_pointer(0x4200 + (6 << 4), 0x4900);
+        dump.write_pointer(0x4900, 0x6000);
+
+        //FieldArea at 0x7000 -> world info owner at 0x8000 with 2 areas, area 30 has its block 0 as category 2
+        dump.write_pointer(0x1_4000_0008, 0x7000);
+        dump.write_pointer(0x7000 + DARK_SOULS_3.world_info_owner, 0x8000);
+        dump.write_u32(0x8008, 2);
+        dump.write_u8(0x8010 + 0xb, 31);
+        dump.write_u8(0x8010 + WORLD_AREA_SIZE + 0xb, 30);
+        dump.write_u8(0x8010 + WORLD_AREA_SIZE + 0x20, 2);
+        dump.write_pointer(0x8010 + WORLD_AREA_SIZE + 0x28, 0x9000);
+        dump.write_u32(0x9008, 30 << 24 | 1 << 16);
+        dump.write_u32(0x9008 + DARK_SOULS_3.world_block_size, 30 << 24);
+        dump.write_u32(0x9020 + DARK_SOULS_3.world_block_size, 2);
+
+        //13000800 is bit 31 - 0 of word 25, 6950 bit 31 - 22 of word 29
+        dump.write_u32(0x5000 + 25 * 4, 0x80000000);
+        dump.write_u32(0x6000 + 29 * 4, 1 << 9);
+
+        let event_flag_man = MemoryPointer::new(0x1_4000_0000, vec![0]);
+        let field_area = MemoryPointer::new(0x1_4000_0008, vec![0]);
+        let get = |flag| get_event_flag(&dump, &event_flag_man, &field_area, &DARK_SOULS_3, flag);
+        assert_eq!(Ok(true), get(13000800));
+        assert_eq!(Ok(false), get(13000801));
+        assert_eq!(Ok(true), get(6950));
+        assert_eq!(Ok(false), get(6951));
+        assert_eq!(Err(MemoryError::InvalidEventFlag(13200800)), get(13200800));
+        assert!(get_event_flag(&dump, &event_flag_man, &field_area, &SEKIRO, 13000800).is_err());
+
+        set_event_flag(&dump, &event_flag_man, &field_area, &DARK_SOULS_3, 13000801, true).unwrap();
+        set_event_flag(&dump, &event_flag_man, &field_area, &DARK_SOULS_3, 13000801, false).unwrap();
+        set_event_flag(&dump, &event_flag_man, &field_area, &DARK_SOULS_3, 6951, true).unwrap();
+        set_event_flag(&dump, &event_flag_man, &field_area, &DARK_SOULS_3, 6951, false).unwrap();
+        assert_eq!(Ok(false), get(13000801));
+        assert_eq!(Ok(true), get(13000800));
+        assert_eq!(Err(MemoryError::InvalidEventFlag(13200800)), set_event_flag(&dump, &event_flag_man, &field_area, &DARK_SOULS_3, 13200800, true));
+
+        //The category 0 bitfield of group 6 is shared by 6950 and the 9x areas, only the lowest id reports it
+        let snapshot = snapshot(&dump, &event_flag_man, &field_area, &DARK_SOULS_3).unwrap();
+        assert_eq!(2, snapshot.count());
+        assert!(snapshot.get(13000800) && snapshot.get(6950));
+    }
+}
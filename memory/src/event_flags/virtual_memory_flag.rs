@@ -0,0 +1,256 @@
+// This file is part of the soulmemory-rs distribution (https://github.com/FrankvdStam/soulmemory-rs).
+// Copyright (c) 2022 Frank van der Stam.
+// https://github.com/FrankvdStam/soulmemory-rs/blob/main/LICENSE
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+
+//Elden Ring's CSFD4VirtualMemoryFlag, Armored Core VI uses the same. A flag id is split into a category, id / divisor,
+//and an index into that category's bitfield. The categories are kept in a red-black tree (an msvc std::map) keyed by category.
+
+use crate::error::MemoryError;
+use crate::pointer::MemoryPointer;
+use crate::source::MemorySource;
+use crate::event_flags::snapshot::EventFlagSnapshot;
+
+///Deeper than any balanced tree of 32 bit keys, only a corrupt tree gets here
+const MAX_DEPTH: usize = 128;
+
+///Flags per category, both games use 1000. Anything far beyond that is a stale pointer, not a bitfield to allocate.
+const MAX_DIVISOR: u32 = 100000;
+
+fn read_u32(memory: &dyn MemorySource, address: usize) -> Result<u32, MemoryError>
+{
+    return memory.read_u32(address).ok_or(MemoryError::Unreadable(address));
+}
+
+fn read_pointer(memory: &dyn MemorySource, address: usize) -> Result<usize, MemoryError>
+{
+    return memory.read_pointer(address).ok_or(MemoryError::Unreadable(address));
+}
+
+///The number of flags per category
+fn read_divisor(memory: &dyn MemorySource, virtual_memory_flag: &MemoryPointer) -> Result<u32, MemoryError>
+{
+    let divisor = virtual_memory_flag.read_u32(memory, Some(0x1c))?;
+    if divisor == 0 || divisor > MAX_DIVISOR
+    {
+        return Err(MemoryError::Unreadable(virtual_memory_flag.resolve(memory, Some(0x1c))?));
+    }
+    return Ok(divisor);
+}
+
+///The tree node for the category, a lower bound search from the root
+fn find_category(memory: &dyn MemorySource, virtual_memory_flag: &MemoryPointer, category: u32) -> Result<Option<usize>, MemoryError>
+{
+    let head = virtual_memory_flag.read_pointer(memory, Some(0x38))?;
+    let mut result = head;
+    let mut node = read_pointer(memory, head + 0x8)?;
+    for _ in 0..MAX_DEPTH
+    {
+        if memory.read_u8(node + 0x19).ok_or(MemoryError::Unreadable(node + 0x19))? != 0
+        {
+            if result == head || category < read_u32(memory, result + 0x20)?
+            {
+                return Ok(None);
+            }
+            return Ok(Some(result));
+        }
+
+        if read_u32(memory, node + 0x20)? < category
+        {
+            node = read_pointer(memory, node + 0x10)?;
+        }
+        else
+        {
+            result = node;
+            node = read_pointer(memory, node)?;
+        }
+    }
+    return Err(MemoryError::Unreadable(node));
+}
+
+///The bitfield is either one of the equally sized blocks the flag manager allocated, not allocated yet, or its own allocation
+fn get_bitfield(memory: &dyn MemorySource, virtual_memory_flag: &MemoryPointer, node: usize) -> Result<Option<usize>, MemoryError>
+{
+    let bits = match read_u32(memory, node + 0x28)?
+    {
+        1 =>
+        {
+            let block_size = virtual_memory_flag.read_u32(memory, Some(0x20))? as usize;
+            let blocks = virtual_memory_flag.read_pointer(memory, Some(0x28))?;
+            blocks + block_size * read_u32(memory, node + 0x30)? as usize
+        }
+        2 => 0,
+        _ => read_pointer(memory, node + 0x30)?,
+    };
+    return Ok(if bits == 0 { None } else { Some(bits) });
+}
+
+///The tree's nodes in key order
+fn collect_nodes(memory: &dyn MemorySource, node: usize, depth: usize, nodes: &mut Vec<usize>) -> Result<(), MemoryError>
+{
+    if depth > MAX_DEPTH
+    {
+        return Err(MemoryError::Unreadable(node));
+    }
+    if memory.read_u8(node + 0x19).ok_or(MemoryError::Unreadable(node + 0x19))? != 0
+    {
+        return Ok(());
+    }
+
+    collect_nodes(memory, read_pointer(memory, node)?, depth + 1, nodes)?;
+    nodes.push(node);
+    return collect_nodes(memory, read_pointer(memory, node + 0x10)?, depth + 1, nodes);
+}
+
+///The tree node of the flag's category and the flag's index in the category's bitfield
+fn find_flag(memory: &dyn MemorySource, virtual_memory_flag: &MemoryPointer, event_flag_id: u32) -> Result<(usize, usize), MemoryError>
+{
+    let divisor = read_divisor(memory, virtual_memory_flag)?;
+    let category = event_flag_id / divisor;
+    let node = find_category(memory, virtual_memory_flag, category)?.ok_or(MemoryError::InvalidEventFlag(event_flag_id))?;
+    return Ok((node, (event_flag_id % divisor) as usize));
+}
+
+///virtual_memory_flag resolves to CSFD4VirtualMemoryFlag or AC6's CSEventFlagMan
+pub fn get_event_flag(memory: &dyn MemorySource, virtual_memory_flag: &MemoryPointer, event_flag_id: u32) -> Result<bool, MemoryError>
+{
+    let (node, index) = find_flag(memory, virtual_memory_flag, event_flag_id)?;
+    let bits = match get_bitfield(memory, virtual_memory_flag, node)?
+    {
+        Some(bits) => bits,
+        None => return Ok(false),
+    };
+
+    let address = bits + (index >> 3);
+    let value = memory.read_u8(address).ok_or(MemoryError::Unreadable(address))?;
+    return Ok(value & (1 << (7 - (index & 7))) != 0);
+}
+
+///Fails for a category whose bitfield the game hasn't allocated yet
+pub fn set_event_flag(memory: &dyn MemorySource, virtual_memory_flag: &MemoryPointer, event_flag_id: u32, state: bool) -> Result<(), MemoryError>
+{
+    let (node, index) = find_flag(memory, virtual_memory_flag, event_flag_id)?;
+    let bits = get_bitfield(memory, virtual_memory_flag, node)?.ok_or(MemoryError::Unreadable(node + 0x30))?;
+
+    let address = bits + (index >> 3);
+    let mask = 1 << (7 - (index & 7));
+    let value = memory.read_u8(address).ok_or(MemoryError::Unreadable(address))?;
+    let value = if state { value | mask } else { value & !mask };
+    return if memory.write_u8(address, value) { Ok(()) } else { Err(MemoryError::Unreadable(address)) };
+}
+
+///Decodes the bitfield of every category in the tree
+pub fn snapshot(memory: &dyn MemorySource, virtual_memory_flag: &MemoryPointer) -> Result<EventFlagSnapshot, MemoryError>
+{
+    let divisor = read_divisor(memory, virtual_memory_flag)?;
+
+    let head = virtual_memory_flag.read_pointer(memory, Some(0x38))?;
+    let mut nodes = Vec::new();
+    collect_nodes(memory, read_pointer(memory, head + 0x8)?, 0, &mut nodes)?;
+
+    let mut snapshot = EventFlagSnapshot::new();
+    let mut bytes = vec![0u8; (divisor as usize).div_ceil(8)];
+    for node in nodes
+    {
+        let bits = match get_bitfield(memory, virtual_memory_flag, node)?
+        {
+            Some(bits) => bits,
+            None => continue,
+        };
+        if !memory.read(bits, &mut bytes)
+        {
+            return Err(MemoryError::Unreadable(bits));
+        }
+
+        let first_flag = read_u32(memory, node + 0x20)?.wrapping_mul(divisor);
+        for index in 0..divisor
+        {
+            if bytes[(index >> 3) as usize] & (1 << (7 - (index & 7))) != 0
+            {
+                snapshot.set(first_flag.wrapping_add(index), true);
+            }
+        }
+    }
+    return Ok(snapshot);
+}
+
+#[cfg(test)]
+mod tests
+{
+    use crate::dump::MemoryDump;
+    use crate::event_flags::virtual_memory_flag::*;
+
+    fn write_node(dump: &MemoryDump, node: usize, left: usize, right: usize, category: u32, kind: u32, value: usize)
+    {
+        dump.write_pointer(node, left);
+        dump.write_pointer(node + 0x10, right);
+        dump.write_u32(node + 0x20, category);
+        dump.write_u32(node + 0x28, kind);
+        dump.write_pointer(node + 0x30, value);
+    }
+
+    #[test]
+    fn read_flags()
+    {
+        let mut dump = MemoryDump::new(true, 0x1_4000_0000, vec![0u8; 0x100]);
+        dump.add_region(0x1000, vec![0u8; 0x1000]);
+        dump.add_region(0x10000, vec![0u8; 0x1000]);
+
+        //The flag manager at 0x1000 with 0x100 byte blocks at 0x10000, the tree's head at 0x1100 and nodes after it
+        let (head, root, left, right) = (0x1100, 0x1200, 0x1300, 0x1400);
+        dump.write_pointer(0x1_4000_0000, 0x1000);
+        dump.write_u32(0x1000 + 0x1c, 1000);
+        dump.write_u32(0x1000 + 0x20, 0x100);
+        dump.write_pointer(0x1000 + 0x28, 0x10000);
+        dump.write_pointer(0x1000 + 0x38, head);
+        dump.write_pointer(head + 0x8, root);
+        dump.write_u8(head + 0x19, 1);
+        write_node(&dump, root, left, right, 1000, 1, 2);
+        write_node(&dump, left, head, head, 10, 0, 0x10800);
+        write_node(&dump, right, head, head, 4000, 2, 0);
+
+        //1000123 is in the block at index 2, 10459 in its own allocation
+        dump.write_u8(0x10200 + 123 / 8, 1 << (7 - 123 % 8));
+        dump.write_u8(0x10800 + 459 / 8, 1 << (7 - 459 % 8));
+
+        let virtual_memory_flag = MemoryPointer::new(0x1_4000_0000, vec![0]);
+        let get = |flag| get_event_flag(&dump, &virtual_memory_flag, flag);
+        assert_eq!(Ok(true), get(1000123));
+        assert_eq!(Ok(false), get(1000124));
+        assert_eq!(Ok(true), get(10459));
+        assert_eq!(Ok(false), get(10460));
+        assert_eq!(Ok(false), get(4000001));
+        assert_eq!(Err(MemoryError::InvalidEventFlag(2000000)), get(2000000));
+        assert_eq!(Err(MemoryError::InvalidEventFlag(5)), get(5));
+
+        set_event_flag(&dump, &virtual_memory_flag, 1000124, true).unwrap();
+        set_event_flag(&dump, &virtual_memory_flag, 10459, false).unwrap();
+        assert_eq!(Ok(true), get(1000124));
+        assert_eq!(Ok(true), get(1000123));
+        assert_eq!(Ok(false), get(10459));
+        set_event_flag(&dump, &virtual_memory_flag, 1000124, false).unwrap();
+        set_event_flag(&dump, &virtual_memory_flag, 10459, true).unwrap();
+        assert!(set_event_flag(&dump, &virtual_memory_flag, 4000001, true).is_err());
+
+        let snapshot = snapshot(&dump, &virtual_memory_flag).unwrap();
+        assert_eq!(2, snapshot.count());
+        assert!(snapshot.get(1000123) && snapshot.get(10459));
+
+        //A garbage divisor isn't used to size the bitfield
+        dump.write_u32(0x1000 + 0x1c, u32::MAX);
+        assert_eq!(Err(MemoryError::Unreadable(0x1000 + 0x1c)), crate::event_flags::virtual_memory_flag::snapshot(&dump, &virtual_memory_flag).map(|s| s.count()));
+        assert_eq!(Err(MemoryError::Unreadable(0x1000 + 0x1c)), get(1000123));
+    }
+}
@@ -0,0 +1,33 @@
+// This file is part of the soulmemory-rs distribution (https://github.com/FrankvdStam/soulmemory-rs).
+// Copyright (c) 2022 Frank van der Stam.
+// https://github.com/FrankvdStam/soulmemory-rs/blob/main/LICENSE
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+
+#![allow(clippy::needless_return)]
+
+//! Access to a game's memory that doesn't care where the memory comes from. The dll reads the live process,
+//! tests and tools read a dump of it, so scans, pointer chains and flag decoding can be checked on any platform.
+
+pub mod error;
+pub mod source;
+pub mod pointer;
+pub mod dump;
+mod minidump;
+pub mod event_flags;
+
+pub use error::MemoryError;
+pub use source::MemorySource;
+pub use pointer::MemoryPointer;
+pub use dump::MemoryDump;
@@ -0,0 +1,166 @@
+// This file is part of the soulmemory-rs distribution (https://github.com/FrankvdStam/soulmemory-rs).
+// Copyright (c) 2022 Frank van der Stam.
+// https://github.com/FrankvdStam/soulmemory-rs/blob/main/LICENSE
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+
+//Just enough of the minidump format to get at the memory, https://learn.microsoft.com/en-us/windows/win32/api/minidumpapiset/
+
+use crate::dump::Region;
+
+pub const SIGNATURE: &[u8] = b"MDMP";
+
+const MODULE_LIST_STREAM: u32 = 4;
+const MEMORY_LIST_STREAM: u32 = 5;
+const SYSTEM_INFO_STREAM: u32 = 7;
+const MEMORY64_LIST_STREAM: u32 = 9;
+
+const PROCESSOR_ARCHITECTURE_AMD64: u16 = 9;
+
+pub struct Minidump
+{
+    pub is_64_bit: bool,
+    pub module_base: usize,
+    pub module_size: usize,
+    pub regions: Vec<Region>,
+}
+
+fn read_u16(bytes: &[u8], offset: usize) -> Result<u16, String>
+{
+    return bytes.get(offset..offset + 2).map(|b| u16::from_le_bytes([b[0], b[1]])).ok_or(format!("truncated at 0x{:x}", offset));
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> Result<u32, String>
+{
+    return bytes.get(offset..offset + 4).map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]])).ok_or(format!("truncated at 0x{:x}", offset));
+}
+
+fn read_u64(bytes: &[u8], offset: usize) -> Result<u64, String>
+{
+    return Ok(read_u32(bytes, offset)? as u64 | (read_u32(bytes, offset + 4)? as u64) << 32);
+}
+
+fn read_bytes(bytes: &[u8], offset: usize, size: usize) -> Result<Vec<u8>, String>
+{
+    return bytes.get(offset..offset + size).map(|b| b.to_vec()).ok_or(format!("truncated at 0x{:x}", offset));
+}
+
+///The main module is the first one in the module list, memory comes from either memory list
+pub fn parse(bytes: &[u8]) -> Result<Minidump, String>
+{
+    let stream_count = read_u32(bytes, 8)? as usize;
+    let directory = read_u32(bytes, 12)? as usize;
+
+    let mut dump = Minidump { is_64_bit: true, module_base: 0, module_size: 0, regions: Vec::new() };
+    let mut has_module = false;
+    for i in 0..stream_count
+    {
+        let entry = directory + i * 12;
+        let stream = read_u32(bytes, entry + 8)? as usize;
+        match read_u32(bytes, entry)?
+        {
+            MODULE_LIST_STREAM if read_u32(bytes, stream)? > 0 =>
+            {
+                dump.module_base = read_u64(bytes, stream + 4)? as usize;
+                dump.module_size = read_u32(bytes, stream + 4 + 8)? as usize;
+                has_module = true;
+            }
+            MEMORY_LIST_STREAM =>
+            {
+                for range in 0..read_u32(bytes, stream)? as usize
+                {
+                    let descriptor = stream + 4 + range * 16;
+                    let size = read_u32(bytes, descriptor + 8)? as usize;
+                    let rva = read_u32(bytes, descriptor + 12)? as usize;
+                    dump.regions.push(Region { base: read_u64(bytes, descriptor)? as usize, bytes: read_bytes(bytes, rva, size)? });
+                }
+            }
+            MEMORY64_LIST_STREAM =>
+            {
+                let mut rva = read_u64(bytes, stream + 8)? as usize;
+                for range in 0..read_u64(bytes, stream)? as usize
+                {
+                    let descriptor = stream + 16 + range * 16;
+                    let size = read_u64(bytes, descriptor + 8)? as usize;
+                    dump.regions.push(Region { base: read_u64(bytes, descriptor)? as usize, bytes: read_bytes(bytes, rva, size)? });
+                    rva += size;
+                }
+            }
+            SYSTEM_INFO_STREAM => dump.is_64_bit = read_u16(bytes, stream)? == PROCESSOR_ARCHITECTURE_AMD64,
+            _ => {}
+        }
+    }
+
+    if !has_module
+    {
+        return Err(String::from("minidump has no module list"));
+    }
+    return Ok(dump);
+}
+
+#[cfg(test)]
+mod tests
+{
+    use crate::dump::MemoryDump;
+    use crate::minidump::*;
+    use crate::source::MemorySource;
+
+    const MODULE_SIZE: usize = 108;
+
+    fn push_u32(bytes: &mut Vec<u8>, value: u32) { bytes.extend_from_slice(&value.to_le_bytes()); }
+    fn push_u64(bytes: &mut Vec<u8>, value: u64) { bytes.extend_from_slice(&value.to_le_bytes()); }
+
+    #[test]
+    fn parse_minidump()
+    {
+        //Header, 3 directory entries, system info, module list, memory64 list and the memory itself
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(SIGNATURE);
+        for value in [0xa793, 3, 32, 0, 0, 0, 0] { push_u32(&mut bytes, value); }
+
+        let system_info = 32 + 3 * 12;
+        let module_list = system_info + 4;
+        let memory_list = module_list + 4 + MODULE_SIZE;
+        let memory = memory_list + 16 + 2 * 16;
+        for (stream, rva) in [(SYSTEM_INFO_STREAM, system_info), (MODULE_LIST_STREAM, module_list), (MEMORY64_LIST_STREAM, memory_list)]
+        {
+            push_u32(&mut bytes, stream);
+            push_u32(&mut bytes, 0);
+            push_u32(&mut bytes, rva as u32);
+        }
+
+        push_u32(&mut bytes, PROCESSOR_ARCHITECTURE_AMD64 as u32);
+        push_u32(&mut bytes, 1);
+        push_u64(&mut bytes, 0x1_4000_0000);
+        push_u32(&mut bytes, 0x20);
+        bytes.extend_from_slice(&[0u8; MODULE_SIZE - 12]);
+
+        push_u64(&mut bytes, 2);
+        push_u64(&mut bytes, memory as u64);
+        for (base, size) in [(0x1_4000_0000u64, 0x20u64), (0x7ff0_0000_0000, 0x8)] { push_u64(&mut bytes, base); push_u64(&mut bytes, size); }
+        assert_eq!(memory, bytes.len());
+        bytes.extend_from_slice(&[0x90; 0x1c]);
+        bytes.extend_from_slice(&[0xc3, 0xc3, 0xc3, 0xc3]);
+        push_u64(&mut bytes, 0xdead_beef);
+
+        let dump = MemoryDump::parse(bytes).unwrap();
+        assert!(dump.is_64_bit());
+        assert_eq!(0x1_4000_0000, dump.module_base());
+        assert_eq!(0x20, dump.module().len());
+        assert_eq!(Some(0xc3c3c3c3), dump.read_u32(0x1_4000_001c));
+        assert_eq!(Some(0xdead_beef), dump.read_u64(0x7ff0_0000_0000));
+        assert!(MemoryDump::parse(b"MDMP\0\0".to_vec()).is_err());
+        assert!(MemoryDump::parse(b"not a dump".to_vec()).is_err());
+    }
+}
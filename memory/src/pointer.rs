@@ -0,0 +1,90 @@
+// This file is part of the soulmemory-rs distribution (https://github.com/FrankvdStam/soulmemory-rs).
+// Copyright (c) 2022 Frank van der Stam.
+// https://github.com/FrankvdStam/soulmemory-rs/blob/main/LICENSE
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+
+use crate::error::MemoryError;
+use crate::source::MemorySource;
+
+///A base address and a chain of offsets, resolved on every access the same way mem-rs pointers are:
+///every offset but the last is dereferenced, the last one is added.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MemoryPointer
+{
+    pub base: usize,
+    pub chain: Vec<usize>,
+}
+
+impl MemoryPointer
+{
+    pub fn new(base: usize, chain: Vec<usize>) -> Self
+    {
+        return MemoryPointer { base, chain };
+    }
+
+    ///The same pointer, followed further
+    pub fn append(&self, offsets: &[usize]) -> MemoryPointer
+    {
+        let mut chain = self.chain.clone();
+        chain.extend_from_slice(offsets);
+        return MemoryPointer::new(self.base, chain);
+    }
+
+    pub fn resolve(&self, source: &dyn MemorySource, offset: Option<usize>) -> Result<usize, MemoryError>
+    {
+        let mut chain = self.chain.clone();
+        chain.extend(offset);
+
+        let mut address = self.base;
+        for (i, offset) in chain.iter().enumerate()
+        {
+            address = address.checked_add(*offset).ok_or(MemoryError::Unreadable(address))?;
+            if i + 1 < chain.len()
+            {
+                address = match source.read_pointer(address)
+                {
+                    Some(0) | None => return Err(MemoryError::Unreadable(address)),
+                    Some(pointer) => pointer,
+                };
+            }
+        }
+        return Ok(address);
+    }
+
+    pub fn read_u8(&self, source: &dyn MemorySource, offset: Option<usize>) -> Result<u8, MemoryError>
+    {
+        let address = self.resolve(source, offset)?;
+        return source.read_u8(address).ok_or(MemoryError::Unreadable(address));
+    }
+
+    pub fn read_u32(&self, source: &dyn MemorySource, offset: Option<usize>) -> Result<u32, MemoryError>
+    {
+        let address = self.resolve(source, offset)?;
+        return source.read_u32(address).ok_or(MemoryError::Unreadable(address));
+    }
+
+    ///Reads the pointer stored at the resolved address
+    pub fn read_pointer(&self, source: &dyn MemorySource, offset: Option<usize>) -> Result<usize, MemoryError>
+    {
+        let address = self.resolve(source, offset)?;
+        return source.read_pointer(address).ok_or(MemoryError::Unreadable(address));
+    }
+
+    pub fn write_u32(&self, source: &dyn MemorySource, offset: Option<usize>, value: u32) -> Result<(), MemoryError>
+    {
+        let address = self.resolve(source, offset)?;
+        return if source.write_u32(address, value) { Ok(()) } else { Err(MemoryError::Unreadable(address)) };
+    }
+}
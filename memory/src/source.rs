@@ -0,0 +1,68 @@
+// This file is part of the soulmemory-rs distribution (https://github.com/FrankvdStam/soulmemory-rs).
+// Copyright (c) 2022 Frank van der Stam.
+// https://github.com/FrankvdStam/soulmemory-rs/blob/main/LICENSE
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+
+use soulmemory_patterns::Symbol;
+use crate::pointer::MemoryPointer;
+
+///A process's memory: the live game or a dump of it. Addresses are absolute.
+pub trait MemorySource
+{
+    fn is_64_bit(&self) -> bool;
+    fn read(&self, address: usize, buffer: &mut [u8]) -> bool;
+    fn write(&self, address: usize, buffer: &[u8]) -> bool;
+    ///Scans the main module for the symbol, returns the match plus offset or the target of the relative instruction
+    fn scan(&self, name: &str, symbol: &Symbol) -> Result<usize, String>;
+
+    fn scan_pointer(&self, name: &str, symbol: &Symbol) -> Result<MemoryPointer, String>
+    {
+        return Ok(MemoryPointer::new(self.scan(name, symbol)?, symbol.chain.clone()));
+    }
+
+    fn read_u8(&self, address: usize) -> Option<u8>
+    {
+        let mut buffer = [0u8; 1];
+        return if self.read(address, &mut buffer) { Some(buffer[0]) } else { None };
+    }
+
+    fn read_u32(&self, address: usize) -> Option<u32>
+    {
+        let mut buffer = [0u8; 4];
+        return if self.read(address, &mut buffer) { Some(u32::from_le_bytes(buffer)) } else { None };
+    }
+
+    fn read_u64(&self, address: usize) -> Option<u64>
+    {
+        let mut buffer = [0u8; 8];
+        return if self.read(address, &mut buffer) { Some(u64::from_le_bytes(buffer)) } else { None };
+    }
+
+    ///Reads a pointer the size of the game's pointers
+    fn read_pointer(&self, address: usize) -> Option<usize>
+    {
+        return if self.is_64_bit() { self.read_u64(address).map(|p| p as usize) } else { self.read_u32(address).map(|p| p as usize) };
+    }
+
+    fn write_u8(&self, address: usize, value: u8) -> bool
+    {
+        return self.write(address, &[value]);
+    }
+
+    fn write_u32(&self, address: usize, value: u32) -> bool
+    {
+        return self.write(address, &value.to_le_bytes());
+    }
+}
@@ -0,0 +1,52 @@
+// This file is part of the soulmemory-rs distribution (https://github.com/FrankvdStam/soulmemory-rs).
+// Copyright (c) 2022 Frank van der Stam.
+// https://github.com/FrankvdStam/soulmemory-rs/blob/main/LICENSE
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+
+#![allow(clippy::needless_return)]
+
+//! Pattern definitions for the game backends. Kept free of windows dependencies so the data files can be validated,
+//! and tested against module dumps, on any platform.
+
+pub mod pattern;
+pub mod symbol;
+
+pub use pattern::Pattern;
+pub use symbol::{PatternFile, Resolution, Symbol};
+
+#[cfg(test)]
+mod tests
+{
+    use std::fs;
+    use std::path::Path;
+    use crate::PatternFile;
+
+    #[test]
+    fn shipped_pattern_files()
+    {
+        let directory = Path::new(env!("CARGO_MANIFEST_DIR")).join("../soulmemory-rs/patterns");
+        let mut count = 0;
+        for entry in fs::read_dir(directory).unwrap()
+        {
+            let path = entry.unwrap().path();
+            if path.extension().map(|e| e == "toml").unwrap_or(false)
+            {
+                PatternFile::load(&path).unwrap();
+                count += 1;
+            }
+        }
+        assert_eq!(8, count);
+    }
+}
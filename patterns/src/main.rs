@@ -0,0 +1,157 @@
+// This file is part of the soulmemory-rs distribution (https://github.com/FrankvdStam/soulmemory-rs).
+// Copyright (c) 2022 Frank van der Stam.
+// https://github.com/FrankvdStam/soulmemory-rs/blob/main/LICENSE
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+
+#![allow(clippy::needless_return)]
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use soulmemory_patterns::PatternFile;
+
+const USAGE: &str = "usage: pattern-check [--dump <module dump>] <pattern file or directory>...
+
+Validates pattern files. With a dump of the game's main module, as it is laid out in memory, every symbol is
+scanned for and reported as an offset from the start of the module.";
+
+fn pattern_files(path: &Path) -> Result<Vec<PathBuf>, String>
+{
+    if !path.is_dir()
+    {
+        return Ok(vec![path.to_path_buf()]);
+    }
+
+    let mut files = Vec::new();
+    for entry in fs::read_dir(path).map_err(|e| format!("failed to read {}: {}", path.display(), e))?
+    {
+        let file = entry.map_err(|e| e.to_string())?.path();
+        if file.extension().map(|e| e == "toml").unwrap_or(false)
+        {
+            files.push(file);
+        }
+    }
+    files.sort();
+    return Ok(files);
+}
+
+///Prints each symbol's location in the dump, returns false when one of them doesn't match
+fn check_dump(patterns: &PatternFile, dump: &[u8]) -> bool
+{
+    let mut success = true;
+    for (name, symbol) in &patterns.symbols
+    {
+        match symbol.resolve(dump)
+        {
+            Ok(resolution) if resolution.matches.len() > 1 =>
+            {
+                println!("  {}: 0x{:x}, warning: {} matches, the first one is used", name, resolution.address, resolution.matches.len());
+            }
+            Ok(resolution) => println!("  {}: 0x{:x}", name, resolution.address),
+            Err(error) =>
+            {
+                println!("  {}: {}", name, error);
+                success = false;
+            }
+        }
+    }
+    return success;
+}
+
+fn main() -> ExitCode
+{
+    let mut dump_path = None;
+    let mut paths = Vec::new();
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next()
+    {
+        match arg.as_str()
+        {
+            "--dump" => match args.next()
+            {
+                Some(path) => dump_path = Some(PathBuf::from(path)),
+                None =>
+                {
+                    eprintln!("{}", USAGE);
+                    return ExitCode::FAILURE;
+                }
+            },
+            "-h" | "--help" =>
+            {
+                println!("{}", USAGE);
+                return ExitCode::SUCCESS;
+            }
+            _ => paths.push(PathBuf::from(arg)),
+        }
+    }
+
+    if paths.is_empty()
+    {
+        eprintln!("{}", USAGE);
+        return ExitCode::FAILURE;
+    }
+
+    let dump = match &dump_path
+    {
+        Some(path) => match fs::read(path)
+        {
+            Ok(dump) => Some(dump),
+            Err(error) =>
+            {
+                eprintln!("failed to read {}: {}", path.display(), error);
+                return ExitCode::FAILURE;
+            }
+        },
+        None => None,
+    };
+
+    let mut success = true;
+    for path in &paths
+    {
+        let files = match pattern_files(path)
+        {
+            Ok(files) => files,
+            Err(error) =>
+            {
+                eprintln!("{}", error);
+                success = false;
+                continue;
+            }
+        };
+
+        for file in files
+        {
+            match PatternFile::load(&file)
+            {
+                Ok(patterns) =>
+                {
+                    println!("{}: {} symbols", file.display(), patterns.symbols.len());
+                    if let Some(dump) = &dump
+                    {
+                        success &= check_dump(&patterns, dump);
+                    }
+                }
+                Err(error) =>
+                {
+                    println!("{}", error);
+                    success = false;
+                }
+            }
+        }
+    }
+
+    return if success { ExitCode::SUCCESS } else { ExitCode::FAILURE };
+}
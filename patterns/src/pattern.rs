@@ -0,0 +1,154 @@
+// This file is part of the soulmemory-rs distribution (https://github.com/FrankvdStam/soulmemory-rs).
+// Copyright (c) 2022 Frank van der Stam.
+// https://github.com/FrankvdStam/soulmemory-rs/blob/main/LICENSE
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+
+use std::fmt;
+use std::fmt::Display;
+use serde::{Deserialize, Serialize};
+
+///An array of bytes signature. Written as space separated hex bytes, ? or ?? marks a byte that may be anything:
+///"48 8b 0d ? ? ? ? 48 89 5c 24 50"
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Pattern
+{
+    bytes: Vec<Option<u8>>,
+}
+
+impl Pattern
+{
+    pub fn parse(text: &str) -> Result<Pattern, String>
+    {
+        let mut bytes = Vec::new();
+        for (index, token) in text.split_whitespace().enumerate()
+        {
+            if token == "?" || token == "??"
+            {
+                bytes.push(None);
+            }
+            else if token.len() == 2 && token.chars().all(|c| c.is_ascii_hexdigit())
+            {
+                bytes.push(Some(u8::from_str_radix(token, 16).unwrap()));
+            }
+            else
+            {
+                return Err(format!("invalid byte \"{}\" at position {}", token, index));
+            }
+        }
+
+        if bytes.is_empty()
+        {
+            return Err(String::from("empty pattern"));
+        }
+        if bytes.iter().all(|b| b.is_none())
+        {
+            return Err(String::from("pattern only contains wildcards"));
+        }
+        return Ok(Pattern { bytes });
+    }
+
+    #[allow(clippy::len_without_is_empty)]
+    pub fn len(&self) -> usize
+    {
+        return self.bytes.len();
+    }
+
+    pub fn is_wildcard(&self, index: usize) -> bool
+    {
+        return self.bytes.get(index).map(|b| b.is_none()).unwrap_or(false);
+    }
+
+    pub fn matches_at(&self, haystack: &[u8], position: usize) -> bool
+    {
+        return match haystack.get(position..position + self.bytes.len())
+        {
+            Some(window) => window.iter().zip(&self.bytes).all(|(h, b)| b.map(|b| b == *h).unwrap_or(true)),
+            None => false,
+        };
+    }
+
+    ///Every offset in the haystack the pattern matches at
+    pub fn find_all(&self, haystack: &[u8]) -> Vec<usize>
+    {
+        if haystack.len() < self.bytes.len()
+        {
+            return Vec::new();
+        }
+        return (0..=haystack.len() - self.bytes.len()).filter(|p| self.matches_at(haystack, *p)).collect();
+    }
+}
+
+///Formats the pattern the way mem-rs parses it: single spaces, lowercase hex and ? for wildcards
+impl Display for Pattern
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        let tokens: Vec<String> = self.bytes.iter().map(|b| match b
+        {
+            Some(byte) => format!("{:02x}", byte),
+            None => String::from("?"),
+        }).collect();
+        write!(f, "{}", tokens.join(" "))
+    }
+}
+
+impl TryFrom<String> for Pattern
+{
+    type Error = String;
+
+    fn try_from(text: String) -> Result<Self, Self::Error>
+    {
+        return Pattern::parse(&text);
+    }
+}
+
+impl From<Pattern> for String
+{
+    fn from(pattern: Pattern) -> Self
+    {
+        return pattern.to_string();
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use crate::pattern::Pattern;
+
+    #[test]
+    fn parse_patterns()
+    {
+        let pattern = Pattern::parse("48 8B 0d ?  ?? ? ? 48").unwrap();
+        assert_eq!(8, pattern.len());
+        assert!(pattern.is_wildcard(3) && pattern.is_wildcard(4) && !pattern.is_wildcard(7) && !pattern.is_wildcard(8));
+        assert_eq!("48 8b 0d ? ? ? ? 48", pattern.to_string());
+
+        assert!(Pattern::parse("").is_err());
+        assert!(Pattern::parse("? ??").is_err());
+        assert!(Pattern::parse("48 8").is_err());
+        assert!(Pattern::parse("48 8bb").is_err());
+        assert!(Pattern::parse("48 zz").is_err());
+        assert!(Pattern::parse("48,8b").is_err());
+    }
+
+    #[test]
+    fn find_patterns()
+    {
+        let pattern = Pattern::parse("8b ? c3").unwrap();
+        assert_eq!(vec![1, 4], pattern.find_all(&[0x00, 0x8b, 0x01, 0xc3, 0x8b, 0x02, 0xc3]));
+        assert!(pattern.find_all(&[0x8b, 0x01]).is_empty());
+    }
+}
@@ -0,0 +1,186 @@
+// This file is part of the soulmemory-rs distribution (https://github.com/FrankvdStam/soulmemory-rs).
+// Copyright (c) 2022 Frank van der Stam.
+// https://github.com/FrankvdStam/soulmemory-rs/blob/main/LICENSE
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use serde::{Deserialize, Serialize};
+use crate::pattern::Pattern;
+
+///Where a backend finds something in the game's memory. With an instruction length the pattern is a rip relative
+///instruction, offset points at its displacement. Without one, offset is added to the address of the match.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Symbol
+{
+    pub pattern: Pattern,
+    pub offset: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub instruction_length: Option<usize>,
+    #[serde(default)]
+    pub chain: Vec<usize>,
+}
+
+///Where a symbol's pattern matched in a module dump, as offsets from the start of the module
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Resolution
+{
+    pub matches: Vec<usize>,
+    ///The match plus offset, or the target of the relative instruction
+    pub address: usize,
+}
+
+impl Symbol
+{
+    ///Checks the offsets against the pattern, patterns are scanned as is so these mistakes only show up in game
+    pub fn validate(&self) -> Result<(), String>
+    {
+        match self.instruction_length
+        {
+            Some(instruction_length) =>
+            {
+                if self.offset + 4 > instruction_length
+                {
+                    return Err(format!("displacement at offset {} doesn't fit in an instruction of {} bytes", self.offset, instruction_length));
+                }
+                if self.offset + 4 > self.pattern.len()
+                {
+                    return Err(format!("displacement at offset {} is past the end of the pattern", self.offset));
+                }
+                if !(self.offset..self.offset + 4).all(|i| self.pattern.is_wildcard(i))
+                {
+                    return Err(format!("displacement at offset {} should be wildcards, it changes with every build", self.offset));
+                }
+            }
+            None =>
+            {
+                if self.offset > self.pattern.len()
+                {
+                    return Err(format!("offset {} is past the end of the pattern", self.offset));
+                }
+            }
+        }
+        return Ok(());
+    }
+
+    ///Scans a dump of the main module, the same way mem-rs scans the live module. The first match is the one used.
+    pub fn resolve(&self, module: &[u8]) -> Result<Resolution, String>
+    {
+        let matches = self.pattern.find_all(module);
+        let first = *matches.first().ok_or("no match")?;
+
+        let address = match self.instruction_length
+        {
+            Some(instruction_length) =>
+            {
+                let bytes = module.get(first + self.offset..first + self.offset + 4).ok_or("displacement is past the end of the dump")?;
+                let displacement = i32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+                let target = (first + instruction_length) as i64 + displacement as i64;
+                usize::try_from(target).map_err(|_| format!("relative target {} is before the start of the module", target))?
+            }
+            None => first + self.offset,
+        };
+        return Ok(Resolution { matches, address });
+    }
+}
+
+///The symbols of one game, stored as a toml table per symbol:
+///
+///[SprjEventFlagMan]
+///pattern = "48 8b 0d ? ? ? ? 48 89 5c 24 50"
+///offset = 3
+///instruction_length = 7
+///chain = [0]
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct PatternFile
+{
+    pub symbols: BTreeMap<String, Symbol>,
+}
+
+impl PatternFile
+{
+    pub fn load(path: &Path) -> Result<PatternFile, String>
+    {
+        let text = fs::read_to_string(path).map_err(|e| format!("failed to read {}: {}", path.display(), e))?;
+        return PatternFile::parse(&text).map_err(|e| format!("{}: {}", path.display(), e));
+    }
+
+    pub fn parse(text: &str) -> Result<PatternFile, String>
+    {
+        let file = toml::from_str::<PatternFile>(text).map_err(|e| e.message().to_string())?;
+        file.validate()?;
+        return Ok(file);
+    }
+
+    pub fn validate(&self) -> Result<(), String>
+    {
+        for (name, symbol) in &self.symbols
+        {
+            symbol.validate().map_err(|e| format!("{}: {}", name, e))?;
+        }
+        return Ok(());
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Symbol>
+    {
+        return self.symbols.get(name);
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use crate::symbol::*;
+
+    #[test]
+    fn parse_pattern_file()
+    {
+        let file = PatternFile::parse(r#"
+            [SprjEventFlagMan]
+            pattern = "48 8b 0d ? ? ? ? 48 89 5c 24 50"
+            offset = 3
+            instruction_length = 7
+            chain = [0]
+
+            ["set_event_flag"]
+            pattern = "40 53 48 83 ec 20"
+            offset = 0
+        "#).unwrap();
+        assert_eq!(Some(7), file.get("SprjEventFlagMan").unwrap().instruction_length);
+        assert!(file.get("set_event_flag").unwrap().chain.is_empty());
+
+        let error = PatternFile::parse("[igt]\npattern = \"48 8b 05 ? ? ? ?\"\noffset = 3\ninstruction_length = 6").unwrap_err();
+        assert!(error.starts_with("igt: "));
+        assert!(PatternFile::parse("[igt]\npattern = \"48 8b 05 12 ? ? ?\"\noffset = 3\ninstruction_length = 7").is_err());
+        assert!(PatternFile::parse("[igt]\npattern = \"48 8b 05 ? ? ? ?\"\noffset = 3\nlength = 7").is_err());
+        assert!(PatternFile::parse("[igt]\npattern = \"48 8b 5\"\noffset = 0").is_err());
+    }
+
+    #[test]
+    fn resolve_symbols()
+    {
+        //mov rcx, [rip + 0x10] at 0x2, then the same instruction again at 0x9
+        let module = [0x90, 0x90, 0x48, 0x8b, 0x0d, 0x10, 0x00, 0x00, 0x00, 0x48, 0x8b, 0x0d, 0xf0, 0xff, 0xff, 0xff];
+        let symbol = Symbol { pattern: Pattern::parse("48 8b 0d ? ? ? ?").unwrap(), offset: 3, instruction_length: Some(7), chain: vec![0] };
+        assert_eq!(Resolution { matches: vec![2, 9], address: 0x19 }, symbol.resolve(&module).unwrap());
+
+        let symbol = Symbol { pattern: Pattern::parse("90 48 8b").unwrap(), offset: 1, instruction_length: None, chain: Vec::new() };
+        assert_eq!(2, symbol.resolve(&module).unwrap().address);
+        assert!(Symbol { pattern: Pattern::parse("c3").unwrap(), offset: 0, instruction_length: None, chain: Vec::new() }.resolve(&module).is_err());
+    }
+}
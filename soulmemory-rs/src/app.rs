@@ -14,30 +14,68 @@
 // You should have received a copy of the GNU General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
+use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
+use log::{error, info, warn};
 use windows::Win32::Foundation::HINSTANCE;
-use imgui::{Condition, Ui};
+use imgui::{Condition, TreeNodeFlags, Ui};
 use crate::widgets::widget::Widget;
 use crate::util::server::Server;
-use crate::widgets::ai_toggle_widget::AiToggleWidget;
-use crate::widgets::basic_position_widget::PlayerPositionWidget;
-use crate::widgets::chr_dbg_flags_widget::ChrDbgFlagsWidget;
-use crate::widgets::event_flag_widget::EventFlagWidget;
-use crate::widgets::misc_widget::MiscWidget;
+use crate::util::protocol::{ChrDbgFlagState, Notification, Request, Response, PROTOCOL_VERSION};
 use crate::games::*;
+use crate::recorder::{RecordFormat, Recorder};
+use crate::recorder::recorder::RECORDING_DIRECTORY;
+use crate::util::{get_module_directory, read_main_module_header};
+use crate::splits::{Autosplitter, SplitInput};
+use crate::chr_dbg_flags::{ChrDbgFlagSettings, CHR_DBG_FLAGS_DIRECTORY};
+use crate::games::traits::buffered_event_flags::EventFlag;
+use soulmemory_tas::{script_path, TasPlayer, TasRecording, TasScript, TAS_DIRECTORY};
+use crate::input::{AiToggle, InputChain, InputContext, InputFrame, Remap};
+use crate::input::xinput::XInputHook;
+use crate::macros::MacroRunner;
+use soulmemory_config::{resolve_path, Config, CONFIG_FILE};
+use crate::error::Error;
+use crate::games::game_id::GameId;
+use crate::versions::{GameVersion, PeInfo, VersionTable, VERSIONS_DIRECTORY};
+use crate::hotkeys::HotkeyManager;
+use crate::layout::{Layout, LAYOUT_FILE, MAIN_WINDOW};
+use crate::widgets::registry::{WidgetContext, WidgetRegistry};
 
 pub struct App
 {
     pub game: Box<dyn Game>,
     pub hmodule: HINSTANCE,
-    #[allow(dead_code)]
     server: Server,
     widgets: Vec<Box<dyn Widget>>,
+    recorder: Option<Recorder>,
+    autosplitter: Arc<Mutex<Autosplitter>>,
+    chr_dbg_flag_settings: Arc<Mutex<ChrDbgFlagSettings>>,
+    tas_player: Arc<Mutex<TasPlayer>>,
+    tas_directory: Option<PathBuf>,
+    input_chain: InputChain,
+    xinput_hook: XInputHook,
+    hotkeys: Arc<Mutex<HotkeyManager>>,
+    layout: Layout,
+    saved_layout: Layout,
+    layout_path: Option<PathBuf>,
+    version: GameVersion,
+    error: Option<Error>,
 }
 
 impl App
 {
-    pub fn init(process_name: &String, hmodule: HINSTANCE)
+    pub fn init(process_name: &String, hmodule: HINSTANCE) -> Result<(), Error>
+    {
+        return App::init_with_config(process_name, hmodule, Config::default());
+    }
+
+    pub fn init_with_config(process_name: &String, hmodule: HINSTANCE, config: Config) -> Result<(), Error>
+    {
+        return App::init_with_registry(process_name, hmodule, config, WidgetRegistry::new());
+    }
+
+    ///Widgets registered on top of WidgetRegistry::new are shown after the built in ones
+    pub fn init_with_registry(process_name: &String, hmodule: HINSTANCE, config: Config, registry: WidgetRegistry) -> Result<(), Error>
     {
         unsafe
         {
@@ -45,8 +83,9 @@ impl App
             {
                 panic!("init called on app while it is already instantiated.");
             }
-            APP = Some(Arc::new(Mutex::new(App::new(process_name, hmodule))));
+            APP = Some(Arc::new(Mutex::new(App::new(process_name, hmodule, config, registry)?)));
         };
+        return Ok(());
     }
 
     pub fn get_instance() -> Arc<Mutex<App>>
@@ -61,10 +100,10 @@ impl App
         };
     }
 
-    pub fn new(process_name: &String, hmodule: HINSTANCE) -> Self
+    pub fn new(process_name: &String, hmodule: HINSTANCE, config: Config, registry: WidgetRegistry) -> Result<Self, Error>
     {
         //Init the game we're injected in
-        let game: Box<dyn Game> = match process_name.to_lowercase().as_str()
+        let mut game: Box<dyn Game> = match process_name.to_lowercase().as_str()
         {
             "mockgame.exe"              => Box::new(MockGame::new()),
             "darksouls.exe"             => Box::new(DarkSoulsPrepareToDieEdition::new()),
@@ -77,48 +116,515 @@ impl App
             "sekiro.exe"                => Box::new(Sekiro::new()),
             "eldenring.exe"             => Box::new(EldenRing::new()),
             "armoredcore6.exe"          => Box::new(ArmoredCore6::new()),
-            _                           => panic!("unsupported process: {}", process_name.to_lowercase()),
+            _                           => return Err(Error::UnsupportedProcess(process_name.to_lowercase())),
         };
 
-        //get drawable widgets
-        //let widgets = game.get_widgets();
+        let module_directory = get_module_directory(hmodule);
+        let recorder = App::create_recorder(&config, module_directory.as_ref(), game.get_game_id());
+        let game_id = game.get_game_id();
+        let version = App::detect_version(module_directory.as_ref(), game_id);
+        info!("{} version: {}", game_id, version);
+        if !version.is_known() && game_id != GameId::MockGame
+        {
+            info!("{}/{}.toml doesn't list this build, using the built in patterns and offsets", VERSIONS_DIRECTORY, game_id);
+        }
+        game.set_version(version.clone());
 
-        App
+        let autosplitter = Arc::new(Mutex::new(Autosplitter::default()));
+        let chr_dbg_flag_settings = ChrDbgFlagSettings::load(module_directory.as_ref().map(|d| ChrDbgFlagSettings::path(&d.join(CHR_DBG_FLAGS_DIRECTORY), game_id))).unwrap_or_else(|e|
+        {
+            error!("failed to load chr dbg flags: {}", e);
+            ChrDbgFlagSettings::default()
+        });
+        let chr_dbg_flag_settings = Arc::new(Mutex::new(chr_dbg_flag_settings));
+        let tas_player = Arc::new(Mutex::new(TasPlayer::default()));
+        let tas_recording = Arc::new(Mutex::new(TasRecording::default()));
+        let tas_directory = module_directory.as_ref().map(|d| d.join(TAS_DIRECTORY));
+        let remap = Arc::new(Mutex::new(Remap::default()));
+        let ai_toggle = Arc::new(Mutex::new(AiToggle::default()));
+        let macro_runner = Arc::new(Mutex::new(MacroRunner::default()));
+        let hotkeys = Arc::new(Mutex::new(HotkeyManager::new(config.hotkeys.clone(), module_directory.as_ref().map(|d| d.join(CONFIG_FILE)))));
+
+        //Hotkeys see the controller as the player holds it. Record the player before anything touches the input, then remap it. A playing script replaces all of that,
+        //macros and the ai toggle work on top of whatever is left.
+        let mut input_chain = InputChain::default();
+        input_chain.push(Arc::clone(&hotkeys));
+        input_chain.push(Arc::clone(&tas_recording));
+        input_chain.push(Arc::clone(&remap));
+        input_chain.push(Arc::clone(&tas_player));
+        input_chain.push(Arc::clone(&macro_runner));
+        input_chain.push(Arc::clone(&ai_toggle));
+
+        let server = match config.server.enabled
+        {
+            true => Server::new(config.server.address.clone()),
+            false =>
+            {
+                info!("server disabled");
+                Server::default()
+            }
+        };
+
+        let capabilities = game.capabilities();
+        info!("{} supports {:?}", game_id, capabilities);
+        let context = WidgetContext
+        {
+            game_id,
+            module_directory: module_directory.clone(),
+            config: &config,
+            autosplitter: Arc::clone(&autosplitter),
+            chr_dbg_flag_settings: Arc::clone(&chr_dbg_flag_settings),
+            tas_player: Arc::clone(&tas_player),
+            tas_recording,
+            tas_directory: tas_directory.clone(),
+            remap,
+            ai_toggle,
+            macro_runner,
+            hotkeys: Arc::clone(&hotkeys),
+        };
+        let widgets = registry.build(&context, &capabilities);
+
+        let layout_path = module_directory.as_ref().map(|d| d.join(LAYOUT_FILE));
+        let layout = match &layout_path
+        {
+            Some(path) => Layout::load(path).unwrap_or_else(|e|
+            {
+                error!("failed to load layout: {}", e);
+                Layout::default()
+            }),
+            None => Layout::default(),
+        };
+
+        let mut hotkey_manager = hotkeys.lock().unwrap();
+        let mut hotkey_errors: Vec<Error> = hotkey_manager.register("toggle_overlay", "show/hide the overlay", "insert").err().into_iter().collect();
+        for widget in &widgets
+        {
+            hotkey_errors.extend(widget.register_hotkeys(&mut hotkey_manager));
+        }
+        for id in hotkey_manager.unknown_overrides()
+        {
+            warn!("[hotkeys] {} in the config isn't a known action", id);
+        }
+        drop(hotkey_manager);
+
+        let mut app = App
         {
             game,
             hmodule,
-            server: Server::new(String::from("127.0.0.1:54345")),
-            widgets: vec!
-            {
-                Box::new(EventFlagWidget::new()),
-                Box::new(AiToggleWidget::new()),
-                Box::new(PlayerPositionWidget::new()),
-                Box::new(ChrDbgFlagsWidget::new()),
-                Box::new(MiscWidget::new()),
-            }
+            server,
+            widgets,
+            recorder,
+            autosplitter,
+            chr_dbg_flag_settings,
+            tas_player,
+            tas_directory,
+            input_chain,
+            xinput_hook: XInputHook::default(),
+            hotkeys,
+            saved_layout: layout.clone(),
+            layout,
+            layout_path,
+            version,
+            error: None,
+        };
+
+        for error in hotkey_errors
+        {
+            app.report_error(error);
+        }
+        return Ok(app);
+    }
+
+    ///Only when enabled in the config, the directory is relative to the dll
+    fn create_recorder(config: &Config, module_directory: Option<&PathBuf>, game_id: GameId) -> Option<Recorder>
+    {
+        if !config.recording.enabled
+        {
+            return None;
         }
+
+        let directory = match (config.recording.directory.trim(), module_directory)
+        {
+            ("", Some(module_directory)) => module_directory.join(RECORDING_DIRECTORY),
+            ("", None) =>
+            {
+                error!("event flag recording disabled: the dll directory is unknown and recording.directory isn't set");
+                return None;
+            }
+            (directory, module_directory) => resolve_path(module_directory.map(|d| d.as_path()), directory),
+        };
+
+        return match Recorder::new(game_id, directory, RecordFormat::JsonLines, config.recording.max_sessions)
+        {
+            Ok(recorder) => Some(recorder),
+            Err(e) =>
+            {
+                error!("event flag recording disabled: {}", e);
+                None
+            }
+        };
+    }
+
+    ///Reads the build from the executable's PE headers and looks it up in versions/<game>.toml
+    fn detect_version(module_directory: Option<&PathBuf>, game_id: GameId) -> GameVersion
+    {
+        let pe = match read_main_module_header().map(|header| PeInfo::parse(&header))
+        {
+            Some(Ok(pe)) => pe,
+            Some(Err(e)) =>
+            {
+                error!("failed to read the game's PE header: {}", e);
+                PeInfo::default()
+            }
+            None => PeInfo::default(),
+        };
+
+        let table = match module_directory
+        {
+            Some(directory) => VersionTable::load(&VersionTable::path(&directory.join(VERSIONS_DIRECTORY), game_id)).unwrap_or_else(|e|
+            {
+                error!("failed to load versions: {}", e);
+                VersionTable::default()
+            }),
+            None => VersionTable::default(),
+        };
+        return table.detect(pe);
     }
 
-    pub fn refresh(&mut self) -> Result<(), String>
+    pub fn refresh(&mut self) -> Result<(), Error>
     {
         self.game.refresh()?;
+        if self.game.hooks_input()
+        {
+            //Everything but input still works without the hook
+            if let Err(e) = self.xinput_hook.refresh()
+            {
+                self.report_error(e);
+            }
+        }
+        let event_flags = self.dispatch_event_flags();
+        self.update_autosplitter(&event_flags);
+        self.apply_chr_dbg_flags();
+        self.handle_requests();
         Ok(())
     }
 
+    ///Logs an error once and shows it in the overlay until it is dismissed
+    pub fn report_error(&mut self, error: Error)
+    {
+        if self.error.as_ref() != Some(&error)
+        {
+            error!("{}", error);
+        }
+        self.error = Some(error);
+    }
+
+    fn render_error(ui: &Ui, error: &mut Option<Error>)
+    {
+        if let Some(e) = error
+        {
+            ui.text_colored([1.0f32, 0.0f32, 0.0f32, 1.0f32], e.to_string());
+            if ui.button("dismiss")
+            {
+                *error = None;
+            }
+            ui.separator();
+        }
+    }
+
+    fn handle_requests(&mut self)
+    {
+        while let Some(pending) = self.server.get_request()
+        {
+            let response = self.handle_request(&pending.envelope.request);
+            pending.reply(response);
+        }
+    }
+
+    fn handle_request(&mut self, request: &Request) -> Response
+    {
+        match request
+        {
+            Request::GetVersion => Response::Version { protocol: PROTOCOL_VERSION, soulmemory: String::from(env!("CARGO_PKG_VERSION")) },
+            Request::GetGame => Response::Game { game: self.game.get_game_id(), version: self.version.clone() },
+            Request::GetPosition => match self.game.player_position()
+            {
+                Some(position) => Response::Position(position.get_position()),
+                None => Response::unsupported(request),
+            },
+            Request::GetEventFlag { flag } => match self.game.event_flags()
+            {
+                Some(event_flags) => Response::EventFlag { flag: *flag, state: event_flags.get_event_flag_state(*flag) },
+                None => Response::unsupported(request),
+            },
+            Request::GetEventFlagSnapshot => match self.game.event_flags()
+            {
+                Some(event_flags) => match event_flags.snapshot_event_flags()
+                {
+                    Ok(snapshot) => Response::event_flag_snapshot(chrono::offset::Local::now(), &snapshot),
+                    Err(e) => Response::error(e.to_string()),
+                },
+                None => Response::unsupported(request),
+            },
+            Request::SetEventFlag { flag, state } => match self.game.event_flags()
+            {
+                Some(event_flags) =>
+                {
+                    event_flags.set_event_flag_state(*flag, *state);
+                    Response::Ok
+                }
+                None => Response::unsupported(request),
+            },
+            Request::GetChrDbgFlags => match self.game.chr_dbg_flags().map(|chr_dbg_flags| chr_dbg_flags.get_flags())
+            {
+                Some(flags) if !flags.is_empty() => Response::ChrDbgFlags
+                {
+                    flags: flags.into_iter().map(|(flag, name, state)| ChrDbgFlagState { flag, name, state }).collect()
+                },
+                _ => Response::unsupported(request),
+            },
+            Request::SetChrDbgFlag { flag, state } => match self.game.chr_dbg_flags()
+            {
+                Some(chr_dbg_flags) if !chr_dbg_flags.get_flags().is_empty() =>
+                {
+                    chr_dbg_flags.set_flag(*flag, *state);
+                    match self.chr_dbg_flag_settings.lock().unwrap().set(*flag, *state)
+                    {
+                        Ok(()) => Response::Ok,
+                        Err(e) => Response::error(format!("flag set, but not saved: {}", e)),
+                    }
+                }
+                _ => Response::unsupported(request),
+            },
+            Request::TasLoad { path } =>
+            {
+                let path = match &self.tas_directory
+                {
+                    Some(directory) => script_path(directory, path),
+                    None => Err(String::from("the tas directory is unknown")),
+                };
+                match path.and_then(|path| TasScript::load(&path).map(|script| (path, script)))
+                {
+                    Ok((path, script)) =>
+                    {
+                        info!("loaded tas script {} with {} entries", path.display(), script.entries.len());
+                        self.tas_player.lock().unwrap().load(&script);
+                        Response::Ok
+                    }
+                    Err(e) => Response::error(e),
+                }
+            },
+            Request::TasStart => match self.tas_player.lock().unwrap().start()
+            {
+                Ok(()) => Response::Ok,
+                Err(e) => Response::error(e),
+            },
+            Request::TasStop =>
+            {
+                self.tas_player.lock().unwrap().stop();
+                Response::Ok
+            },
+            Request::TasPause =>
+            {
+                self.tas_player.lock().unwrap().pause();
+                Response::Ok
+            },
+            Request::TasResume =>
+            {
+                self.tas_player.lock().unwrap().resume();
+                Response::Ok
+            },
+            Request::GetTasStatus =>
+            {
+                let tas_player = self.tas_player.lock().unwrap();
+                Response::TasStatus { state: tas_player.state(), frame: tas_player.frame(), length: tas_player.length() }
+            },
+            //Handled by the server itself, never queued
+            Request::Subscribe(_) | Request::Unsubscribe => Response::Ok,
+            Request::RequestQuitout => match self.game.quitout()
+            {
+                Some(quitout) =>
+                {
+                    quitout.request_quitout();
+                    Response::Ok
+                }
+                None => Response::unsupported(request),
+            },
+        }
+    }
+
+    ///Runs every frame, also while the overlay is hidden
+    fn handle_hotkeys(&mut self, ui: &Ui)
+    {
+        let hotkeys = Arc::clone(&self.hotkeys);
+        let mut hotkeys = hotkeys.lock().unwrap();
+        let io = ui.io();
+        if let Some(Err(e)) = hotkeys.update(&io.keys_down, io.want_text_input)
+        {
+            error!("failed to rebind hotkey: {}", e);
+        }
+
+        if hotkeys.is_pressed("toggle_overlay")
+        {
+            self.layout.overlay_visible = !self.layout.overlay_visible;
+        }
+
+        for w in &mut self.widgets
+        {
+            w.on_hotkeys(&mut self.game, &hotkeys);
+        }
+    }
+
+    ///Called from the xinput hook on the game's thread for every read of the first controller
+    pub(crate) fn modify_input(&mut self, frame: &mut InputFrame)
+    {
+        let context = InputContext { ai_timer: self.game.ai_timer().map(|ai_timer| ai_timer.get_ai_timer_value()) };
+        self.input_chain.modify(&context, frame);
+    }
+
+    ///Writes back chr dbg flags the game lost, for example after it was restarted
+    fn apply_chr_dbg_flags(&mut self)
+    {
+        if let Some(chr_dbg_flags) = self.game.chr_dbg_flags()
+        {
+            let settings = self.chr_dbg_flag_settings.lock().unwrap();
+            for (flag, state) in settings.out_of_sync(&chr_dbg_flags.get_flags())
+            {
+                chr_dbg_flags.set_flag(flag, state);
+            }
+        }
+    }
+
+    ///Drains the captured event flags once per refresh and hands them to everything that is interested in them
+    fn dispatch_event_flags(&mut self) -> Vec<EventFlag>
+    {
+        let event_flags = match self.game.event_flags()
+        {
+            Some(mut buffered_event_flags) => buffered_event_flags.get_buffered_flags(),
+            None => return Vec::new(),
+        };
+
+        if event_flags.is_empty()
+        {
+            return event_flags;
+        }
+
+        if let Some(Err(e)) = self.recorder.as_mut().map(|r| r.record(&event_flags))
+        {
+            error!("event flag recording stopped: {}", e);
+            self.recorder = None;
+        }
+
+        self.server.publish_event_flags(&event_flags);
+
+        for w in &mut self.widgets
+        {
+            w.on_event_flags(&event_flags);
+        }
+        return event_flags;
+    }
+
+    fn update_autosplitter(&mut self, event_flags: &[EventFlag])
+    {
+        let position = self.game.player_position().map(|p| p.get_position());
+        let in_game_time = self.game.in_game_time().map(|igt| igt.get_in_game_time_milliseconds());
+
+        let events = self.autosplitter.lock().unwrap().update(&SplitInput { event_flags, position, in_game_time });
+        for event in events
+        {
+            info!("autosplitter: {:?}", event);
+            self.server.publish_notification(Notification::Split(event));
+        }
+    }
+
     pub fn render(&mut self, ui: &mut Ui)
     {
-        ui.window("soulmemory-rs")
-            .position([50.0f32, 50.0f32], Condition::Appearing)
-            .size([350.0, 800.0], Condition::Appearing)
-            .build(||
+        self.handle_hotkeys(ui);
+        for w in &mut self.widgets
+        {
+            w.on_frame(&mut self.game);
+        }
+        if self.layout.overlay_visible
         {
-            for w in &mut self.widgets
+            let (game, widgets, layout, error, version) = (&mut self.game, &mut self.widgets, &mut self.layout, &mut self.error, &self.version);
+            App::build_window(ui, layout, MAIN_WINDOW, MAIN_WINDOW, |layout|
+            {
+                ui.text(format!("{} {}", game.get_game_id(), version));
+                App::render_error(ui, error);
+
+                for w in widgets.iter_mut().filter(|w| !layout.is_detached(w.name()))
+                {
+                    w.render(game, ui);
+                }
+                App::render_layout(ui, layout, widgets);
+            });
+
+            for w in widgets.iter_mut()
             {
-                w.render(&mut self.game, ui);
+                if layout.is_detached(w.name())
+                {
+                    let name = String::from(w.name());
+                    App::build_window(ui, layout, &name, &format!("{}: {}", MAIN_WINDOW, name), |_| w.render(game, ui));
+                }
             }
-        });
+        }
+        self.save_layout(ui);
         //ui.show_demo_window(&mut true);
     }
+
+    ///Draws a window where the layout says it was last time and remembers where it is now
+    fn build_window<F: FnOnce(&mut Layout)>(ui: &Ui, layout: &mut Layout, name: &str, title: &str, f: F)
+    {
+        let window = layout.window(name);
+        let built = ui.window(title)
+            .position(window.position, Condition::FirstUseEver)
+            .size(window.size, Condition::FirstUseEver)
+            .collapsed(window.collapsed, Condition::FirstUseEver)
+            .build(||
+        {
+            layout.update(name, Some(ui.window_pos()), Some(ui.window_size()), false);
+            f(layout);
+        });
+
+        if built.is_none()
+        {
+            layout.update(name, None, None, true);
+        }
+    }
+
+    fn render_layout(ui: &Ui, layout: &mut Layout, widgets: &[Box<dyn Widget>])
+    {
+        if ui.collapsing_header("layout", TreeNodeFlags::FRAMED)
+        {
+            ui.text("detach into a separate window:");
+            for w in widgets
+            {
+                let mut detached = layout.is_detached(w.name());
+                if ui.checkbox(w.name(), &mut detached)
+                {
+                    layout.set_detached(w.name(), detached);
+                }
+            }
+        }
+    }
+
+    ///Writes layout.json once a window is done being dragged or resized
+    fn save_layout(&mut self, ui: &Ui)
+    {
+        if self.layout == self.saved_layout || ui.is_any_mouse_down()
+        {
+            return;
+        }
+
+        if let Some(path) = &self.layout_path
+        {
+            if let Err(e) = self.layout.save(path)
+            {
+                error!("failed to save layout: {}", e);
+            }
+        }
+        self.saved_layout = self.layout.clone();
+    }
 }
 
 
@@ -134,6 +640,19 @@ impl Default for App
             hmodule: HINSTANCE(std::ptr::null_mut()),
             server: Server::default(),
             widgets: Vec::new(),
+            recorder: None,
+            autosplitter: Arc::new(Mutex::new(Autosplitter::default())),
+            chr_dbg_flag_settings: Arc::new(Mutex::new(ChrDbgFlagSettings::default())),
+            tas_player: Arc::new(Mutex::new(TasPlayer::default())),
+            tas_directory: None,
+            input_chain: InputChain::default(),
+            xinput_hook: XInputHook::default(),
+            hotkeys: Arc::new(Mutex::new(HotkeyManager::default())),
+            layout: Layout::default(),
+            saved_layout: Layout::default(),
+            layout_path: None,
+            version: GameVersion::default(),
+            error: None,
         }
     }
 }
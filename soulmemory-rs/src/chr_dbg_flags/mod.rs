@@ -0,0 +1,120 @@
+// This file is part of the soulmemory-rs distribution (https://github.com/FrankvdStam/soulmemory-rs).
+// Copyright (c) 2022 Frank van der Stam.
+// https://github.com/FrankvdStam/soulmemory-rs/blob/main/LICENSE
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+use crate::games::ChrDbgFlag;
+use crate::games::game_id::GameId;
+
+pub const CHR_DBG_FLAGS_DIRECTORY: &str = "chr_dbg_flags";
+
+#[derive(Default, Serialize, Deserialize)]
+struct ChrDbgFlagFile
+{
+    flags: BTreeMap<u32, bool>,
+}
+
+///The chr dbg flags that were changed from the overlay or the server, stored as <directory>/<game>.json.
+///The game forgets them when it restarts, so they are written again whenever the game's value differs.
+#[derive(Default)]
+pub struct ChrDbgFlagSettings
+{
+    path: Option<PathBuf>,
+    flags: BTreeMap<u32, bool>,
+}
+
+impl ChrDbgFlagSettings
+{
+    pub fn path(directory: &Path, game: GameId) -> PathBuf
+    {
+        return directory.join(format!("{}.json", game));
+    }
+
+    ///Without a path nothing is persisted, a missing file starts out empty
+    pub fn load(path: Option<PathBuf>) -> Result<Self, String>
+    {
+        let file = match &path
+        {
+            Some(path) if path.exists() =>
+            {
+                let text = fs::read_to_string(path).map_err(|e| format!("failed to read {}: {}", path.display(), e))?;
+                serde_json::from_str::<ChrDbgFlagFile>(&text).map_err(|e| format!("{}: {}", path.display(), e))?
+            }
+            _ => ChrDbgFlagFile::default(),
+        };
+        return Ok(ChrDbgFlagSettings { path, flags: file.flags });
+    }
+
+    pub fn get(&self, flag: u32) -> Option<bool>
+    {
+        return self.flags.get(&flag).copied();
+    }
+
+    ///Remembers the state and saves it right away
+    pub fn set(&mut self, flag: u32, state: bool) -> Result<(), String>
+    {
+        self.flags.insert(flag, state);
+        let path = match &self.path
+        {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+
+        if let Some(directory) = path.parent()
+        {
+            fs::create_dir_all(directory).map_err(|e| format!("failed to create {}: {}", directory.display(), e))?;
+        }
+        let text = serde_json::to_string_pretty(&ChrDbgFlagFile { flags: self.flags.clone() }).map_err(|e| e.to_string())?;
+        return fs::write(path, text).map_err(|e| format!("failed to write {}: {}", path.display(), e));
+    }
+
+    ///Flags the game has lost since they were set, with the state they should be written back to
+    pub fn out_of_sync(&self, current: &[ChrDbgFlag]) -> Vec<(u32, bool)>
+    {
+        return current.iter()
+            .filter_map(|(flag, _, state)| self.get(*flag).filter(|wanted| wanted != state).map(|wanted| (*flag, wanted)))
+            .collect();
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use crate::chr_dbg_flags::ChrDbgFlagSettings;
+    use crate::games::game_id::GameId;
+
+    #[test]
+    fn persist_and_reapply()
+    {
+        let directory = std::env::temp_dir().join(format!("soulmemory-chr-dbg-flags-test-{}", std::process::id()));
+        let path = ChrDbgFlagSettings::path(&directory, GameId::DarkSouls3);
+
+        let mut settings = ChrDbgFlagSettings::load(Some(path.clone())).unwrap();
+        settings.set(0, true).unwrap();
+        settings.set(9, false).unwrap();
+
+        let settings = ChrDbgFlagSettings::load(Some(path));
+        std::fs::remove_dir_all(&directory).unwrap();
+        let settings = settings.unwrap();
+        assert_eq!(Some(true), settings.get(0));
+
+        //After a restart the game has forgotten flag 0, flag 9 and 10 are fine or weren't touched
+        let current = vec![(0, String::from("Player No Dead"), false), (9, String::from("All No Dead"), false), (10, String::from("All No Damage"), true)];
+        assert_eq!(vec![(0, true)], settings.out_of_sync(&current));
+    }
+}
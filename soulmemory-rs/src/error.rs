@@ -0,0 +1,103 @@
+// This file is part of the soulmemory-rs distribution (https://github.com/FrankvdStam/soulmemory-rs).
+// Copyright (c) 2022 Frank van der Stam.
+// https://github.com/FrankvdStam/soulmemory-rs/blob/main/LICENSE
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::fmt::{Debug, Display, Formatter};
+use soulmemory_memory::MemoryError;
+
+///Errors that are reported instead of taking down the game. They end up in the log and in the overlay's error banner.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Error
+{
+    ///The dll was injected into a process no game backend exists for
+    UnsupportedProcess(String),
+    ///Attaching to the game or scanning its memory failed, mem-rs reports these as strings
+    Process(String),
+    ///A function hook could not be installed or removed
+    Hook { name: String, message: String },
+    ///The event flag id doesn't map onto the game's flag storage
+    InvalidEventFlag(u32),
+    ///hudhook failed to hook the game's renderer
+    RenderHook(String),
+    ///soulmemory.toml or a binding in it is invalid, the affected setting falls back to its default
+    Config(String),
+    ///A backend's pattern file doesn't parse or lacks a symbol it scans for
+    Patterns(String),
+    ///The current game has the capability, but not this part of it
+    Unsupported(String),
+}
+
+impl Error
+{
+    pub fn hook(name: &str, error: impl Debug) -> Self
+    {
+        return Error::Hook { name: String::from(name), message: format!("{:?}", error) };
+    }
+}
+
+impl Display for Error
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result
+    {
+        match self
+        {
+            Error::UnsupportedProcess(process) => write!(f, "unsupported process: {}", process),
+            Error::Process(message) => write!(f, "process: {}", message),
+            Error::Hook { name, message } => write!(f, "failed to hook {}: {}", name, message),
+            Error::InvalidEventFlag(flag) => write!(f, "invalid event flag {}", flag),
+            Error::RenderHook(message) => write!(f, "failed to hook the renderer: {}", message),
+            Error::Config(message) => write!(f, "config: {}", message),
+            Error::Patterns(message) => write!(f, "patterns: {}", message),
+            Error::Unsupported(feature) => write!(f, "{} not supported by the current game", feature),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<String> for Error
+{
+    fn from(message: String) -> Self
+    {
+        return Error::Process(message);
+    }
+}
+
+impl From<MemoryError> for Error
+{
+    fn from(error: MemoryError) -> Self
+    {
+        return match error
+        {
+            MemoryError::InvalidEventFlag(flag) => Error::InvalidEventFlag(flag),
+            MemoryError::Unreadable(_) => Error::Process(error.to_string()),
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use crate::error::Error;
+
+    #[test]
+    fn display()
+    {
+        assert_eq!("unsupported process: notepad.exe", Error::UnsupportedProcess(String::from("notepad.exe")).to_string());
+        assert_eq!("failed to hook set_event_flag: Some(3)", Error::hook("set_event_flag", Some(3)).to_string());
+        assert_eq!(Error::Process(String::from("not attached")), Error::from(String::from("not attached")));
+        assert_eq!("event flag snapshots not supported by the current game", Error::Unsupported(String::from("event flag snapshots")).to_string());
+    }
+}
@@ -0,0 +1,88 @@
+// This file is part of the soulmemory-rs distribution (https://github.com/FrankvdStam/soulmemory-rs).
+// Copyright (c) 2022 Frank van der Stam.
+// https://github.com/FrankvdStam/soulmemory-rs/blob/main/LICENSE
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//Minimal rfc 4180 reader: comma separated, fields may be quoted, quotes inside quoted fields are doubled.
+pub struct Row
+{
+    ///1-based line the row starts on, quoted fields may span lines
+    pub line: usize,
+    pub fields: Vec<String>,
+}
+
+pub fn parse(text: &str) -> Result<Vec<Row>, String>
+{
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut line = 1;
+    let mut row_line = 1;
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next()
+    {
+        if in_quotes
+        {
+            match c
+            {
+                '\n' =>
+                {
+                    line += 1;
+                    field.push(c);
+                }
+                '"' if chars.peek() == Some(&'"') =>
+                {
+                    field.push('"');
+                    chars.next();
+                }
+                '"' => in_quotes = false,
+                _ => field.push(c),
+            }
+            continue;
+        }
+
+        match c
+        {
+            '"' => in_quotes = true,
+            ',' => row.push(std::mem::take(&mut field)),
+            '\r' => {}
+            '\n' =>
+            {
+                row.push(std::mem::take(&mut field));
+                if !(row.len() == 1 && row[0].is_empty())
+                {
+                    rows.push(Row { line: row_line, fields: std::mem::take(&mut row) });
+                }
+                row.clear();
+                line += 1;
+                row_line = line;
+            }
+            _ => field.push(c),
+        }
+    }
+
+    if in_quotes
+    {
+        return Err(format!("line {}: unterminated quoted field", row_line));
+    }
+
+    if !field.is_empty() || !row.is_empty()
+    {
+        row.push(field);
+        rows.push(Row { line: row_line, fields: row });
+    }
+    return Ok(rows);
+}
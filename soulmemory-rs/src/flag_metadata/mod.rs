@@ -0,0 +1,187 @@
+// This file is part of the soulmemory-rs distribution (https://github.com/FrankvdStam/soulmemory-rs).
+// Copyright (c) 2022 Frank van der Stam.
+// https://github.com/FrankvdStam/soulmemory-rs/blob/main/LICENSE
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+mod csv;
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use serde::Deserialize;
+use crate::games::game_id::GameId;
+
+pub const FLAG_METADATA_DIRECTORY: &str = "flags";
+
+#[derive(Clone, Debug, Default, PartialEq, Deserialize)]
+pub struct FlagMetadata
+{
+    pub name: String,
+    #[serde(default)]
+    pub category: String,
+    #[serde(default)]
+    pub description: String,
+}
+
+#[derive(Deserialize)]
+struct TomlFlagFile
+{
+    #[serde(default)]
+    flag: Vec<TomlFlag>,
+}
+
+#[derive(Deserialize)]
+struct TomlFlag
+{
+    id: u32,
+    #[serde(flatten)]
+    metadata: FlagMetadata,
+}
+
+/// Human readable names for event flags, loaded from <directory>/<game>.toml and/or <directory>/<game>.csv
+///
+/// toml:
+/// [[flag]]
+/// id = 13000800
+/// name = "Iudex Gundyr defeated"
+/// category = "Bosses"
+/// description = "..."
+///
+/// csv, with a header row:
+/// id,name,category,description
+/// 13000800,Iudex Gundyr defeated,Bosses,"..."
+#[derive(Default)]
+pub struct FlagMetadataDatabase
+{
+    entries: HashMap<u32, FlagMetadata>,
+}
+
+impl FlagMetadataDatabase
+{
+    pub fn load(directory: &Path, game: GameId) -> Result<Self, String>
+    {
+        let mut database = FlagMetadataDatabase::default();
+
+        let toml_path = directory.join(format!("{}.toml", game));
+        if toml_path.exists()
+        {
+            let text = fs::read_to_string(&toml_path).map_err(|e| format!("{}: {}", toml_path.display(), e))?;
+            database.add_toml(&text).map_err(|e| format!("{}: {}", toml_path.display(), e))?;
+        }
+
+        let csv_path = directory.join(format!("{}.csv", game));
+        if csv_path.exists()
+        {
+            let text = fs::read_to_string(&csv_path).map_err(|e| format!("{}: {}", csv_path.display(), e))?;
+            database.add_csv(&text).map_err(|e| format!("{}: {}", csv_path.display(), e))?;
+        }
+
+        return Ok(database);
+    }
+
+    pub fn add_toml(&mut self, text: &str) -> Result<(), String>
+    {
+        let file = toml::from_str::<TomlFlagFile>(text).map_err(|e| e.to_string())?;
+        for f in file.flag
+        {
+            self.entries.insert(f.id, f.metadata);
+        }
+        Ok(())
+    }
+
+    pub fn add_csv(&mut self, text: &str) -> Result<(), String>
+    {
+        //The first row is the header
+        for csv::Row { line, fields } in csv::parse(text)?.into_iter().skip(1)
+        {
+            let id = fields.get(0).and_then(|f| f.trim().parse::<u32>().ok()).ok_or(format!("line {}: invalid flag id", line))?;
+            let name = fields.get(1).cloned().unwrap_or_default();
+            let category = fields.get(2).cloned().unwrap_or_default();
+            let description = fields.get(3).cloned().unwrap_or_default();
+            self.entries.insert(id, FlagMetadata { name, category, description });
+        }
+        Ok(())
+    }
+
+    pub fn len(&self) -> usize
+    {
+        return self.entries.len();
+    }
+
+    pub fn get(&self, flag: u32) -> Option<&FlagMetadata>
+    {
+        return self.entries.get(&flag);
+    }
+
+    pub fn get_name(&self, flag: u32) -> &str
+    {
+        return self.entries.get(&flag).map(|m| m.name.as_str()).unwrap_or("");
+    }
+
+    /// Case-insensitive match of a search string against the flag id and its name.
+    pub fn matches(&self, flag: u32, search: &str) -> bool
+    {
+        if search.is_empty()
+        {
+            return true;
+        }
+        if flag.to_string().contains(search)
+        {
+            return true;
+        }
+        let search = search.to_lowercase();
+        return self.get_name(flag).to_lowercase().contains(&search);
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use crate::flag_metadata::FlagMetadataDatabase;
+
+    #[test]
+    fn load_toml_and_csv()
+    {
+        let mut database = FlagMetadataDatabase::default();
+        database.add_toml(r#"
+            [[flag]]
+            id = 13000800
+            name = "Iudex Gundyr defeated"
+            category = "Bosses"
+
+            [[flag]]
+            id = 50006060
+            name = "Ring pickup"
+        "#).unwrap();
+        database.add_csv("id,name,category,description\n13000801,\"Gundyr, encountered\",Bosses,\"said \"\"hi\"\"\"\n").unwrap();
+
+        assert_eq!(3, database.len());
+        assert_eq!("Iudex Gundyr defeated", database.get_name(13000800));
+        assert_eq!("Bosses", database.get(13000800).unwrap().category);
+        assert_eq!("Gundyr, encountered", database.get_name(13000801));
+        assert_eq!("said \"hi\"", database.get(13000801).unwrap().description);
+        assert!(database.matches(13000800, "gundyr"));
+        assert!(database.matches(50006060, "5000"));
+        assert!(!database.matches(50006060, "gundyr"));
+    }
+
+    #[test]
+    fn csv_error_lines()
+    {
+        let mut database = FlagMetadataDatabase::default();
+        let text = "\nid,name,category,description\n1,\"multi\nline\",,\n\nx,broken,,\n";
+        assert_eq!("line 6: invalid flag id", database.add_csv(text).unwrap_err());
+        assert_eq!("line 2: unterminated quoted field", database.add_csv("id,name\n1,\"open\n").unwrap_err());
+    }
+}
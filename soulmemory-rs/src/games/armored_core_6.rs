@@ -18,27 +18,43 @@
 #![allow(unused_imports)]
 
 use std::any::Any;
-use std::mem;
 use std::ops::Deref;
 use std::sync::{Arc, Mutex};
 use ilhook::x64::{CallbackOption, Hooker, HookFlags, HookPoint, HookType, Registers};
-use log::info;
+use log::{info, warn};
 use mem_rs::prelude::*;
 use crate::App;
-use crate::games::traits::buffered_event_flags::{BufferedEventFlags, EventFlag};
+use crate::games::traits::buffered_event_flags::{event_flag_state, BufferedEventFlags, EventFlag};
+use crate::games::traits::in_game_time::InGameTime;
+use crate::games::traits::player_position::PlayerPosition;
+use crate::games::physics_module::PhysicsModule;
+use crate::util::vector3f::Vector3f;
 use crate::games::dx_version::DxVersion;
+use crate::games::game_id::GameId;
+use crate::error::Error;
+use crate::games::{find_symbol, LiveMemory, PatternScan};
+use soulmemory_memory::{MemoryPointer, MemorySource};
+use soulmemory_memory::event_flags::{virtual_memory_flag, EventFlagSnapshot};
+use soulmemory_patterns::PatternFile;
+use crate::versions::GameVersion;
 use crate::games::game::Game;
 use crate::games::GameExt;
 
-type FnGetEventFlag = fn(event_flag_man: u64, event_flag: u32) -> u8;
+const PATTERNS: &str = include_str!("../../patterns/ArmoredCore6.toml");
+
+///Block id of the map the player is in, offset into PlayerIns
+const MAP_ID_OFFSET: usize = 0x6c0;
 
 pub struct ArmoredCore6
 {
     process: Process,
+    version: GameVersion,
 
     event_flags: Arc<Mutex<Vec<EventFlag>>>,
-    virtual_memory_flag: Pointer,
-    fn_get_event_flag: FnGetEventFlag,
+    virtual_memory_flag: MemoryPointer,
+    game_data_man: Pointer,
+    position: PhysicsModule,
+    player_ins: Pointer,
     set_event_flag_address: usize,
     set_event_flag_hook: Option<HookPoint>,
 }
@@ -50,10 +66,13 @@ impl ArmoredCore6
         ArmoredCore6
         {
             process: Process::new("armoredcore6.exe"),
+            version: GameVersion::default(),
 
             event_flags: Arc::new(Mutex::new(Vec::new())),
-            virtual_memory_flag: Pointer::default(),
-            fn_get_event_flag: |_,_|{0},
+            virtual_memory_flag: MemoryPointer::default(),
+            game_data_man: Pointer::default(),
+            position: PhysicsModule::default(),
+            player_ins: Pointer::default(),
             set_event_flag_address: 0,
             set_event_flag_hook: None,
         }
@@ -67,41 +86,91 @@ impl BufferedEventFlags for ArmoredCore6
         return &self.event_flags;
     }
 
-    fn get_event_flag_state(&self, event_flag: u32) -> bool {
-        let result = (self.fn_get_event_flag)(self.virtual_memory_flag.read_u64_rel(None), event_flag);
-        return result == 1;
+    fn get_event_flag_state(&self, event_flag: u32) -> bool
+    {
+        return event_flag_state(event_flag, virtual_memory_flag::get_event_flag(&LiveMemory::new(&self.process), &self.virtual_memory_flag, event_flag));
+    }
+
+    //Writes the bit directly, calling the game's set_event_flag from the overlay thread isn't safe.
+    //The hook doesn't see this write, so the flag is buffered here.
+    fn set_event_flag_state(&self, event_flag: u32, state: bool)
+    {
+        if let Err(e) = virtual_memory_flag::set_event_flag(&LiveMemory::new(&self.process), &self.virtual_memory_flag, event_flag, state)
+        {
+            warn!("{}", Error::from(e));
+            return;
+        }
+
+        let mut guard = self.event_flags.lock().unwrap();
+        guard.push(EventFlag::new(chrono::offset::Local::now(), event_flag, state));
+    }
+
+    fn snapshot_event_flags(&self) -> Result<EventFlagSnapshot, Error>
+    {
+        return Ok(virtual_memory_flag::snapshot(&LiveMemory::new(&self.process), &self.virtual_memory_flag)?);
     }
 }
 
 
 
 
+impl PlayerPosition for ArmoredCore6
+{
+    fn get_position(&self) -> Vector3f
+    {
+        return self.position.get_position();
+    }
+
+    fn set_position(&self, position: &Vector3f)
+    {
+        self.position.set_position(position);
+    }
+
+    fn get_map_id(&self) -> Option<u32>
+    {
+        //0 or -1 while no map is loaded
+        let map_id = self.player_ins.read_u32_rel(Some(self.version.offset("map_id", MAP_ID_OFFSET)));
+        return (map_id != 0 && map_id != u32::MAX).then_some(map_id);
+    }
+}
+
+impl InGameTime for ArmoredCore6
+{
+    fn get_in_game_time_milliseconds(&self) -> u32
+    {
+        return self.game_data_man.read_u32_rel(Some(self.version.offset("igt", 0xa0)));
+    }
+}
+
 impl Game for ArmoredCore6
 {
-    fn refresh(&mut self) -> Result<(), String>
+    fn refresh(&mut self) -> Result<(), Error>
     {
         unsafe
         {
             if !self.process.is_attached()
             {
                 self.process.refresh()?;
+                let patterns = PatternFile::parse(PATTERNS).map_err(Error::Patterns)?;
 
-                self.virtual_memory_flag = self.process.scan_rel("CSEventFlagMan", "48 8b 35 ? ? ? ? 83 f8 ff 0f 44 c1", 3, 7, vec![0])?;
+                self.virtual_memory_flag = LiveMemory::new(&self.process).scan_pointer("CSEventFlagMan", find_symbol(&patterns, &self.version, "CSEventFlagMan")?)?;
+                self.game_data_man = self.process.scan_symbol(&patterns, &self.version, "GameDataMan")?;
+                self.position = PhysicsModule::new(self.process.scan_symbol(&patterns, &self.version, "WorldChrMan")?, self.version.offset("position", 0x70), None);
+                self.player_ins = self.process.scan_symbol(&patterns, &self.version, "PlayerIns")?;
 
-                self.set_event_flag_address = self.process.scan_abs("set_event_flag", "48 89 5c 24 18 56 41 56 41 57 48 83 ec 20 44 8b 49 1c 44 8b f2", 0, Vec::new())?.get_base_address();
-                let get_event_flag_address = self.process.scan_abs("get_event_flag", "44 8b 41 1c 44 8b da 33 d2 41 8b c3 41 f7 f0 4c 8b d1 45 33 c9 44 0f af c0", 0, Vec::new())?.get_base_address();
-                self.fn_get_event_flag = mem::transmute(get_event_flag_address);
+                self.set_event_flag_address = self.process.scan_symbol(&patterns, &self.version, "set_event_flag")?.get_base_address();
 
                 #[cfg(target_arch = "x86_64")]
                 {
                     let h = Hooker::new(self.set_event_flag_address, HookType::JmpBack(set_event_flag_hook_fn), CallbackOption::None, 0, HookFlags::empty());
-                    self.set_event_flag_hook = Some(h.hook().unwrap());
+                    self.set_event_flag_hook = Some(h.hook().map_err(|e| Error::hook("set_event_flag", e))?);
                 }
 
 
-                info!("event_flag_man base address: 0x{:x}", self.virtual_memory_flag.get_base_address());
+                info!("event_flag_man base address: 0x{:x}", self.virtual_memory_flag.base);
+                info!("game_data_man base address: 0x{:x}", self.game_data_man.get_base_address());
+                info!("player position address: 0x{:x}", self.position.get_base_address());
                 info!("set event flag address     : 0x{:x}", self.set_event_flag_address);
-                info!("get event flag address     : 0x{:x}", get_event_flag_address);
             }
             else
             {
@@ -111,13 +180,15 @@ impl Game for ArmoredCore6
                 if byte == 0x48
                 {
                     info!("re-hook set event flag");
-                    let hookpoint = self.set_event_flag_hook.take();
-                    hookpoint.unwrap().unhook().unwrap();
+                    if let Some(hookpoint) = self.set_event_flag_hook.take()
+                    {
+                        hookpoint.unhook().map_err(|e| Error::hook("set_event_flag", e))?;
+                    }
 
                     #[cfg(target_arch = "x86_64")]
                     {
                         let h = Hooker::new(self.set_event_flag_address, HookType::JmpBack(set_event_flag_hook_fn), CallbackOption::None, 0, HookFlags::empty());
-                        self.set_event_flag_hook = Some(h.hook().unwrap());
+                        self.set_event_flag_hook = Some(h.hook().map_err(|e| Error::hook("set_event_flag", e))?);
                     }
                 }
 
@@ -130,8 +201,16 @@ impl Game for ArmoredCore6
     fn get_dx_version(&self) -> DxVersion {
         DxVersion::Dx12
     }
+
+    fn get_game_id(&self) -> GameId {
+        GameId::ArmoredCore6
+    }
     fn event_flags(&mut self) -> Option<Box<&mut dyn BufferedEventFlags>> { Some(Box::new(self)) }
+    fn in_game_time(&mut self) -> Option<Box<&mut dyn InGameTime>> { Some(Box::new(self)) }
+    fn player_position(&mut self) -> Option<Box<&mut dyn PlayerPosition>> { Some(Box::new(self)) }
+    fn hooks_input(&self) -> bool { true }
 
+    fn set_version(&mut self, version: GameVersion) { self.version = version; }
     fn as_any(&self) -> &dyn Any
     {
         self
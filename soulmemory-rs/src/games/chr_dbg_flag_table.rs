@@ -0,0 +1,81 @@
+// This file is part of the soulmemory-rs distribution (https://github.com/FrankvdStam/soulmemory-rs).
+// Copyright (c) 2022 Frank van der Stam.
+// https://github.com/FrankvdStam/soulmemory-rs/blob/main/LICENSE
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use log::{info, warn};
+use mem_rs::prelude::*;
+use soulmemory_patterns::PatternFile;
+use crate::games::{ChrDbgFlag, PatternScan};
+use crate::versions::GameVersion;
+
+///The debug flags are a block of bools, the flag id is the offset into that block. A table without flags means the
+///block wasn't found, the flags are left out of the overlay and the server then.
+pub struct ChrDbgFlagTable
+{
+    pointer: Pointer,
+    flags: &'static [(u32, &'static str)],
+}
+
+impl Default for ChrDbgFlagTable
+{
+    fn default() -> Self
+    {
+        ChrDbgFlagTable { pointer: Pointer::default(), flags: &[] }
+    }
+}
+
+impl ChrDbgFlagTable
+{
+    pub fn new(pointer: Pointer, flags: &'static [(u32, &'static str)]) -> Self
+    {
+        ChrDbgFlagTable { pointer, flags }
+    }
+
+    ///Scans for the "chr dbg" symbol. Not finding it only costs the flags, so it is logged instead of failing the refresh.
+    pub fn scan(process: &Process, patterns: &PatternFile, version: &GameVersion, flags: &'static [(u32, &'static str)]) -> Self
+    {
+        return match process.scan_symbol(patterns, version, "chr dbg")
+        {
+            Ok(pointer) =>
+            {
+                info!("chr dbg flags address  : 0x{:x}", pointer.get_base_address());
+                ChrDbgFlagTable::new(pointer, flags)
+            }
+            Err(e) =>
+            {
+                warn!("chr dbg flags not available: {}", e);
+                ChrDbgFlagTable::default()
+            }
+        };
+    }
+
+    pub fn get_base_address(&self) -> usize
+    {
+        return self.pointer.get_base_address();
+    }
+
+    pub fn get_flags(&self) -> Vec<ChrDbgFlag>
+    {
+        return self.flags.iter().map(|(flag, name)| (*flag, String::from(*name), self.pointer.read_u8_rel(Some(*flag as usize)) == 1)).collect();
+    }
+
+    pub fn set_flag(&self, flag: u32, value: bool)
+    {
+        if self.flags.iter().any(|(f, _)| *f == flag)
+        {
+            self.pointer.write_u8_rel(Some(flag as usize), value as u8);
+        }
+    }
+}
@@ -29,8 +29,19 @@ use mem_rs::pointer::Pointer;
 use mem_rs::prelude::Process;
 use crate::App;
 use crate::games::dx_version::DxVersion;
+use crate::games::game_id::GameId;
+use crate::error::Error;
+use crate::games::PatternScan;
+use soulmemory_patterns::PatternFile;
+use crate::versions::GameVersion;
 use crate::games::{Game, GameExt};
 use crate::games::traits::buffered_event_flags::{BufferedEventFlags, EventFlag};
+use crate::games::traits::in_game_time::InGameTime;
+use crate::games::traits::player_position::PlayerPosition;
+use crate::games::physics_module::PhysicsModule;
+use crate::util::vector3f::Vector3f;
+
+const PATTERNS: &str = include_str!("../../../patterns/DarkSouls2ScholarOfTheFirstSin.toml");
 
 #[cfg(target_arch = "x86")]//This version exists only to make things compile easily for x86
 type FnGetEventFlag = unsafe extern "thiscall" fn(event_flag_man: u64, event_flag: u32) -> u8;
@@ -38,14 +49,26 @@ type FnGetEventFlag = unsafe extern "thiscall" fn(event_flag_man: u64, event_fla
 #[cfg(target_arch = "x86_64")]
 type FnGetEventFlag = unsafe extern "win64" fn(event_flag_man: u64, event_flag: u32) -> u8;
 
+#[cfg(target_arch = "x86")]//This version exists only to make things compile easily for x86
+type FnSetEventFlag = unsafe extern "thiscall" fn(event_flag_man: u64, event_flag: u32, state: u8);
+
+#[cfg(target_arch = "x86_64")]
+type FnSetEventFlag = unsafe extern "win64" fn(event_flag_man: u64, event_flag: u32, state: u8);
+
 pub struct DarkSouls2ScholarOfTheFirstSin
 {
     process: Process,
+    version: GameVersion,
 
     event_flag_man: Pointer,
+    game_data_manager: Pointer,
+    position: PhysicsModule,
     event_flags: Arc<Mutex<Vec<EventFlag>>>,
+    ///Flags to set from the set_event_flag hook, see set_event_flag_state
+    pending_event_flags: Arc<Mutex<Vec<(u32, bool)>>>,
     set_event_flag_hook: Option<HookPoint>,
     fn_get_event_flag: FnGetEventFlag,
+    fn_set_event_flag: FnSetEventFlag,
 }
 
 impl DarkSouls2ScholarOfTheFirstSin
@@ -58,40 +81,78 @@ impl DarkSouls2ScholarOfTheFirstSin
         #[cfg(target_arch = "x86_64")]
         unsafe extern "win64" fn empty(_: u64, _: u32) -> u8 { 0 }
 
+        #[cfg(target_arch = "x86")]//This version exists only to make things compile easily for x86
+        unsafe extern "thiscall" fn empty_set(_: u64, _: u32, _: u8) {}
+
+        #[cfg(target_arch = "x86_64")]
+        unsafe extern "win64" fn empty_set(_: u64, _: u32, _: u8) {}
+
         DarkSouls2ScholarOfTheFirstSin
         {
             process: Process::new("darksoulsii.exe"),
+            version: GameVersion::default(),
 
             event_flag_man: Default::default(),
+            game_data_manager: Pointer::default(),
+            position: PhysicsModule::default(),
             event_flags: Arc::new(Mutex::new(vec![])),
+            pending_event_flags: Arc::new(Mutex::new(vec![])),
             set_event_flag_hook: None,
             fn_get_event_flag: empty,
+            fn_set_event_flag: empty_set,
         }
     }
 }
 
+impl PlayerPosition for DarkSouls2ScholarOfTheFirstSin
+{
+    fn get_position(&self) -> Vector3f
+    {
+        return self.position.get_position();
+    }
+
+    fn set_position(&self, position: &Vector3f)
+    {
+        self.position.set_position(position);
+    }
+}
+
+impl InGameTime for DarkSouls2ScholarOfTheFirstSin
+{
+    fn get_in_game_time_milliseconds(&self) -> u32
+    {
+        return self.game_data_manager.read_u32_rel(Some(0x1c));
+    }
+}
+
 impl Game for DarkSouls2ScholarOfTheFirstSin
 {
-    fn refresh(&mut self) -> Result<(), String>
+    fn refresh(&mut self) -> Result<(), Error>
     {
         if !self.process.is_attached()
         {
             unsafe
             {
                 self.process.refresh()?;
-                self.event_flag_man = self.process.scan_rel("GameDataMan" , "48 8b 35 ? ? ? ? 48 8b e9 48 85 f6", 3, 7, vec![0, 0x70, 0x20])?;
-                let get_event_flag_address = self.process.scan_abs("get_event_flag" , "44 8b d2 b8 ? ? ? ? f7 e2 44 8b ca", 0,  Vec::new())?.get_base_address();
-                let set_event_flag_address = self.process.scan_abs("set_event_flag" , "48 89 74 24 10 57 48 83 ec 20 8b fa 45 0f b6 d8", 0,  Vec::new())?.get_base_address();
+                let patterns = PatternFile::parse(PATTERNS).map_err(Error::Patterns)?;
+                self.event_flag_man = self.process.scan_symbol(&patterns, &self.version, "GameManagerImp event flags")?;
+                self.game_data_manager = self.process.scan_symbol(&patterns, &self.version, "GameManagerImp game data")?;
+                self.position = PhysicsModule::new(self.process.scan_symbol(&patterns, &self.version, "GameManagerImp position")?, 0xa0, None);
+                let get_event_flag_address = self.process.scan_symbol(&patterns, &self.version, "get_event_flag")?.get_base_address();
+                let set_event_flag_address = self.process.scan_symbol(&patterns, &self.version, "set_event_flag")?.get_base_address();
 
                 self.fn_get_event_flag = mem::transmute(get_event_flag_address);
+                self.fn_set_event_flag = mem::transmute(set_event_flag_address);
 
                 #[cfg(target_arch = "x86_64")]
                 {
                     let h = Hooker::new(set_event_flag_address, HookType::JmpBack(crate::games::dark_souls_2_scholar_of_the_first_sin::read_event_flag_hook_fn), CallbackOption::None, 0, HookFlags::empty());
-                    self.set_event_flag_hook = Some(h.hook().unwrap());
+                    self.set_event_flag_hook = Some(h.hook().map_err(|e| Error::hook("set_event_flag", e))?);
                 }
 
                 info!("event_flag_man base address: 0x{:x}", self.event_flag_man.get_base_address());
+                info!("game_data_manager address  : 0x{:x}", self.game_data_manager.get_base_address());
+                info!("player position address: 0x{:x}", self.position.get_base_address());
                 info!("get event flag address     : 0x{:x}", get_event_flag_address);
                 info!("set event flag address     : 0x{:x}", set_event_flag_address);
             }
@@ -105,8 +166,13 @@ impl Game for DarkSouls2ScholarOfTheFirstSin
 
     fn get_dx_version(&self) -> DxVersion { DxVersion::Dx11 }
 
+    fn get_game_id(&self) -> GameId { GameId::DarkSouls2ScholarOfTheFirstSin }
+
     fn event_flags(&mut self) -> Option<Box<&mut dyn BufferedEventFlags>> { Some(Box::new(self)) }
+    fn in_game_time(&mut self) -> Option<Box<&mut dyn InGameTime>> { Some(Box::new(self)) }
+    fn player_position(&mut self) -> Option<Box<&mut dyn PlayerPosition>> { Some(Box::new(self)) }
 
+    fn set_version(&mut self, version: GameVersion) { self.version = version; }
     fn as_any(&self) -> &dyn Any { self }
 
     fn as_any_mut(&mut self) -> &mut dyn Any { self }
@@ -125,5 +191,17 @@ unsafe extern "win64" fn read_event_flag_hook_fn(registers: *mut Registers, _:us
 
         let mut guard = scholar.event_flags.lock().unwrap();
         guard.push(EventFlag::new(chrono::offset::Local::now(), event_flag_id, value != 0));
+        drop(guard);
+
+        let pending = mem::take(&mut *scholar.pending_event_flags.lock().unwrap());
+        let fn_set_event_flag = scholar.fn_set_event_flag;
+        drop(app);
+
+        //The app has to be unlocked first, each of these calls runs this hook again
+        let event_flag_man = (*registers).rcx as _;
+        for (event_flag, state) in pending
+        {
+            fn_set_event_flag(event_flag_man, event_flag, state as u8);
+        }
     }
 }
\ No newline at end of file
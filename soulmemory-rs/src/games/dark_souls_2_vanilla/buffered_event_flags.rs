@@ -32,4 +32,11 @@ impl BufferedEventFlags for DarkSouls2Vanilla
         let result = unsafe { (self.fn_get_event_flag)(event_flag_man_address, event_flag) };
         return result == 1;
     }
+
+    //set_event_flag is hooked and the hook locks the app, which the callers of this already hold. Calling it from here
+    //would deadlock, so the write is queued and made by the hook on the game thread, the next time the game sets a flag.
+    fn set_event_flag_state(&self, event_flag: u32, state: bool)
+    {
+        self.pending_event_flags.lock().unwrap().push((event_flag, state));
+    }
 }
\ No newline at end of file
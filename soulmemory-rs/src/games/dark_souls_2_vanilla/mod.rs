@@ -26,10 +26,21 @@ use mem_rs::pointer::Pointer;
 use mem_rs::prelude::Process;
 use crate::App;
 use crate::games::dx_version::DxVersion;
+use crate::games::game_id::GameId;
+use crate::error::Error;
+use crate::games::PatternScan;
+use soulmemory_patterns::PatternFile;
+use crate::versions::GameVersion;
 use crate::games::{Game, GameExt};
 use crate::games::traits::buffered_event_flags::{BufferedEventFlags, EventFlag};
+use crate::games::traits::in_game_time::InGameTime;
+use crate::games::traits::player_position::PlayerPosition;
+use crate::games::physics_module::PhysicsModule;
+use crate::util::vector3f::Vector3f;
 use crate::util::{get_stack_u32, get_stack_u8};
 
+const PATTERNS: &str = include_str!("../../../patterns/DarkSouls2Vanilla.toml");
+
 #[cfg(target_arch = "x86")]
 type FnGetEventFlag = unsafe extern "thiscall" fn(event_flag_man: u32, event_flag: u32) -> u8;
 
@@ -37,14 +48,27 @@ type FnGetEventFlag = unsafe extern "thiscall" fn(event_flag_man: u32, event_fla
 #[cfg(target_arch = "x86_64")]
 type FnGetEventFlag = unsafe extern "win64" fn(event_flag_man: u32, event_flag: u32) -> u8;
 
+#[cfg(target_arch = "x86")]
+type FnSetEventFlag = unsafe extern "thiscall" fn(event_flag_man: u32, event_flag: u32, state: u8);
+
+//This version exists only to make things compile easily for x64
+#[cfg(target_arch = "x86_64")]
+type FnSetEventFlag = unsafe extern "win64" fn(event_flag_man: u32, event_flag: u32, state: u8);
+
 pub struct DarkSouls2Vanilla
 {
     process: Process,
+    version: GameVersion,
 
     event_flag_man: Pointer,
+    game_data_manager: Pointer,
+    position: PhysicsModule,
     event_flags: Arc<Mutex<Vec<EventFlag>>>,
+    ///Flags to set from the set_event_flag hook, see set_event_flag_state
+    pending_event_flags: Arc<Mutex<Vec<(u32, bool)>>>,
     set_event_flag_hook: Option<HookPoint>,
     fn_get_event_flag: FnGetEventFlag,
+    fn_set_event_flag: FnSetEventFlag,
 }
 
 impl DarkSouls2Vanilla
@@ -57,37 +81,75 @@ impl DarkSouls2Vanilla
         #[cfg(target_arch = "x86_64")]
         unsafe extern "win64" fn empty(_: u32, _: u32) -> u8 { 0 }
 
+        #[cfg(target_arch = "x86")]
+        unsafe extern "thiscall" fn empty_set(_: u32, _: u32, _: u8) {}
+
+        #[cfg(target_arch = "x86_64")]
+        unsafe extern "win64" fn empty_set(_: u32, _: u32, _: u8) {}
+
         DarkSouls2Vanilla
         {
             process: Process::new("darksoulsii.exe"),
+            version: GameVersion::default(),
 
             event_flag_man: Default::default(),
+            game_data_manager: Pointer::default(),
+            position: PhysicsModule::default(),
             event_flags: Arc::new(Mutex::new(vec![])),
+            pending_event_flags: Arc::new(Mutex::new(vec![])),
             set_event_flag_hook: None,
             fn_get_event_flag: empty,
+            fn_set_event_flag: empty_set,
         }
     }
 }
 
+impl PlayerPosition for DarkSouls2Vanilla
+{
+    fn get_position(&self) -> Vector3f
+    {
+        return self.position.get_position();
+    }
+
+    fn set_position(&self, position: &Vector3f)
+    {
+        self.position.set_position(position);
+    }
+}
+
+impl InGameTime for DarkSouls2Vanilla
+{
+    fn get_in_game_time_milliseconds(&self) -> u32
+    {
+        return self.game_data_manager.read_u32_rel(Some(0x1c));
+    }
+}
+
 impl Game for DarkSouls2Vanilla
 {
-    fn refresh(&mut self) -> Result<(), String>
+    fn refresh(&mut self) -> Result<(), Error>
     {
         if !self.process.is_attached()
         {
             unsafe
                 {
                     self.process.refresh()?;
-                    self.event_flag_man = self.process.scan_abs("GameManagerImp", "56 ff d2 c7 05 ? ? ? ? 00 00 00 00 5e", 5, vec![0, 0, 0x44, 0x10])?;
-                    let get_event_flag_address = self.process.scan_abs("get_event_flag", "55 8b ec 53 56 57 8b 7d 08 b8 ? ? ? ? f7", 0, Vec::new())?.get_base_address();
-                    let set_event_flag_address = self.process.scan_abs("set_event_flag", "55 8b ec 83 ec 08 53 56 8b 75 08 b8 ? ? ? ? f7", 0, Vec::new())?.get_base_address();
+                    let patterns = PatternFile::parse(PATTERNS).map_err(Error::Patterns)?;
+                    self.event_flag_man = self.process.scan_symbol(&patterns, &self.version, "GameManagerImp event flags")?;
+                    self.game_data_manager = self.process.scan_symbol(&patterns, &self.version, "GameManagerImp game data")?;
+                    self.position = PhysicsModule::new(self.process.scan_symbol(&patterns, &self.version, "GameManagerImp position")?, 0xa0, None);
+                    let get_event_flag_address = self.process.scan_symbol(&patterns, &self.version, "get_event_flag")?.get_base_address();
+                    let set_event_flag_address = self.process.scan_symbol(&patterns, &self.version, "set_event_flag")?.get_base_address();
 
                     self.fn_get_event_flag = mem::transmute(get_event_flag_address);
+                    self.fn_set_event_flag = mem::transmute(set_event_flag_address);
 
                     let h = Hooker::new(set_event_flag_address, HookType::JmpBack(set_event_flag_hook_fn), CallbackOption::None, 0, HookFlags::empty());
-                    self.set_event_flag_hook = Some(h.hook().unwrap());
+                    self.set_event_flag_hook = Some(h.hook().map_err(|e| Error::hook("set_event_flag", e))?);
 
                     info!("event_flag_man base address: 0x{:x}", self.event_flag_man.get_base_address());
+                    info!("game_data_manager address  : 0x{:x}", self.game_data_manager.get_base_address());
+                    info!("player position address: 0x{:x}", self.position.get_base_address());
                     info!("get event flag address     : 0x{:x}", get_event_flag_address);
                     info!("set event flag address     : 0x{:x}", set_event_flag_address);
                 }
@@ -99,8 +161,13 @@ impl Game for DarkSouls2Vanilla
 
     fn get_dx_version(&self) -> DxVersion { DxVersion::Dx9 }
 
+    fn get_game_id(&self) -> GameId { GameId::DarkSouls2Vanilla }
+
     fn event_flags(&mut self) -> Option<Box<&mut dyn BufferedEventFlags>> { Some(Box::new(self)) }
+    fn in_game_time(&mut self) -> Option<Box<&mut dyn InGameTime>> { Some(Box::new(self)) }
+    fn player_position(&mut self) -> Option<Box<&mut dyn PlayerPosition>> { Some(Box::new(self)) }
 
+    fn set_version(&mut self, version: GameVersion) { self.version = version; }
     fn as_any(&self) -> &dyn Any { self }
 
     fn as_any_mut(&mut self) -> &mut dyn Any { self }
@@ -118,5 +185,17 @@ unsafe extern "cdecl" fn set_event_flag_hook_fn(registers: *mut Registers, _:usi
 
         let mut guard = vanilla.event_flags.lock().unwrap();
         guard.push(EventFlag::new(chrono::offset::Local::now(), event_flag_id, value != 0));
+        drop(guard);
+
+        let pending = mem::take(&mut *vanilla.pending_event_flags.lock().unwrap());
+        let fn_set_event_flag = vanilla.fn_set_event_flag;
+        drop(app);
+
+        //The app has to be unlocked first, each of these calls runs this hook again
+        let event_flag_man = (*registers).ecx as _;
+        for (event_flag, state) in pending
+        {
+            fn_set_event_flag(event_flag_man, event_flag, state as u8);
+        }
     }
 }
\ No newline at end of file
@@ -19,25 +19,40 @@
 
 
 use std::any::Any;
-use std::mem;
 use std::ops::Deref;
 use std::sync::{Arc, Mutex};
 use ilhook::x64::{CallbackOption, Hooker, HookFlags, HookPoint, HookType, Registers};
-use log::info;
+use log::{info, warn};
 use mem_rs::prelude::*;
 use crate::App;
 use crate::games::dx_version::DxVersion;
-use crate::games::traits::buffered_event_flags::{BufferedEventFlags, EventFlag};
+use crate::games::game_id::GameId;
+use crate::error::Error;
+use crate::games::{find_symbol, LiveMemory, PatternScan};
+use soulmemory_memory::{MemoryPointer, MemorySource};
+use soulmemory_memory::event_flags::{sprj, EventFlagSnapshot};
+use soulmemory_patterns::PatternFile;
+use crate::versions::GameVersion;
+use crate::games::traits::buffered_event_flags::{event_flag_state, BufferedEventFlags, EventFlag};
+use crate::games::traits::in_game_time::InGameTime;
+use crate::games::traits::player_position::PlayerPosition;
+use crate::games::physics_module::PhysicsModule;
+use crate::util::vector3f::Vector3f;
 use crate::games::game::Game;
 use crate::games::{GameExt};
 
+const PATTERNS: &str = include_str!("../../patterns/DarkSouls3.toml");
+
 pub struct DarkSouls3
 {
     process: Process,
+    version: GameVersion,
 
     event_flags: Arc<Mutex<Vec<EventFlag>>>,
-    event_flag_man: Pointer,
-    fn_get_event_flag: fn(event_flag_man: u64, event_flag: u32) -> u8,
+    event_flag_man: MemoryPointer,
+    field_area: MemoryPointer,
+    game_data_man: Pointer,
+    position: PhysicsModule,
     set_event_flag_hook: Option<HookPoint>,
 }
 
@@ -48,10 +63,13 @@ impl DarkSouls3
         DarkSouls3
         {
             process: Process::new("darksoulsiii.exe"),
+            version: GameVersion::default(),
 
             event_flags: Arc::new(Mutex::new(Vec::new())),
-            event_flag_man: Pointer::default(),
-            fn_get_event_flag: |_,_|{0},
+            event_flag_man: MemoryPointer::default(),
+            field_area: MemoryPointer::default(),
+            game_data_man: Pointer::default(),
+            position: PhysicsModule::default(),
             set_event_flag_hook: None,
         }
     }
@@ -64,43 +82,91 @@ impl BufferedEventFlags for DarkSouls3
         return &self.event_flags;
     }
 
-    fn get_event_flag_state(&self, event_flag: u32) -> bool {
-        let result = (self.fn_get_event_flag)(self.event_flag_man.read_u64_rel(None), event_flag);
-        return result == 1;
+    fn get_event_flag_state(&self, event_flag: u32) -> bool
+    {
+        return event_flag_state(event_flag, sprj::get_event_flag(&LiveMemory::new(&self.process), &self.event_flag_man, &self.field_area, &sprj::DARK_SOULS_3, event_flag));
+    }
+
+    //Writes the bit directly, calling the game's set_event_flag from the overlay thread isn't safe.
+    //The hook doesn't see this write, so the flag is buffered here.
+    fn set_event_flag_state(&self, event_flag: u32, state: bool)
+    {
+        if let Err(e) = sprj::set_event_flag(&LiveMemory::new(&self.process), &self.event_flag_man, &self.field_area, &sprj::DARK_SOULS_3, event_flag, state)
+        {
+            warn!("{}", Error::from(e));
+            return;
+        }
+
+        let mut guard = self.event_flags.lock().unwrap();
+        guard.push(EventFlag::new(chrono::offset::Local::now(), event_flag, state));
+    }
+
+    fn snapshot_event_flags(&self) -> Result<EventFlagSnapshot, Error>
+    {
+        return Ok(sprj::snapshot(&LiveMemory::new(&self.process), &self.event_flag_man, &self.field_area, &sprj::DARK_SOULS_3)?);
+    }
+}
+
+impl PlayerPosition for DarkSouls3
+{
+    fn get_position(&self) -> Vector3f
+    {
+        return self.position.get_position();
+    }
+
+    fn set_position(&self, position: &Vector3f)
+    {
+        self.position.set_position(position);
+    }
+
+    fn get_angle(&self) -> Option<f32>
+    {
+        return self.position.get_angle();
+    }
+
+    fn set_angle(&self, angle: f32)
+    {
+        self.position.set_angle(angle);
+    }
+}
+
+impl InGameTime for DarkSouls3
+{
+    fn get_in_game_time_milliseconds(&self) -> u32
+    {
+        return self.game_data_man.read_u32_rel(Some(self.version.offset("igt", 0xa4)));
     }
 }
 
 impl Game for DarkSouls3
 {
-    fn refresh(&mut self) -> Result<(), String> {
+    fn refresh(&mut self) -> Result<(), Error> {
         if !self.process.is_attached()
         {
             unsafe
             {
                 self.process.refresh()?;
+                let patterns = PatternFile::parse(PATTERNS).map_err(Error::Patterns)?;
 
 
-                self.event_flag_man = self.process.scan_rel("SprjEventFlagMan", "48 c7 05 ? ? ? ? 00 00 00 00 48 8b 7c 24 38 c7 46 54 ff ff ff ff 48 83 c4 20 5e c3", 3, 11, vec![0])?;
-                //.ScanRelative("playerIns", "48 8b 0d ? ? ? ? 45 33 c0 48 8d 55 e7 e8 ? ? ? ? 0f 2f 73 70 72 0d f3 ? ? ? ? ? ? ? ? 0f 11 43 70", 3, 7)
-                //.CreatePointer(out _playerIns, 0, 0x80)
-                //.CreatePointer(out _sprjChrPhysicsModule, 0, 0x40, 0x28) -> position
-                //_sprjChrPhysicsModule.ReadFloat(0x80),
-                //_sprjChrPhysicsModule.ReadFloat(0x84),
-                //_sprjChrPhysicsModule.ReadFloat(0x88)
+                self.event_flag_man = LiveMemory::new(&self.process).scan_pointer("SprjEventFlagMan", find_symbol(&patterns, &self.version, "SprjEventFlagMan")?)?;
+                self.field_area = LiveMemory::new(&self.process).scan_pointer("FieldArea", find_symbol(&patterns, &self.version, "FieldArea")?)?;
+                self.game_data_man = self.process.scan_symbol(&patterns, &self.version, "GameDataMan")?;
+                self.position = PhysicsModule::new(self.process.scan_symbol(&patterns, &self.version, "WorldChrMan")?, self.version.offset("position", 0x80), Some(self.version.offset("angle", 0x74)));
 
-                let set_event_flag_address = self.process.scan_abs("set_event_flag", "40 55 57 41 54 41 57 48 83 ec 58 80 b9 28 02 00 00 00 45 0f b6 f9 45 0f b6 e0 8b ea 48 8b f9", 0, Vec::new())?.get_base_address();
-                let get_event_flag_address = self.process.scan_abs("get_event_flag", "40 53 48 83 ec 20 80 b9 28 02 00 00 00 8b da 74 4d", 0, Vec::new())?.get_base_address();
-                self.fn_get_event_flag = mem::transmute(get_event_flag_address);
+                let set_event_flag_address = self.process.scan_symbol(&patterns, &self.version, "set_event_flag")?.get_base_address();
 
                 #[cfg(target_arch = "x86_64")]
                 {
                     let h = Hooker::new(set_event_flag_address, HookType::JmpBack(set_event_flag_hook_fn), CallbackOption::None, 0, HookFlags::empty());
-                    self.set_event_flag_hook = Some(h.hook().unwrap());
+                    self.set_event_flag_hook = Some(h.hook().map_err(|e| Error::hook("set_event_flag", e))?);
                 }
 
-                info!("event_flag_man base address: 0x{:x}", self.event_flag_man.get_base_address());
+                info!("game_data_man base address : 0x{:x}", self.game_data_man.get_base_address());
+                info!("event_flag_man base address: 0x{:x}", self.event_flag_man.base);
+                info!("field_area base address    : 0x{:x}", self.field_area.base);
+                info!("player position address: 0x{:x}", self.position.get_base_address());
                 info!("set event flag address     : 0x{:x}", set_event_flag_address);
-                info!("get event flag address     : 0x{:x}", get_event_flag_address);
             }
         }
         else
@@ -113,8 +179,16 @@ impl Game for DarkSouls3
     fn get_dx_version(&self) -> DxVersion {
         DxVersion::Dx11
     }
+
+    fn get_game_id(&self) -> GameId {
+        GameId::DarkSouls3
+    }
     fn event_flags(&mut self) -> Option<Box<&mut dyn BufferedEventFlags>> { Some(Box::new(self)) }
+    fn in_game_time(&mut self) -> Option<Box<&mut dyn InGameTime>> { Some(Box::new(self)) }
+    fn player_position(&mut self) -> Option<Box<&mut dyn PlayerPosition>> { Some(Box::new(self)) }
+    fn hooks_input(&self) -> bool { true }
 
+    fn set_version(&mut self, version: GameVersion) { self.version = version; }
     fn as_any(&self) -> &dyn Any
     {
         self
@@ -17,21 +17,37 @@
 use std::any::Any;
 use std::ops::Deref;
 use std::sync::{Arc, Mutex};
-use log::info;
+use log::{info, warn};
 use mem_rs::pointer::Pointer;
 use mem_rs::prelude::{Process, ReadWrite};
 use ilhook::x86::{Hooker, HookType, Registers, CallbackOption, HookFlags, HookPoint};
 use crate::App;
 use crate::games::dx_version::DxVersion;
-use crate::games::traits::buffered_event_flags::{BufferedEventFlags, EventFlag};
+use crate::games::game_id::GameId;
+use crate::error::Error;
+use crate::games::{find_symbol, LiveMemory, PatternScan};
+use soulmemory_memory::{MemoryPointer, MemorySource};
+use soulmemory_memory::event_flags::{dark_souls_1, EventFlagSnapshot};
+use soulmemory_patterns::PatternFile;
+use crate::versions::GameVersion;
+use crate::games::traits::buffered_event_flags::{event_flag_state, BufferedEventFlags, EventFlag};
+use crate::games::traits::in_game_time::InGameTime;
+use crate::games::traits::player_position::PlayerPosition;
+use crate::games::physics_module::PhysicsModule;
+use crate::util::vector3f::Vector3f;
 use crate::games::game::{Game};
 use crate::games::game_ext::GameExt;
 use crate::util::{get_stack_u32, get_stack_u8};
 
+const PATTERNS: &str = include_str!("../../patterns/DarkSoulsPrepareToDieEdition.toml");
+
 pub struct DarkSoulsPrepareToDieEdition
 {
     process: Process,
-    event_flag_man: Pointer,
+    version: GameVersion,
+    event_flag_man: MemoryPointer,
+    game_data_man: Pointer,
+    position: PhysicsModule,
     event_flags: Arc<Mutex<Vec<EventFlag>>>,
     set_event_flag_hook: Option<HookPoint>,
 }
@@ -43,7 +59,10 @@ impl DarkSoulsPrepareToDieEdition
         DarkSoulsPrepareToDieEdition
         {
             process: Process::new("darksouls.exe"),
-            event_flag_man: Pointer::default(),
+            version: GameVersion::default(),
+            event_flag_man: MemoryPointer::default(),
+            game_data_man: Pointer::default(),
+            position: PhysicsModule::default(),
             event_flags: Arc::new(Mutex::new(Vec::new())),
             set_event_flag_hook: None,
         }
@@ -59,29 +78,82 @@ impl BufferedEventFlags for DarkSoulsPrepareToDieEdition
 
     fn get_event_flag_state(&self, event_flag: u32) -> bool
     {
-        let (offset, mask) = get_event_flag_offset(event_flag);
-        let value = self.event_flag_man.read_u32_rel(Some(offset)) as usize;
-        return (value & mask) != 0;
+        return event_flag_state(event_flag, dark_souls_1::get_event_flag(&LiveMemory::new(&self.process), &self.event_flag_man, event_flag));
+    }
+
+    //set_event_flag uses a custom calling convention as well, write the bit directly instead.
+    //The hook doesn't see this write, so the flag is buffered here.
+    fn set_event_flag_state(&self, event_flag: u32, state: bool)
+    {
+        if let Err(e) = dark_souls_1::set_event_flag(&LiveMemory::new(&self.process), &self.event_flag_man, event_flag, state)
+        {
+            warn!("{}", Error::from(e));
+            return;
+        }
+
+        let mut guard = self.event_flags.lock().unwrap();
+        guard.push(EventFlag::new(chrono::offset::Local::now(), event_flag, state));
+    }
+
+    fn snapshot_event_flags(&self) -> Result<EventFlagSnapshot, Error>
+    {
+        return Ok(dark_souls_1::snapshot(&LiveMemory::new(&self.process), &self.event_flag_man)?);
+    }
+}
+
+
+impl PlayerPosition for DarkSoulsPrepareToDieEdition
+{
+    fn get_position(&self) -> Vector3f
+    {
+        return self.position.get_position();
+    }
+
+    fn set_position(&self, position: &Vector3f)
+    {
+        self.position.set_position(position);
+    }
+
+    fn get_angle(&self) -> Option<f32>
+    {
+        return self.position.get_angle();
+    }
+
+    fn set_angle(&self, angle: f32)
+    {
+        self.position.set_angle(angle);
     }
 }
 
+impl InGameTime for DarkSoulsPrepareToDieEdition
+{
+    fn get_in_game_time_milliseconds(&self) -> u32
+    {
+        return self.game_data_man.read_u32_rel(Some(self.version.offset("igt", 0x68)));
+    }
+}
 
 impl Game for DarkSoulsPrepareToDieEdition
 {
-    fn refresh(&mut self) -> Result<(), String>
+    fn refresh(&mut self) -> Result<(), Error>
     {
         if !self.process.is_attached()
         {
             unsafe
             {
                 self.process.refresh()?;
-                self.event_flag_man = self.process.scan_abs("event flags", "56 8B F1 8B 46 1C 50 A1 ? ? ? ? 32 C9", 8, vec![0, 0, 0])?;
-                let set_event_flag_address = self.process.scan_abs("set_event_flag", "80 b8 14 01 00 00 00 56 8b 74 24 08 74 ? 57 51 50", 0, Vec::new())?.get_base_address();
+                let patterns = PatternFile::parse(PATTERNS).map_err(Error::Patterns)?;
+                self.event_flag_man = LiveMemory::new(&self.process).scan_pointer("event flags", find_symbol(&patterns, &self.version, "event flags")?)?;
+                self.game_data_man  = self.process.scan_symbol(&patterns, &self.version, "GameDataMan")?;
+                self.position = PhysicsModule::new(self.process.scan_symbol(&patterns, &self.version, "WorldChrMan")?, self.version.offset("position", 0x10), Some(self.version.offset("angle", 0x4)));
+                let set_event_flag_address = self.process.scan_symbol(&patterns, &self.version, "set_event_flag")?.get_base_address();
 
                 let h = Hooker::new(set_event_flag_address, HookType::JmpBack(capture_the_flag), CallbackOption::None, 0, HookFlags::empty());
-                self.set_event_flag_hook = Some(h.hook().unwrap());
+                self.set_event_flag_hook = Some(h.hook().map_err(|e| Error::hook("set_event_flag", e))?);
 
-                info!("event_flag_man base address: 0x{:x}", self.event_flag_man.get_base_address());
+                info!("game_data_man base address : 0x{:x}", self.game_data_man.get_base_address());
+                info!("event_flag_man base address: 0x{:x}", self.event_flag_man.base);
+                info!("player position address: 0x{:x}", self.position.get_base_address());
                 info!("set event flag address     : 0x{:x}", set_event_flag_address);
             }
         }
@@ -94,8 +166,15 @@ impl Game for DarkSoulsPrepareToDieEdition
     fn get_dx_version(&self) -> DxVersion {
         DxVersion::Dx9
     }
+
+    fn get_game_id(&self) -> GameId {
+        GameId::DarkSoulsPrepareToDieEdition
+    }
     fn event_flags(&mut self) -> Option<Box<&mut dyn BufferedEventFlags>> { Some(Box::new(self)) }
+    fn in_game_time(&mut self) -> Option<Box<&mut dyn InGameTime>> { Some(Box::new(self)) }
+    fn player_position(&mut self) -> Option<Box<&mut dyn PlayerPosition>> { Some(Box::new(self)) }
 
+    fn set_version(&mut self, version: GameVersion) { self.version = version; }
     fn as_any(&self) -> &dyn Any
     {
         self
@@ -103,66 +182,6 @@ impl Game for DarkSoulsPrepareToDieEdition
     fn as_any_mut(&mut self) -> &mut dyn Any { self }
 }
 
-//Credit to JKAnderson for the event flag reading code, https://github.com/JKAnderson/DS-Gadget
-//Implementing it this way over calling the read event flag function, because it is a custom calling convention
-fn get_event_flag_group(group: &String) -> usize
-{
-    return match group.as_str()
-    {
-        "0" => 0x00000,
-        "1" => 0x00500,
-        "5" => 0x05F00,
-        "6" => 0x0B900,
-        "7" => 0x11300,
-        _ => panic!("Invalid group {}", group),
-    }
-}
-
-fn get_event_flag_area(area: &String) -> usize
-{
-    return match area.as_str()
-    {
-        "000" => 00,
-        "100" => 01,
-        "101" => 02,
-        "102" => 03,
-        "110" => 04,
-        "120" => 05,
-        "121" => 06,
-        "130" => 07,
-        "131" => 08,
-        "132" => 09,
-        "140" => 10,
-        "141" => 11,
-        "150" => 12,
-        "151" => 13,
-        "160" => 14,
-        "170" => 15,
-        "180" => 16,
-        "181" => 17,
-        _ => panic!("Invalid area {}", area),
-    }
-}
-
-fn get_event_flag_offset(even_flag_id: u32) -> (usize, usize)
-{
-    let flag_str = format!("{:0>8}", even_flag_id);
-    let group = flag_str[..1].to_string();
-    let area = flag_str[1..4].to_string();
-    let section_str = flag_str[4..5].to_string();
-    let number_str = flag_str[5..].to_string();
-    let section = section_str.parse::<usize>().unwrap();
-    let number = number_str.parse::<usize>().unwrap();
-
-    let mut offset = get_event_flag_group(&group);
-    offset += get_event_flag_area(&area) * 0x500;
-    offset += section * 128;
-    offset += (number - (number % 32)) / 8;
-
-    let mask = 0x80000000 >> (number % 32);
-    return (offset, mask);
-}
-
 unsafe extern "cdecl" fn capture_the_flag(reg:*mut Registers, _:usize)
 {
     let instance = App::get_instance();
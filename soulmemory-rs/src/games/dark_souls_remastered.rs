@@ -18,41 +18,47 @@
 #![allow(unused_imports)]
 
 
-use std::{mem};
 use std::any::Any;
 use std::ops::Deref;
 use std::sync::{Arc, Mutex};
 use ilhook::x64::HookPoint;
-use log::info;
+use log::{info, warn};
 use mem_rs::prelude::*;
-use windows::Win32::UI::Input::XboxController::XINPUT_STATE;
 use crate::App;
 use crate::games::dx_version::DxVersion;
+use crate::games::game_id::GameId;
+use crate::error::Error;
+use crate::games::{find_symbol, LiveMemory, PatternScan};
+use soulmemory_memory::{MemoryPointer, MemorySource};
+use soulmemory_memory::event_flags::{dark_souls_1, EventFlagSnapshot};
+use soulmemory_patterns::PatternFile;
+use crate::versions::GameVersion;
 use crate::games::game::Game;
 use crate::games::GameExt;
 use crate::games::ilhook::*;
-use crate::tas::tas::{get_xinput_get_state_fn_address, tas_ai_toggle, XInputGetState};
-use crate::tas::toggle_mode::ToggleMode;
-use crate::games::traits::buffered_event_flags::{BufferedEventFlags, EventFlag};
+use crate::games::traits::buffered_event_flags::{event_flag_state, BufferedEventFlags, EventFlag};
+use crate::games::traits::in_game_time::InGameTime;
+use crate::games::traits::ai_timer::AiTimer;
+use crate::games::traits::player_position::PlayerPosition;
+use crate::games::physics_module::PhysicsModule;
+use crate::util::vector3f::Vector3f;
 
 
-type FnGetEventFlag = fn(event_flag_man: u64, event_flag: u32) -> u8;
+const PATTERNS: &str = include_str!("../../patterns/DarkSoulsRemastered.toml");
+
 pub struct DarkSoulsRemastered
 {
     process: Process,
+    version: GameVersion,
 
     ai_timer: Pointer,
     game_data_man: Pointer,
+    position: PhysicsModule,
 
-    event_flag_man: Pointer,
-    fn_get_event_flag: FnGetEventFlag,
+    event_flag_man: MemoryPointer,
     event_flags: Arc<Mutex<Vec<EventFlag>>>,
 
     set_event_flag_hook: Option<HookPoint>,
-    xinput_get_state_hook: Option<HookPoint>,
-
-    pub ai_timer_toggle_threshold: f32,
-    pub ai_timer_toggle_mode: ToggleMode,
 }
 
 impl DarkSoulsRemastered
@@ -62,30 +68,56 @@ impl DarkSoulsRemastered
         DarkSoulsRemastered
         {
             process: Process::new("DarkSoulsRemastered.exe"),
+            version: GameVersion::default(),
 
             ai_timer: Pointer::default(),
             game_data_man: Pointer::default(),
+            position: PhysicsModule::default(),
 
-            event_flag_man: Pointer::default(),
-            fn_get_event_flag: |_,_|{return 0},
+            event_flag_man: MemoryPointer::default(),
             event_flags: Arc::new(Mutex::new(Vec::new())),
 
             set_event_flag_hook: None,
-            xinput_get_state_hook: None,
-
-            ai_timer_toggle_threshold: 4.8f32,
-            ai_timer_toggle_mode: ToggleMode::None,
         }
     }
+}
+
+impl AiTimer for DarkSoulsRemastered
+{
+    fn get_ai_timer_value(&self) -> f32
+    {
+        self.ai_timer.read_f32_rel(Some(self.version.offset("ai_timer", 0x24)))
+    }
+}
+
+impl PlayerPosition for DarkSoulsRemastered
+{
+    fn get_position(&self) -> Vector3f
+    {
+        return self.position.get_position();
+    }
+
+    fn set_position(&self, position: &Vector3f)
+    {
+        self.position.set_position(position);
+    }
 
-    pub fn get_in_game_time_milliseconds(&self) -> u32
+    fn get_angle(&self) -> Option<f32>
     {
-        return self.game_data_man.read_u32_rel(Some(0xa4));
+        return self.position.get_angle();
     }
 
-    pub fn get_ai_timer_value(&self) -> f32
+    fn set_angle(&self, angle: f32)
     {
-        self.ai_timer.read_f32_rel(Some(0x24))
+        self.position.set_angle(angle);
+    }
+}
+
+impl InGameTime for DarkSoulsRemastered
+{
+    fn get_in_game_time_milliseconds(&self) -> u32
+    {
+        return self.game_data_man.read_u32_rel(Some(self.version.offset("igt", 0xa4)));
     }
 }
 
@@ -97,43 +129,61 @@ impl BufferedEventFlags for DarkSoulsRemastered
     }
     fn get_event_flag_state(&self, event_flag: u32) -> bool
     {
-        let event_flag_man_address = self.event_flag_man.read_u32_rel(None) as u64; //Bit memes because DSR is 64bit, compiled with 32bit wide pointers
-        let result = (self.fn_get_event_flag)(event_flag_man_address, event_flag);
-        return result == 1;
+        let memory = LiveMemory::new(&self.process);
+        return event_flag_state(event_flag, dark_souls_1::remastered_storage(&memory, &self.event_flag_man).and_then(|storage| dark_souls_1::get_event_flag(&memory, &storage, event_flag)));
+    }
+
+    //Writes the bit directly, calling the game's set_event_flag from the overlay thread isn't safe.
+    //The hook doesn't see this write, so the flag is buffered here.
+    fn set_event_flag_state(&self, event_flag: u32, state: bool)
+    {
+        let memory = LiveMemory::new(&self.process);
+        if let Err(e) = dark_souls_1::remastered_storage(&memory, &self.event_flag_man).and_then(|storage| dark_souls_1::set_event_flag(&memory, &storage, event_flag, state))
+        {
+            warn!("{}", Error::from(e));
+            return;
+        }
+
+        let mut guard = self.event_flags.lock().unwrap();
+        guard.push(EventFlag::new(chrono::offset::Local::now(), event_flag, state));
+    }
+
+    fn snapshot_event_flags(&self) -> Result<EventFlagSnapshot, Error>
+    {
+        let memory = LiveMemory::new(&self.process);
+        let storage = dark_souls_1::remastered_storage(&memory, &self.event_flag_man)?;
+        return Ok(dark_souls_1::snapshot(&memory, &storage)?);
     }
 }
 
 impl Game for DarkSoulsRemastered
 {
-    fn refresh(&mut self) -> Result<(), String>
+    fn refresh(&mut self) -> Result<(), Error>
     {
         if !self.process.is_attached()
         {
             unsafe
             {
                 self.process.refresh()?;
-                self.game_data_man  = self.process.scan_rel("GameDataMan", "48 8b 05 ? ? ? ? 48 8b 50 10 48 89 54 24 60", 3, 7, vec![0])?;
-                self.ai_timer       = self.process.scan_rel("ai timer", "48 8b 0d ? ? ? ? 48 85 c9 74 0e 48 83 c1 28", 3, 7, vec![0])?;
-                self.event_flag_man = self.process.scan_rel("event flags", "48 8B 0D ? ? ? ? 99 33 C2 45 33 C0 2B C2 8D 50 F6", 3, 7, vec![0])?;
+                let patterns = PatternFile::parse(PATTERNS).map_err(Error::Patterns)?;
+                self.game_data_man  = self.process.scan_symbol(&patterns, &self.version, "GameDataMan")?;
+                self.ai_timer       = self.process.scan_symbol(&patterns, &self.version, "ai timer")?;
+                self.event_flag_man = LiveMemory::new(&self.process).scan_pointer("event flags", find_symbol(&patterns, &self.version, "event flags")?)?;
+                self.position       = PhysicsModule::new(self.process.scan_symbol(&patterns, &self.version, "WorldChrMan")?, self.version.offset("position", 0x10), Some(self.version.offset("angle", 0x4)));
 
-                let set_event_flag_address = self.process.scan_abs("set_event_flag", "48 89 5c 24 08 57 48 83 ec 20 80 b9 24 02 00 00 00 41 0f b6 f8", 0, Vec::new())?.get_base_address();
-                let get_event_flag_address = self.process.scan_abs("get_event_flag", "40 53 48 83 ec 20 80 b9 24 02 00 00 00 8b da 74 4d", 0, Vec::new())?.get_base_address();
-                self.fn_get_event_flag = mem::transmute(get_event_flag_address);
+                let set_event_flag_address = self.process.scan_symbol(&patterns, &self.version, "set_event_flag")?.get_base_address();
 
                 #[cfg(target_arch = "x86_64")]
                 {
                     let h = Hooker::new(set_event_flag_address, HookType::JmpBack(set_event_flag_hook_fn), CallbackOption::None, 0, HookFlags::empty());
-                    self.set_event_flag_hook = Some(h.hook().unwrap());
-
-                    let h = Hooker::new(get_xinput_get_state_fn_address() as usize, HookType::Retn(xinput_get_state_hook_fn), CallbackOption::None, 0, HookFlags::empty());
-                    self.xinput_get_state_hook = Some(h.hook().unwrap());
+                    self.set_event_flag_hook = Some(h.hook().map_err(|e| Error::hook("set_event_flag", e))?);
                 }
 
                 info!("game_data_man base address : 0x{:x}", self.game_data_man.get_base_address());
                 info!("ai_timer base address      : 0x{:x}", self.ai_timer.get_base_address());
-                info!("event_flag_man base address: 0x{:x}", self.event_flag_man.get_base_address());
+                info!("event_flag_man base address: 0x{:x}", self.event_flag_man.base);
+                info!("player position address: 0x{:x}", self.position.get_base_address());
                 info!("set event flag address     : 0x{:x}", set_event_flag_address);
-                info!("get event flag address     : 0x{:x}", get_event_flag_address);
             }
         }
         else
@@ -147,8 +197,17 @@ impl Game for DarkSoulsRemastered
         DxVersion::Dx11
     }
 
+    fn get_game_id(&self) -> GameId {
+        GameId::DarkSoulsRemastered
+    }
+
     fn event_flags(&mut self) -> Option<Box<&mut dyn BufferedEventFlags>> { Some(Box::new(self)) }
+    fn in_game_time(&mut self) -> Option<Box<&mut dyn InGameTime>> { Some(Box::new(self)) }
+    fn player_position(&mut self) -> Option<Box<&mut dyn PlayerPosition>> { Some(Box::new(self)) }
+    fn ai_timer(&mut self) -> Option<Box<&mut dyn AiTimer>> { Some(Box::new(self)) }
+    fn hooks_input(&self) -> bool { true }
 
+    fn set_version(&mut self, version: GameVersion) { self.version = version; }
     fn as_any(&self) -> &dyn Any
     {
         self
@@ -170,24 +229,4 @@ unsafe extern "win64" fn set_event_flag_hook_fn(registers: *mut Registers, _:usi
         let mut guard = vanilla.event_flags.lock().unwrap();
         guard.push(EventFlag::new(chrono::offset::Local::now(), event_flag_id, value != 0));
     }
-}
-
-#[cfg(target_arch = "x86_64")]
-unsafe extern "win64" fn xinput_get_state_hook_fn(registers: *mut Registers, ori_func_ptr: usize, _: usize) -> usize
-{
-    let original_func: XInputGetState = mem::transmute(ori_func_ptr);
-
-    let instance = App::get_instance();
-    let app = instance.lock().unwrap();
-
-    if let Some(dsr) = GameExt::get_game_ref::<DarkSoulsRemastered>(app.game.deref())
-    {
-        let dw_user_index = (*registers).rcx as u32;
-        let p_state = (*registers).rdx as *mut XINPUT_STATE;
-
-        let res = original_func(dw_user_index, p_state);
-        tas_ai_toggle(dsr.ai_timer_toggle_mode, dsr.get_ai_timer_value(), dsr.ai_timer_toggle_threshold, p_state);
-        return res as usize;
-    }
-    panic!("Failed to resolve DSR");
 }
\ No newline at end of file
@@ -1,14 +1,45 @@
 use std::any::Any;
 use crate::games::dx_version::DxVersion;
+use crate::games::game_id::GameId;
+use crate::error::Error;
 use crate::games::traits::buffered_event_flags::BufferedEventFlags;
 use crate::games::traits::player_position::PlayerPosition;
+use crate::games::traits::in_game_time::InGameTime;
+use crate::games::traits::ai_timer::AiTimer;
+use crate::games::traits::quitout::Quitout;
+use crate::games::traits::capability::Capability;
+use crate::games::GetSetChrDbgFlags;
+use crate::versions::GameVersion;
 
 pub trait Game
 {
-    fn refresh(&mut self) -> Result<(), String>;
+    fn refresh(&mut self) -> Result<(), Error>;
     fn get_dx_version(&self) -> DxVersion;
+    fn get_game_id(&self) -> GameId;
     fn player_position(&mut self) -> Option<Box<&mut dyn PlayerPosition>>{ None }
     fn event_flags(&mut self) -> Option<Box<&mut dyn BufferedEventFlags>>{ None }
+    fn in_game_time(&mut self) -> Option<Box<&mut dyn InGameTime>>{ None }
+    fn chr_dbg_flags(&mut self) -> Option<Box<&mut dyn GetSetChrDbgFlags>>{ None }
+    fn ai_timer(&mut self) -> Option<Box<&mut dyn AiTimer>>{ None }
+    ///Games that read the controller through XInputGetState, the app hooks it for them
+    fn hooks_input(&self) -> bool { false }
+    fn quitout(&mut self) -> Option<Box<&mut dyn Quitout>>{ None }
+    ///Called once before the first refresh with the detected build, backends scan for its symbols
+    fn set_version(&mut self, _version: GameVersion) {}
+
+    ///Derived from the accessors above, the widget registry uses this to decide which widgets to show
+    fn capabilities(&mut self) -> Vec<Capability>
+    {
+        let mut capabilities = Vec::new();
+        if self.event_flags().is_some()     { capabilities.push(Capability::EventFlags); }
+        if self.player_position().is_some() { capabilities.push(Capability::PlayerPosition); }
+        if self.in_game_time().is_some()    { capabilities.push(Capability::InGameTime); }
+        if self.chr_dbg_flags().is_some()   { capabilities.push(Capability::ChrDbgFlags); }
+        if self.ai_timer().is_some()        { capabilities.push(Capability::AiTimer); }
+        if self.hooks_input()               { capabilities.push(Capability::Input); }
+        if self.quitout().is_some()         { capabilities.push(Capability::Quitout); }
+        return capabilities;
+    }
     fn as_any(&self) -> &dyn Any;
     fn as_any_mut(&mut self) -> &mut dyn Any;
 }
@@ -0,0 +1,79 @@
+// This file is part of the soulmemory-rs distribution (https://github.com/FrankvdStam/soulmemory-rs).
+// Copyright (c) 2022 Frank van der Stam.
+// https://github.com/FrankvdStam/soulmemory-rs/blob/main/LICENSE
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::fmt;
+use std::fmt::Display;
+use serde::{Deserialize, Serialize};
+
+/// Identifies a supported game. The numeric values are written to disk by the recorder, never reorder them.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
+#[repr(u8)]
+pub enum GameId
+{
+    MockGame = 0,
+    DarkSoulsPrepareToDieEdition = 1,
+    DarkSoulsRemastered = 2,
+    DarkSouls2Vanilla = 3,
+    DarkSouls2ScholarOfTheFirstSin = 4,
+    DarkSouls3 = 5,
+    Sekiro = 6,
+    EldenRing = 7,
+    ArmoredCore6 = 8,
+}
+
+impl GameId
+{
+    pub fn from_u8(value: u8) -> Option<GameId>
+    {
+        return match value
+        {
+            0 => Some(GameId::MockGame),
+            1 => Some(GameId::DarkSoulsPrepareToDieEdition),
+            2 => Some(GameId::DarkSoulsRemastered),
+            3 => Some(GameId::DarkSouls2Vanilla),
+            4 => Some(GameId::DarkSouls2ScholarOfTheFirstSin),
+            5 => Some(GameId::DarkSouls3),
+            6 => Some(GameId::Sekiro),
+            7 => Some(GameId::EldenRing),
+            8 => Some(GameId::ArmoredCore6),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str
+    {
+        return match self
+        {
+            GameId::MockGame                        => "MockGame",
+            GameId::DarkSoulsPrepareToDieEdition    => "DarkSoulsPrepareToDieEdition",
+            GameId::DarkSoulsRemastered             => "DarkSoulsRemastered",
+            GameId::DarkSouls2Vanilla               => "DarkSouls2Vanilla",
+            GameId::DarkSouls2ScholarOfTheFirstSin  => "DarkSouls2ScholarOfTheFirstSin",
+            GameId::DarkSouls3                      => "DarkSouls3",
+            GameId::Sekiro                          => "Sekiro",
+            GameId::EldenRing                       => "EldenRing",
+            GameId::ArmoredCore6                    => "ArmoredCore6",
+        }
+    }
+}
+
+impl Display for GameId
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        write!(f, "{}", self.name())
+    }
+}
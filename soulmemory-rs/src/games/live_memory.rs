@@ -0,0 +1,47 @@
+use mem_rs::prelude::*;
+use soulmemory_memory::MemorySource;
+use soulmemory_patterns::Symbol;
+
+///The running game's memory as a MemorySource, so backends can share decoding code with the tests that run on dumps
+pub struct LiveMemory<'a>
+{
+    process: &'a Process,
+}
+
+impl<'a> LiveMemory<'a>
+{
+    pub fn new(process: &'a Process) -> Self
+    {
+        return LiveMemory { process };
+    }
+}
+
+impl MemorySource for LiveMemory<'_>
+{
+    fn is_64_bit(&self) -> bool
+    {
+        //The dll runs inside the game, so it always matches the game's architecture
+        return cfg!(target_arch = "x86_64");
+    }
+
+    fn read(&self, address: usize, buffer: &mut [u8]) -> bool
+    {
+        return self.process.read_memory_abs(address, buffer);
+    }
+
+    fn write(&self, address: usize, buffer: &[u8]) -> bool
+    {
+        return self.process.write_memory_abs(address, buffer);
+    }
+
+    fn scan(&self, name: &str, symbol: &Symbol) -> Result<usize, String>
+    {
+        let pattern = symbol.pattern.to_string();
+        let pointer = match symbol.instruction_length
+        {
+            Some(instruction_length) => self.process.scan_rel(name, &pattern, symbol.offset, instruction_length, Vec::new())?,
+            None => self.process.scan_abs(name, &pattern, symbol.offset, Vec::new())?,
+        };
+        return Ok(pointer.get_base_address());
+    }
+}
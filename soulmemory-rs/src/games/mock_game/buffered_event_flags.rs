@@ -2,6 +2,8 @@ use std::sync::{Arc, Mutex};
 use crate::games::mock_game::BufferedEventFlags;
 use crate::games::mock_game::MockGame;
 use crate::games::traits::buffered_event_flags::EventFlag;
+use crate::error::Error;
+use soulmemory_memory::event_flags::EventFlagSnapshot;
 
 impl BufferedEventFlags for MockGame
 {
@@ -10,8 +12,49 @@ impl BufferedEventFlags for MockGame
         return &self.event_flags;
     }
 
-    fn get_event_flag_state(&self, _event_flag: u32) -> bool
+    fn get_event_flag_state(&self, event_flag: u32) -> bool
     {
-        return true;
+        return *self.event_flag_states.lock().unwrap().get(&event_flag).unwrap_or(&false);
     }
-}
\ No newline at end of file
+
+    fn set_event_flag_state(&self, event_flag: u32, state: bool)
+    {
+        self.raise_event_flag(event_flag, state);
+    }
+
+    fn snapshot_event_flags(&self) -> Result<EventFlagSnapshot, Error>
+    {
+        let mut snapshot = EventFlagSnapshot::new();
+        for (flag, state) in self.event_flag_states.lock().unwrap().iter()
+        {
+            snapshot.set(*flag, *state);
+        }
+        return Ok(snapshot);
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use soulmemory_memory::event_flags::FlagChange;
+    use crate::games::mock_game::MockGame;
+    use crate::games::traits::buffered_event_flags::BufferedEventFlags;
+
+    #[test]
+    fn diff_snapshots()
+    {
+        let game = MockGame::new();
+        game.raise_event_flag(13000800, true);
+        game.raise_event_flag(6950, true);
+        game.raise_event_flag(6951, false);
+        let a = game.snapshot_event_flags().unwrap();
+
+        game.raise_event_flag(6950, false);
+        game.raise_event_flag(50000001, true);
+        game.raise_event_flag(13000800, true);
+        let b = game.snapshot_event_flags().unwrap();
+
+        assert_eq!(vec![FlagChange { flag: 6950, state: false }, FlagChange { flag: 50000001, state: true }], a.diff(&b));
+        assert_eq!(vec![FlagChange { flag: 6950, state: true }, FlagChange { flag: 50000001, state: false }], b.diff(&a));
+    }
+}
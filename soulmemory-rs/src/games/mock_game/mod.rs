@@ -1,16 +1,26 @@
 use std::any::Any;
+use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
+use std::time::Instant;
 use rand::random;
 use crate::games::game::Game;
 use crate::games::dx_version::DxVersion;
+use crate::games::game_id::GameId;
+use crate::error::Error;
 use crate::games::traits::buffered_event_flags::BufferedEventFlags;
 use crate::games::traits::buffered_event_flags::EventFlag;
+use crate::games::traits::in_game_time::InGameTime;
+use crate::games::traits::player_position::PlayerPosition;
+use crate::util::vector3f::Vector3f;
 
 pub mod buffered_event_flags;
 
 pub struct MockGame
 {
     event_flags: Arc<Mutex<Vec<EventFlag>>>,
+    event_flag_states: Mutex<HashMap<u32, bool>>,
+    started: Instant,
+    position: Mutex<Vector3f>,
 }
 
 impl MockGame
@@ -27,20 +37,45 @@ impl MockGame
         MockGame
         {
             event_flags: Arc::new(Mutex::new(vec)),
+            event_flag_states: Mutex::new(HashMap::new()),
+            started: Instant::now(),
+            position: Mutex::new(Vector3f::default()),
         }
     }
 
     pub fn raise_event_flag(&self, flag: u32, state: bool)
     {
+        self.event_flag_states.lock().unwrap().insert(flag, state);
         let event_flag = EventFlag::new(chrono::offset::Local::now(), flag, state);
         let mut guard = self.access_flag_storage().lock().unwrap();
         guard.push(event_flag);
     }
 }
 
+impl PlayerPosition for MockGame
+{
+    fn get_position(&self) -> Vector3f
+    {
+        return *self.position.lock().unwrap();
+    }
+
+    fn set_position(&self, position: &Vector3f)
+    {
+        *self.position.lock().unwrap() = *position;
+    }
+}
+
+impl InGameTime for MockGame
+{
+    fn get_in_game_time_milliseconds(&self) -> u32
+    {
+        return self.started.elapsed().as_millis() as u32;
+    }
+}
+
 impl Game for MockGame
 {
-    fn refresh(&mut self) -> Result<(), String>
+    fn refresh(&mut self) -> Result<(), Error>
     {
         Ok(())
     }
@@ -49,7 +84,13 @@ impl Game for MockGame
         todo!()
     }
 
+    fn get_game_id(&self) -> GameId {
+        GameId::MockGame
+    }
+
     fn event_flags(&mut self) -> Option<Box<&mut dyn BufferedEventFlags>> { Some(Box::new(self)) }
+    fn in_game_time(&mut self) -> Option<Box<&mut dyn InGameTime>> { Some(Box::new(self)) }
+    fn player_position(&mut self) -> Option<Box<&mut dyn PlayerPosition>> { Some(Box::new(self)) }
 
     fn as_any(&self) -> &dyn Any
     {
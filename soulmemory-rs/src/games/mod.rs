@@ -25,8 +25,13 @@ mod elden_ring;
 mod armored_core_6;
 mod mock_game;
 pub mod dx_version;
+pub mod game_id;
 mod game;
 mod game_ext;
+mod physics_module;
+mod chr_dbg_flag_table;
+mod pattern_scan;
+mod live_memory;
 
 
 #[cfg(target_arch = "x86")]
@@ -46,6 +51,8 @@ mod ilhook
 
 pub use game::Game;
 pub use game_ext::GameExt;
+pub use pattern_scan::{find_symbol, PatternScan};
+pub use live_memory::LiveMemory;
 
 pub use dark_souls_prepare_to_die_edition::DarkSoulsPrepareToDieEdition;
 pub use dark_souls_remastered::DarkSoulsRemastered;
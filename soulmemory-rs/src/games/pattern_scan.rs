@@ -0,0 +1,30 @@
+use mem_rs::prelude::*;
+use soulmemory_patterns::{PatternFile, Symbol};
+use crate::error::Error;
+use crate::versions::GameVersion;
+
+///The symbol from the backend's pattern file, or from the detected build when its version table overrides it
+pub fn find_symbol<'a>(patterns: &'a PatternFile, version: &'a GameVersion, name: &str) -> Result<&'a Symbol, Error>
+{
+    return version.symbol(name).or(patterns.get(name)).ok_or(Error::Patterns(format!("no pattern for {}", name)));
+}
+
+pub trait PatternScan
+{
+    fn scan_symbol(&self, patterns: &PatternFile, version: &GameVersion, name: &str) -> Result<Pointer, Error>;
+}
+
+impl PatternScan for Process
+{
+    fn scan_symbol(&self, patterns: &PatternFile, version: &GameVersion, name: &str) -> Result<Pointer, Error>
+    {
+        let symbol = find_symbol(patterns, version, name)?;
+        let pattern = symbol.pattern.to_string();
+        let pointer = match symbol.instruction_length
+        {
+            Some(instruction_length) => self.scan_rel(name, &pattern, symbol.offset, instruction_length, symbol.chain.clone()),
+            None => self.scan_abs(name, &pattern, symbol.offset, symbol.chain.clone()),
+        };
+        return Ok(pointer?);
+    }
+}
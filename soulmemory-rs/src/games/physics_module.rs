@@ -0,0 +1,77 @@
+// This file is part of the soulmemory-rs distribution (https://github.com/FrankvdStam/soulmemory-rs).
+// Copyright (c) 2022 Frank van der Stam.
+// https://github.com/FrankvdStam/soulmemory-rs/blob/main/LICENSE
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use mem_rs::prelude::*;
+use crate::util::vector3f::Vector3f;
+
+///Every game keeps the player's position in a physics module (ChrPhysicsModule, ChrPhysicsCtrl, ...) at the end of a
+///pointer chain starting at the player instance. Only the chain and the offsets of the fields inside the module differ.
+pub struct PhysicsModule
+{
+    pointer: Pointer,
+    position_offset: usize,
+    angle_offset: Option<usize>,
+}
+
+impl Default for PhysicsModule
+{
+    fn default() -> Self
+    {
+        PhysicsModule { pointer: Pointer::default(), position_offset: 0, angle_offset: None }
+    }
+}
+
+impl PhysicsModule
+{
+    ///The position is stored as 3 consecutive floats starting at position_offset
+    pub fn new(pointer: Pointer, position_offset: usize, angle_offset: Option<usize>) -> Self
+    {
+        PhysicsModule { pointer, position_offset, angle_offset }
+    }
+
+    pub fn get_base_address(&self) -> usize
+    {
+        return self.pointer.get_base_address();
+    }
+
+    pub fn get_position(&self) -> Vector3f
+    {
+        let x = self.pointer.read_f32_rel(Some(self.position_offset));
+        let y = self.pointer.read_f32_rel(Some(self.position_offset + 0x4));
+        let z = self.pointer.read_f32_rel(Some(self.position_offset + 0x8));
+        return Vector3f::new(x, y, z);
+    }
+
+    pub fn set_position(&self, position: &Vector3f)
+    {
+        self.pointer.write_f32_rel(Some(self.position_offset), position.x);
+        self.pointer.write_f32_rel(Some(self.position_offset + 0x4), position.y);
+        self.pointer.write_f32_rel(Some(self.position_offset + 0x8), position.z);
+    }
+
+    pub fn get_angle(&self) -> Option<f32>
+    {
+        return self.angle_offset.map(|offset| self.pointer.read_f32_rel(Some(offset)));
+    }
+
+    pub fn set_angle(&self, angle: f32)
+    {
+        if let Some(offset) = self.angle_offset
+        {
+            self.pointer.write_f32_rel(Some(offset), angle);
+        }
+    }
+}
@@ -19,20 +19,31 @@
 
 use std::ops::Deref;
 use std::any::Any;
-use std::mem;
 use std::sync::{Arc, Mutex};
-use log::info;
+use log::{info, warn};
 use mem_rs::prelude::*;
 use crate::App;
 use crate::games::{ChrDbgFlag, GameExt, GetSetChrDbgFlags};
 use crate::games::dx_version::DxVersion;
+use crate::games::game_id::GameId;
+use crate::error::Error;
+use crate::games::{find_symbol, LiveMemory, PatternScan};
+use soulmemory_memory::{MemoryPointer, MemorySource};
+use soulmemory_memory::event_flags::{sprj, EventFlagSnapshot};
+use soulmemory_patterns::PatternFile;
+use crate::versions::GameVersion;
 use crate::games::game::Game;
 use crate::games::traits::player_position::PlayerPosition;
-use crate::games::traits::buffered_event_flags::{BufferedEventFlags, EventFlag};
+use crate::games::physics_module::PhysicsModule;
+use crate::games::chr_dbg_flag_table::ChrDbgFlagTable;
+use crate::games::traits::buffered_event_flags::{event_flag_state, BufferedEventFlags, EventFlag};
+use crate::games::traits::in_game_time::InGameTime;
+use crate::games::traits::quitout::Quitout;
 use crate::util::vector3f::Vector3f;
 use crate::games::ilhook::*;
 
-type FnGetEventFlag = fn(event_flag_man: u64, event_flag: u32) -> u8;
+const PATTERNS: &str = include_str!("../../patterns/Sekiro.toml");
+
 
 #[derive(Eq, PartialEq, Hash, Copy, Clone)]
 #[repr(usize)]
@@ -54,16 +65,35 @@ pub enum SekiroChrDbgFlag
     AllNoUpdateAi = 16,
 }
 
+const CHR_DBG_FLAGS: &[(u32, &str)] = &[
+    (SekiroChrDbgFlag::PlayerNoDead                as u32, "Player No Dead"),
+    (SekiroChrDbgFlag::PlayerExterminate           as u32, "Player Exterminate"),
+    (SekiroChrDbgFlag::PlayerExterminateStamina    as u32, "Player Exterminate Stamina"),
+    (SekiroChrDbgFlag::PlayerNoGoodsConsume        as u32, "Player No Goods Consume"),
+    (SekiroChrDbgFlag::PlayerNoResourceItemConsume as u32, "Player No Resource Item Consume"),
+    (SekiroChrDbgFlag::PlayerNoRevivalConsume      as u32, "Player No Revival Consume"),
+    (SekiroChrDbgFlag::PlayerHide                  as u32, "Player Hide"),
+    (SekiroChrDbgFlag::PlayerSilenced              as u32, "Player Silenced"),
+    (SekiroChrDbgFlag::AllNoDead                   as u32, "All No Dead"),
+    (SekiroChrDbgFlag::AllNoDamage                 as u32, "All No Damage"),
+    (SekiroChrDbgFlag::AllNoHit                    as u32, "All No Hit"),
+    (SekiroChrDbgFlag::AllNoAttack                 as u32, "All No Attack"),
+    (SekiroChrDbgFlag::AllNoMove                   as u32, "All No Move"),
+    (SekiroChrDbgFlag::AllNoUpdateAi               as u32, "All No Update Ai"),
+];
+
 
 pub struct Sekiro
 {
     process: Process,
+    version: GameVersion,
 
     event_flags: Arc<Mutex<Vec<EventFlag>>>,
-    event_flag_man: Pointer,
-    position: Pointer,
-    chr_dbg_flags: Pointer,
-    fn_get_event_flag: FnGetEventFlag,
+    event_flag_man: MemoryPointer,
+    field_area: MemoryPointer,
+    position: PhysicsModule,
+    chr_dbg_flags: ChrDbgFlagTable,
+    igt: Pointer,
     set_event_flag_hook: Option<HookPoint>,
 
     menu_man: Pointer,
@@ -76,21 +106,25 @@ impl Sekiro
         Sekiro
         {
             process: Process::new("sekiro.exe"),
+            version: GameVersion::default(),
             event_flags: Arc::new(Mutex::new(Vec::new())),
-            event_flag_man: Pointer::default(),
-            position: Pointer::default(),
-            chr_dbg_flags: Pointer::default(),
-            fn_get_event_flag: |_,_|{0},
+            event_flag_man: MemoryPointer::default(),
+            field_area: MemoryPointer::default(),
+            position: PhysicsModule::default(),
+            chr_dbg_flags: ChrDbgFlagTable::default(),
+            igt: Pointer::default(),
             set_event_flag_hook: None,
 
             menu_man: Pointer::default(),
         }
     }
+}
 
-
-    pub fn request_quitout(&self)
+impl Quitout for Sekiro
+{
+    fn request_quitout(&self)
     {
-        self.menu_man.write_u32_rel(Some(0x23c), 1);
+        self.menu_man.write_u32_rel(Some(self.version.offset("quitout", 0x23c)), 1);
     }
 }
 
@@ -103,18 +137,22 @@ impl PlayerPosition for Sekiro
             return Vector3f::default();
         }
 
-        let x = self.position.read_f32_rel(Some(0x80));
-        let y = self.position.read_f32_rel(Some(0x84));
-        let z = self.position.read_f32_rel(Some(0x88));
-
-        return Vector3f::new(x, y, z);
+        return self.position.get_position();
     }
 
     fn set_position(&self, position: &Vector3f)
     {
-        self.position.write_f32_rel(Some(0x80), position.x);
-        self.position.write_f32_rel(Some(0x84), position.y);
-        self.position.write_f32_rel(Some(0x88), position.z);
+        self.position.set_position(position);
+    }
+
+    fn get_angle(&self) -> Option<f32>
+    {
+        return self.position.get_angle();
+    }
+
+    fn set_angle(&self, angle: f32)
+    {
+        self.position.set_angle(angle);
     }
 }
 
@@ -123,33 +161,14 @@ impl GetSetChrDbgFlags for Sekiro
 {
     fn get_flags(&self) -> Vec<ChrDbgFlag>
     {
-        let mut buffer = [0u8; 17];
-        self.chr_dbg_flags.read_memory_rel(None, &mut buffer);
-
-        let mut result = Vec::new();
-        result.push((SekiroChrDbgFlag::PlayerNoDead                as u32, String::from("Player No Dead")                 , buffer[SekiroChrDbgFlag::PlayerNoDead                   as usize] == 1));
-        result.push((SekiroChrDbgFlag::PlayerExterminate           as u32, String::from("Player Exterminate")             , buffer[SekiroChrDbgFlag::PlayerExterminate              as usize] == 1));
-        result.push((SekiroChrDbgFlag::PlayerExterminateStamina    as u32, String::from("Player Exterminate Stamina")     , buffer[SekiroChrDbgFlag::PlayerExterminateStamina       as usize] == 1));
-        result.push((SekiroChrDbgFlag::PlayerNoGoodsConsume        as u32, String::from("Player No Goods Consume")        , buffer[SekiroChrDbgFlag::PlayerNoGoodsConsume           as usize] == 1));
-        result.push((SekiroChrDbgFlag::PlayerNoResourceItemConsume as u32, String::from("Player No Resource Item Consume"), buffer[SekiroChrDbgFlag::PlayerNoResourceItemConsume    as usize] == 1));
-        result.push((SekiroChrDbgFlag::PlayerNoRevivalConsume      as u32, String::from("Player No Revival Consume")      , buffer[SekiroChrDbgFlag::PlayerNoRevivalConsume         as usize] == 1));
-        result.push((SekiroChrDbgFlag::PlayerHide                  as u32, String::from("Player Hide")                    , buffer[SekiroChrDbgFlag::PlayerHide                     as usize] == 1));
-        result.push((SekiroChrDbgFlag::PlayerSilenced              as u32, String::from("Player Silenced")                , buffer[SekiroChrDbgFlag::PlayerSilenced                 as usize] == 1));
-        result.push((SekiroChrDbgFlag::AllNoDead                   as u32, String::from("All No Dead")                    , buffer[SekiroChrDbgFlag::AllNoDead                      as usize] == 1));
-        result.push((SekiroChrDbgFlag::AllNoDamage                 as u32, String::from("All No Damage")                  , buffer[SekiroChrDbgFlag::AllNoDamage                    as usize] == 1));
-        result.push((SekiroChrDbgFlag::AllNoHit                    as u32, String::from("All No Hit")                     , buffer[SekiroChrDbgFlag::AllNoHit                       as usize] == 1));
-        result.push((SekiroChrDbgFlag::AllNoAttack                 as u32, String::from("All No Attack")                  , buffer[SekiroChrDbgFlag::AllNoAttack                    as usize] == 1));
-        result.push((SekiroChrDbgFlag::AllNoMove                   as u32, String::from("All No Move")                    , buffer[SekiroChrDbgFlag::AllNoMove                      as usize] == 1));
-        result.push((SekiroChrDbgFlag::AllNoUpdateAi               as u32, String::from("All No Update Ai")               , buffer[SekiroChrDbgFlag::AllNoUpdateAi                  as usize] == 1));
-        return result;
+        return self.chr_dbg_flags.get_flags();
     }
 
     fn set_flag(&self, flag: u32, value: bool)
     {
         if self.process.is_attached()
         {
-            let value = match value{ true => 1, false => 0};
-            self.chr_dbg_flags.write_u8_rel(Some(flag as usize), value);
+            self.chr_dbg_flags.set_flag(flag, value);
         }
     }
 }
@@ -162,43 +181,71 @@ impl BufferedEventFlags for Sekiro
         return &self.event_flags;
     }
 
-    fn get_event_flag_state(&self, event_flag: u32) -> bool {
-        let result = (self.fn_get_event_flag)(self.event_flag_man.read_u64_rel(None), event_flag);
-        return result == 1;
+    fn get_event_flag_state(&self, event_flag: u32) -> bool
+    {
+        return event_flag_state(event_flag, sprj::get_event_flag(&LiveMemory::new(&self.process), &self.event_flag_man, &self.field_area, &sprj::SEKIRO, event_flag));
+    }
+
+    //Writes the bit directly, calling the game's set_event_flag from the overlay thread isn't safe.
+    //The hook doesn't see this write, so the flag is buffered here.
+    fn set_event_flag_state(&self, event_flag: u32, state: bool)
+    {
+        if let Err(e) = sprj::set_event_flag(&LiveMemory::new(&self.process), &self.event_flag_man, &self.field_area, &sprj::SEKIRO, event_flag, state)
+        {
+            warn!("{}", Error::from(e));
+            return;
+        }
+
+        let mut guard = self.event_flags.lock().unwrap();
+        guard.push(EventFlag::new(chrono::offset::Local::now(), event_flag, state));
+    }
+
+    fn snapshot_event_flags(&self) -> Result<EventFlagSnapshot, Error>
+    {
+        return Ok(sprj::snapshot(&LiveMemory::new(&self.process), &self.event_flag_man, &self.field_area, &sprj::SEKIRO)?);
+    }
+}
+
+impl InGameTime for Sekiro
+{
+    fn get_in_game_time_milliseconds(&self) -> u32
+    {
+        return self.igt.read_u32_rel(Some(self.version.offset("igt", 0x9c)));
     }
 }
 
 impl Game for Sekiro
 {
-    fn refresh(&mut self) -> Result<(), String> {
+    fn refresh(&mut self) -> Result<(), Error> {
         if !self.process.is_attached()
         {
             unsafe
             {
                 self.process.refresh()?;
+                let patterns = PatternFile::parse(PATTERNS).map_err(Error::Patterns)?;
 
-                self.event_flag_man = self.process.scan_rel("SprjEventFlagMan", "48 8b 0d ? ? ? ? 48 89 5c 24 50 48 89 6c 24 58 48 89 74 24 60", 3, 7, vec![0])?;
-                self.position = self.process.scan_rel("WorldChrManImp", "48 8B 35 ? ? ? ? 44 0F 28 18", 3, 7, vec![0, 0x48, 0x28])?;
-                self.chr_dbg_flags = self.process.scan_rel("chr dbg", "80 3d ? ? ? ? 00 0f ? ? ? ? ? 48 8b 9b d0 11 00 00", 2, 7, Vec::new())?;
-                self.menu_man = self.process.scan_rel("MenuMan", "48 8b 05 ? ? ? ? 0f b6 d1 48 8b 88 08 33 00 00", 3, 7, vec![0])?;
+                self.event_flag_man = LiveMemory::new(&self.process).scan_pointer("SprjEventFlagMan", find_symbol(&patterns, &self.version, "SprjEventFlagMan")?)?;
+                self.field_area = LiveMemory::new(&self.process).scan_pointer("FieldArea", find_symbol(&patterns, &self.version, "FieldArea")?)?;
+                self.position = PhysicsModule::new(self.process.scan_symbol(&patterns, &self.version, "WorldChrManImp")?, self.version.offset("position", 0x80), Some(self.version.offset("angle", 0x74)));
+                self.chr_dbg_flags = ChrDbgFlagTable::scan(&self.process, &patterns, &self.version, CHR_DBG_FLAGS);
+                self.menu_man = self.process.scan_symbol(&patterns, &self.version, "MenuMan")?;
+                self.igt = self.process.scan_symbol(&patterns, &self.version, "igt")?;
 
-                let set_event_flag_address = self.process.scan_abs("set_event_flag", "40 55 41 54 41 55 41 56 48 83 ec 58 80 b9 28 02 00 00 00 45 0f b6 e1 45 0f b6 e8 44 8b f2 48 8b e9", 0, Vec::new())?.get_base_address();
-                let get_event_flag_address = self.process.scan_abs("get_event_flag", "40 53 48 83 ec 20 80 b9 28 02 00 00 00 8b da", 0, Vec::new())?.get_base_address();
-                self.fn_get_event_flag = mem::transmute(get_event_flag_address);
+                let set_event_flag_address = self.process.scan_symbol(&patterns, &self.version, "set_event_flag")?.get_base_address();
 
                 #[cfg(target_arch = "x86_64")]
                 {
                     let h = Hooker::new(set_event_flag_address, HookType::JmpBack(set_event_flag_hook_fn), CallbackOption::None, 0, HookFlags::empty());
-                    self.set_event_flag_hook = Some(h.hook().unwrap());
+                    self.set_event_flag_hook = Some(h.hook().map_err(|e| Error::hook("set_event_flag", e))?);
                 }
 
 
-                info!("event_flag_man base address: 0x{:x}", self.event_flag_man.get_base_address());
+                info!("event_flag_man base address: 0x{:x}", self.event_flag_man.base);
+                info!("FieldArea      base address: 0x{:x}", self.field_area.base);
                 info!("WorldChrManImp base address: 0x{:x}", self.position.get_base_address());
-                info!("chr dbg flags  base address: 0x{:x}", self.chr_dbg_flags.get_base_address());
                 info!("MenuMan        base address: 0x{:x}", self.menu_man.get_base_address());
+                info!("igt            base address: 0x{:x}", self.igt.get_base_address());
                 info!("set event flag address     : 0x{:x}", set_event_flag_address);
-                info!("get event flag address     : 0x{:x}", get_event_flag_address);
             }
         }
         else
@@ -211,8 +258,17 @@ impl Game for Sekiro
     fn get_dx_version(&self) -> DxVersion {
         DxVersion::Dx11
     }
+
+    fn get_game_id(&self) -> GameId {
+        GameId::Sekiro
+    }
     fn event_flags(&mut self) -> Option<Box<&mut dyn BufferedEventFlags>> { Some(Box::new(self)) }
+    fn in_game_time(&mut self) -> Option<Box<&mut dyn InGameTime>> { Some(Box::new(self)) }
+    fn chr_dbg_flags(&mut self) -> Option<Box<&mut dyn GetSetChrDbgFlags>> { Some(Box::new(self)) }
+    fn hooks_input(&self) -> bool { true }
     fn player_position(&mut self) -> Option<Box<&mut dyn PlayerPosition>>{ Some(Box::new(self)) }
+    fn quitout(&mut self) -> Option<Box<&mut dyn Quitout>>{ Some(Box::new(self)) }
+    fn set_version(&mut self, version: GameVersion) { self.version = version; }
     fn as_any(&self) -> &dyn Any
     {
         self
@@ -0,0 +1,4 @@
+pub trait AiTimer
+{
+    fn get_ai_timer_value(&self) -> f32;
+}
@@ -1,7 +1,15 @@
 use std::{fmt, mem};
+use std::collections::BTreeSet;
 use std::fmt::Display;
 use std::sync::{Arc, Mutex};
 use chrono::{DateTime, Local};
+use log::warn;
+use soulmemory_memory::event_flags::EventFlagSnapshot;
+use soulmemory_memory::MemoryError;
+use crate::error::Error;
+
+///Invalid flags that were logged already
+static INVALID_FLAGS: Mutex<BTreeSet<u32>> = Mutex::new(BTreeSet::new());
 
 #[derive(Clone, Copy)]
 pub struct  EventFlag
@@ -27,10 +35,36 @@ pub trait BufferedEventFlags
 {
     fn access_flag_storage(&self) -> &Arc<Mutex<Vec<EventFlag>>>;
     fn get_event_flag_state(&self, event_flag: u32) -> bool;
+    fn set_event_flag_state(&self, event_flag: u32, state: bool);
+
+    ///Reads every flag in the game's flag storage, including the ones set before the hook was installed
+    fn snapshot_event_flags(&self) -> Result<EventFlagSnapshot, Error>
+    {
+        return Err(Error::Unsupported(String::from("event flag snapshots")));
+    }
 
     fn get_buffered_flags(&mut self) -> Vec<EventFlag>
     {
         let mut event_flags = self.access_flag_storage().lock().unwrap();
         mem::replace(&mut event_flags, Vec::new())
     }
-}
\ No newline at end of file
+}
+
+///get_event_flag_state is polled every frame, also in the main menu where the flag storage isn't allocated. Unreadable
+///storage reads as false without logging, an invalid flag is logged the first time it is read.
+pub fn event_flag_state(event_flag: u32, state: Result<bool, MemoryError>) -> bool
+{
+    match state
+    {
+        Ok(state) => return state,
+        Err(MemoryError::InvalidEventFlag(_)) =>
+        {
+            if INVALID_FLAGS.lock().unwrap().insert(event_flag)
+            {
+                warn!("{}", Error::InvalidEventFlag(event_flag));
+            }
+        }
+        Err(MemoryError::Unreadable(_)) => {}
+    }
+    return false;
+}
@@ -0,0 +1,15 @@
+use serde::{Deserialize, Serialize};
+
+///What a game supports, widgets that need a capability the game doesn't have are left out
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Capability
+{
+    EventFlags,
+    PlayerPosition,
+    InGameTime,
+    ChrDbgFlags,
+    AiTimer,
+    Input,
+    Quitout,
+}
@@ -0,0 +1,4 @@
+pub trait InGameTime
+{
+    fn get_in_game_time_milliseconds(&self) -> u32;
+}
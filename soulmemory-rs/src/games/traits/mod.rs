@@ -1,2 +1,6 @@
 pub mod player_position;
 pub mod buffered_event_flags;
+pub mod in_game_time;
+pub mod ai_timer;
+pub mod quitout;
+pub mod capability;
@@ -4,4 +4,10 @@ pub trait PlayerPosition
 {
     fn get_position(&self) -> Vector3f;
     fn set_position(&self, position: &Vector3f);
+
+    ///Facing direction in radians, for games where it is known
+    fn get_angle(&self) -> Option<f32> { None }
+    fn set_angle(&self, _angle: f32) {}
+
+    fn get_map_id(&self) -> Option<u32> { None }
 }
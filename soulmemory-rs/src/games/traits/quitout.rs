@@ -0,0 +1,4 @@
+pub trait Quitout
+{
+    fn request_quitout(&self);
+}
@@ -0,0 +1,319 @@
+// This file is part of the soulmemory-rs distribution (https://github.com/FrankvdStam/soulmemory-rs).
+// Copyright (c) 2022 Frank van der Stam.
+// https://github.com/FrankvdStam/soulmemory-rs/blob/main/LICENSE
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use soulmemory_config::{key_name, Binding, Config};
+use crate::error::Error;
+use crate::input::{InputContext, InputFrame, InputModifier};
+
+const VK_SHIFT: u16 = 0x10;
+const VK_CONTROL: u16 = 0x11;
+const VK_MENU: u16 = 0x12;
+const VK_ESCAPE: u16 = 0x1b;
+///Left/right variants of shift, control and alt
+const MODIFIER_KEYS: &[u16] = &[VK_SHIFT, VK_CONTROL, VK_MENU, 0xa0, 0xa1, 0xa2, 0xa3, 0xa4, 0xa5];
+
+pub struct HotkeyAction
+{
+    pub id: String,
+    pub description: String,
+    pub binding: Option<Binding>,
+    down: bool,
+    pressed: bool,
+}
+
+///Named actions with their bindings. Widgets register their actions once, the app updates the manager every
+///frame and the widgets ask whether their action was pressed. Bindings are stored in the [hotkeys] section of the config.
+#[derive(Default)]
+pub struct HotkeyManager
+{
+    actions: Vec<HotkeyAction>,
+    overrides: BTreeMap<String, String>,
+    config_path: Option<PathBuf>,
+    pad_buttons: u16,
+    capturing: Option<String>,
+    captured_pad_buttons: u16,
+    keys_down: Vec<bool>,
+    error: Option<String>,
+}
+
+impl HotkeyManager
+{
+    ///overrides are the bindings from the config, they replace the defaults actions are registered with
+    pub fn new(overrides: BTreeMap<String, String>, config_path: Option<PathBuf>) -> Self
+    {
+        HotkeyManager { overrides, config_path, ..Default::default() }
+    }
+
+    ///Replaces an action with the same id. The action is registered even when a binding doesn't parse: an invalid
+    ///binding from the config falls back to the default, an invalid default leaves the action unbound.
+    pub fn register(&mut self, id: &str, description: &str, default_binding: &str) -> Result<(), Error>
+    {
+        let mut result = Ok(());
+        let mut binding = match Binding::parse(default_binding)
+        {
+            Ok(binding) => binding,
+            Err(e) =>
+            {
+                result = Err(Error::Config(format!("invalid default binding for {}: {}", id, e)));
+                None
+            }
+        };
+        if let Some(text) = self.overrides.get(id)
+        {
+            match Binding::parse(text)
+            {
+                Ok(overridden) => binding = overridden,
+                Err(e) => result = Err(Error::Config(format!("invalid binding for {} in [hotkeys], using the default: {}", id, e))),
+            }
+        }
+
+        self.unregister(id);
+        self.actions.push(HotkeyAction { id: String::from(id), description: String::from(description), binding, down: false, pressed: false });
+        return result;
+    }
+
+    ///Actions that come and go, like the macros. Their binding stays in the config.
+    pub fn unregister(&mut self, id: &str)
+    {
+        self.actions.retain(|action| action.id != id);
+    }
+
+    ///Ids in the config's [hotkeys] table that no action was registered for
+    pub fn unknown_overrides(&self) -> Vec<&str>
+    {
+        return self.overrides.keys().filter(|id| !self.actions.iter().any(|action| action.id == **id)).map(|id| id.as_str()).collect();
+    }
+
+    pub fn actions(&self) -> &[HotkeyAction]
+    {
+        return &self.actions;
+    }
+
+    ///True for the frame the binding went down
+    pub fn is_pressed(&self, id: &str) -> bool
+    {
+        return self.actions.iter().any(|action| action.id == id && action.pressed);
+    }
+
+    ///Why the last rebind failed
+    pub fn error(&self) -> Option<&str>
+    {
+        return self.error.as_deref();
+    }
+
+    pub fn capturing(&self) -> Option<&str>
+    {
+        return self.capturing.as_deref();
+    }
+
+    ///The next key combo or gamepad chord becomes the action's binding, escape cancels
+    pub fn start_capture(&mut self, id: &str)
+    {
+        self.capturing = Some(String::from(id));
+        self.captured_pad_buttons = 0;
+        self.error = None;
+    }
+
+    ///Actions that share their binding with another action
+    pub fn conflicts(&self) -> Vec<(String, String)>
+    {
+        let mut conflicts = Vec::new();
+        for (i, a) in self.actions.iter().enumerate()
+        {
+            for b in &self.actions[i + 1..]
+            {
+                if a.binding.is_some() && a.binding == b.binding
+                {
+                    conflicts.push((a.id.clone(), b.id.clone()));
+                }
+            }
+        }
+        return conflicts;
+    }
+
+    ///Refuses a binding that is already in use by another action
+    pub fn rebind(&mut self, id: &str, binding: Option<Binding>) -> Result<(), String>
+    {
+        if let Some(other) = self.actions.iter().find(|action| action.id != id && binding.is_some() && action.binding == binding)
+        {
+            return Err(format!("{} is already bound to {}", binding.unwrap().to_text(), other.id));
+        }
+
+        let action = self.actions.iter_mut().find(|action| action.id == id).ok_or(format!("unknown action {}", id))?;
+        action.binding = binding;
+        action.down = true;//Don't fire for the keys that were just captured
+        self.overrides.insert(String::from(id), binding.map(|b| b.to_text()).unwrap_or_default());
+        return self.save();
+    }
+
+    ///Writes the bindings that differ from the defaults into the config file's [hotkeys] table
+    fn save(&self) -> Result<(), String>
+    {
+        return match &self.config_path
+        {
+            Some(path) => Config::save_hotkeys(path, &self.overrides),
+            None => Ok(()),
+        };
+    }
+
+    ///Called once per frame with the keyboard state indexed by virtual key code. Returns the result of a finished
+    ///capture, if any.
+    pub fn update(&mut self, keys_down: &[bool], want_text_input: bool) -> Option<Result<(), String>>
+    {
+        let down = |key: u16| keys_down.get(key as usize).copied().unwrap_or(false);
+        let ctrl = down(VK_CONTROL);
+        let shift = down(VK_SHIFT);
+        let alt = down(VK_MENU);
+
+        let captured = match self.capturing.clone()
+        {
+            Some(id) => self.capture(&id, keys_down, ctrl, shift, alt),
+            None => None,
+        };
+
+        for action in &mut self.actions
+        {
+            let binding_down = match action.binding
+            {
+                Some(Binding::Keyboard { key, ctrl: c, shift: s, alt: a }) => !want_text_input && down(key) && ctrl == c && shift == s && alt == a,
+                Some(Binding::Gamepad { buttons }) => self.pad_buttons & buttons == buttons,
+                None => false,
+            };
+            action.pressed = binding_down && !action.down && self.capturing.is_none();
+            action.down = binding_down;
+        }
+
+        self.keys_down = keys_down.to_vec();
+        return captured;
+    }
+
+    fn capture(&mut self, id: &str, keys_down: &[bool], ctrl: bool, shift: bool, alt: bool) -> Option<Result<(), String>>
+    {
+        let pressed = (0x08u16..0x100).find(|key| keys_down.get(*key as usize).copied().unwrap_or(false)
+            && !self.keys_down.get(*key as usize).copied().unwrap_or(false)
+            && !MODIFIER_KEYS.contains(key)
+            && key_name(*key).is_some());
+
+        let binding = match pressed
+        {
+            Some(VK_ESCAPE) =>
+            {
+                self.capturing = None;
+                return None;
+            }
+            Some(key) => Binding::Keyboard { key, ctrl, shift, alt },
+            //A chord is complete once all its buttons are released
+            None if self.pad_buttons != 0 =>
+            {
+                self.captured_pad_buttons |= self.pad_buttons;
+                return None;
+            }
+            None if self.captured_pad_buttons != 0 => Binding::Gamepad { buttons: self.captured_pad_buttons },
+            None => return None,
+        };
+
+        self.capturing = None;
+        let result = self.rebind(id, Some(binding));
+        self.error = result.as_ref().err().cloned();
+        return Some(result);
+    }
+}
+
+///Only watches the first controller, the buttons still reach the game
+impl InputModifier for HotkeyManager
+{
+    fn modify(&mut self, _context: &InputContext, frame: &mut InputFrame)
+    {
+        self.pad_buttons = frame.input.buttons;
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use std::collections::BTreeMap;
+    use crate::hotkeys::*;
+
+    #[test]
+    fn press_capture_and_conflicts()
+    {
+        let mut overrides = BTreeMap::new();
+        overrides.insert(String::from("quitout"), String::from("f5"));
+        let mut hotkeys = HotkeyManager::new(overrides, None);
+        hotkeys.register("quitout", "quitout", "oem_5").unwrap();
+        hotkeys.register("save_position_1", "save position 1", "ctrl+shift+1").unwrap();
+        hotkeys.register("restore_position_1", "restore position 1", "ctrl+1").unwrap();
+
+        let mut keys = vec![false; 256];
+        keys[0x11] = true;
+        keys[0x31] = true;
+        hotkeys.update(&keys, false);
+        assert!(hotkeys.is_pressed("restore_position_1"));
+        assert!(!hotkeys.is_pressed("save_position_1"));
+        hotkeys.update(&keys, false);
+        assert!(!hotkeys.is_pressed("restore_position_1"));
+
+        keys[0x74] = true;
+        hotkeys.update(&keys, false);
+        assert!(!hotkeys.is_pressed("quitout"), "modifiers have to match exactly");
+
+        //Capture a gamepad chord
+        let mut keys = vec![false; 256];
+        hotkeys.update(&keys, false);
+        hotkeys.start_capture("quitout");
+        for buttons in [0x0100, 0x0300, 0x0200]
+        {
+            hotkeys.modify(&InputContext::default(), &mut InputFrame { user_index: 0, connected: true, input: soulmemory_tas::TasInput { buttons, ..Default::default() } });
+            assert!(hotkeys.update(&keys, false).is_none());
+        }
+        hotkeys.modify(&InputContext::default(), &mut InputFrame::default());
+        assert_eq!(Some(Ok(())), hotkeys.update(&keys, false));
+        assert_eq!(Some(Binding::Gamepad { buttons: 0x0300 }), hotkeys.actions()[0].binding);
+
+        //Capturing a combo that is in use fails
+        hotkeys.start_capture("quitout");
+        keys[0x11] = true;
+        keys[0x31] = true;
+        assert!(hotkeys.update(&keys, false).unwrap().unwrap_err().contains("restore_position_1"));
+        assert!(hotkeys.conflicts().is_empty());
+    }
+
+    #[test]
+    fn invalid_and_unknown_overrides()
+    {
+        let mut overrides = BTreeMap::new();
+        overrides.insert(String::from("quitout"), String::from("hyper+1"));
+        overrides.insert(String::from("macro_deflect"), String::from("f6"));
+        overrides.insert(String::from("typo"), String::from("f7"));
+        let mut hotkeys = HotkeyManager::new(overrides, None);
+
+        assert!(matches!(hotkeys.register("quitout", "quitout", "oem_5"), Err(Error::Config(_))));
+        assert_eq!(Binding::parse("oem_5").unwrap(), hotkeys.actions()[0].binding);
+        assert!(matches!(hotkeys.register("broken", "broken", "ctrl"), Err(Error::Config(_))));
+        assert_eq!(None, hotkeys.actions()[1].binding);
+
+        hotkeys.register("macro_deflect", "run macro deflect", "f5").unwrap();
+        hotkeys.register("macro_deflect", "run macro deflect", "f8").unwrap();
+        assert_eq!(3, hotkeys.actions().len());
+        assert_eq!(Binding::parse("f6").unwrap(), hotkeys.actions()[2].binding);
+        assert_eq!(vec!["typo"], hotkeys.unknown_overrides());
+
+        hotkeys.unregister("macro_deflect");
+        assert_eq!(vec!["macro_deflect", "typo"], hotkeys.unknown_overrides());
+    }
+}
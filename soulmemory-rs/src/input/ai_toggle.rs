@@ -0,0 +1,291 @@
+// This file is part of the soulmemory-rs distribution (https://github.com/FrankvdStam/soulmemory-rs).
+// Copyright (c) 2022 Frank van der Stam.
+// https://github.com/FrankvdStam/soulmemory-rs/blob/main/LICENSE
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::VecDeque;
+use crate::input::modifier::{InputContext, InputFrame, InputModifier};
+
+///Timer values kept for the plot, about 5 seconds of polls
+pub const HISTORY_LENGTH: usize = 300;
+///Scale of the plot until the period is known, the timers wrap at about 5 seconds
+pub const DEFAULT_PERIOD: f32 = 5.0f32;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AiToggleMode
+{
+    Off,
+    ///Presses the button when the timer passes a threshold
+    Auto,
+    ///Only measures when the player presses the scheduled buttons
+    Practice,
+}
+
+///Press button for a number of frames once the ai timer passes threshold
+#[derive(Clone, Debug, PartialEq)]
+pub struct ScheduleEntry
+{
+    pub threshold: f32,
+    pub button: u16,
+    pub frames: u32,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ToggleStats
+{
+    pub hits: u32,
+    pub misses: u32,
+    ///Timer value at the press minus the threshold
+    pub last_error: Option<f32>,
+    total_error: f32,
+}
+
+impl ToggleStats
+{
+    pub fn mean_error(&self) -> Option<f32>
+    {
+        let count = self.hits + self.misses;
+        return (count > 0).then(|| self.total_error / count as f32);
+    }
+}
+
+///Toggles weapons (d-pad left or right) at points in the ai timer's cycle. The timer counts up and wraps around,
+///the period is taken from the highest value seen before a wrap. Errors are measured against the period estimated
+///before the poll that is measured, a wrap in that poll would otherwise tune the period to the press.
+pub struct AiToggle
+{
+    pub mode: AiToggleMode,
+    pub schedule: Vec<ScheduleEntry>,
+    ///Largest error that still counts as a hit
+    pub tolerance: f32,
+    stats: Vec<ToggleStats>,
+    history: VecDeque<f32>,
+    previous: Option<f32>,
+    period: Option<f32>,
+    ///Buttons being pressed by the schedule with the frames left
+    holding: Vec<(u16, u32)>,
+    buttons_down: u16,
+}
+
+impl Default for AiToggle
+{
+    fn default() -> Self
+    {
+        AiToggle
+        {
+            mode: AiToggleMode::Off,
+            schedule: vec![ScheduleEntry { threshold: 4.8f32, button: 0x0008, frames: 1 }],//d-pad right
+            tolerance: 0.017f32,
+            stats: Vec::new(),
+            history: VecDeque::with_capacity(HISTORY_LENGTH),
+            previous: None,
+            period: None,
+            holding: Vec::new(),
+            buttons_down: 0,
+        }
+    }
+}
+
+impl AiToggle
+{
+    pub fn stats(&self, index: usize) -> ToggleStats
+    {
+        return self.stats.get(index).cloned().unwrap_or_default();
+    }
+
+    pub fn reset_stats(&mut self)
+    {
+        self.stats.clear();
+    }
+
+    pub fn history(&self) -> &VecDeque<f32>
+    {
+        return &self.history;
+    }
+
+    pub fn period(&self) -> Option<f32>
+    {
+        return self.period;
+    }
+
+    ///Whether the timer passed threshold going from previous to current, including across a wrap
+    fn crossed(previous: f32, current: f32, threshold: f32) -> bool
+    {
+        if current >= previous
+        {
+            return previous < threshold && threshold <= current;
+        }
+        return threshold > previous || threshold <= current;
+    }
+
+    ///The shortest distance around the cycle, once the period is known
+    fn wrap_error(error: f32, period: Option<f32>) -> f32
+    {
+        return match period
+        {
+            Some(period) if error > period / 2.0f32 => error - period,
+            Some(period) if error < -period / 2.0f32 => error + period,
+            _ => error,
+        };
+    }
+
+    fn record(&mut self, index: usize, timer: f32, period: Option<f32>)
+    {
+        let error = Self::wrap_error(timer - self.schedule[index].threshold, period);
+        let stats = &mut self.stats[index];
+        if error.abs() <= self.tolerance
+        {
+            stats.hits += 1;
+        }
+        else
+        {
+            stats.misses += 1;
+        }
+        stats.last_error = Some(error);
+        stats.total_error += error;
+    }
+
+    ///The scheduled entry for button that is closest to the timer
+    fn nearest_entry(&self, button: u16, timer: f32, period: Option<f32>) -> Option<usize>
+    {
+        return self.schedule.iter().enumerate()
+            .filter(|(_, entry)| entry.button & button != 0)
+            .min_by(|(_, a), (_, b)| Self::wrap_error(timer - a.threshold, period).abs().total_cmp(&Self::wrap_error(timer - b.threshold, period).abs()))
+            .map(|(index, _)| index);
+    }
+}
+
+impl InputModifier for AiToggle
+{
+    fn modify(&mut self, context: &InputContext, frame: &mut InputFrame)
+    {
+        let timer = match context.ai_timer
+        {
+            Some(timer) => timer,
+            None => return,
+        };
+
+        if self.history.len() == HISTORY_LENGTH
+        {
+            self.history.pop_front();
+        }
+        self.history.push_back(timer);
+
+        let previous = self.previous.replace(timer);
+        let period = self.period;
+        if let Some(previous) = previous.filter(|previous| timer < *previous)
+        {
+            self.period = Some(self.period.map_or(previous, |period| period.max(previous)));
+        }
+        self.stats.resize(self.schedule.len(), ToggleStats::default());
+
+        match self.mode
+        {
+            AiToggleMode::Off => {}
+            AiToggleMode::Auto =>
+            {
+                if let Some(previous) = previous
+                {
+                    for index in 0..self.schedule.len()
+                    {
+                        if Self::crossed(previous, timer, self.schedule[index].threshold)
+                        {
+                            self.holding.push((self.schedule[index].button, self.schedule[index].frames.max(1)));
+                            self.record(index, timer, period);
+                        }
+                    }
+                }
+            }
+            AiToggleMode::Practice =>
+            {
+                let pressed = frame.input.buttons & !self.buttons_down;
+                for bit in (0..16).map(|i| 1u16 << i).filter(|bit| pressed & bit != 0)
+                {
+                    if let Some(index) = self.nearest_entry(bit, timer, period)
+                    {
+                        self.record(index, timer, period);
+                    }
+                }
+            }
+        }
+        self.buttons_down = frame.input.buttons;
+
+        for (button, frames) in &mut self.holding
+        {
+            frame.input.buttons |= *button;
+            *frames -= 1;
+        }
+        self.holding.retain(|(_, frames)| *frames > 0);
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use crate::input::*;
+    use soulmemory_tas::TasInput;
+
+    fn poll(ai_toggle: &mut AiToggle, timer: f32, buttons: u16) -> u16
+    {
+        let mut frame = InputFrame { user_index: 0, connected: true, input: TasInput { buttons, ..Default::default() } };
+        ai_toggle.modify(&InputContext { ai_timer: Some(timer) }, &mut frame);
+        return frame.input.buttons;
+    }
+
+    #[test]
+    fn auto_schedule_wraps()
+    {
+        let mut ai_toggle = AiToggle { mode: AiToggleMode::Auto, tolerance: 0.05f32, ..Default::default() };
+        ai_toggle.schedule = vec![ScheduleEntry { threshold: 4.0f32, button: 0x0008, frames: 2 }, ScheduleEntry { threshold: 0.1f32, button: 0x0004, frames: 1 }];
+
+        let pressed: Vec<u16> = [3.9f32, 4.02f32, 4.5f32, 4.9f32, 0.2f32, 0.3f32].iter().map(|timer| poll(&mut ai_toggle, *timer, 0)).collect();
+        assert_eq!(vec![0, 0x0008, 0x0008, 0, 0x0004, 0], pressed);
+        assert_eq!(Some(4.9f32), ai_toggle.period());
+        assert_eq!(1, ai_toggle.stats(0).hits);
+        assert_eq!(1, ai_toggle.stats(1).misses);
+    }
+
+    #[test]
+    fn auto_stats_use_previous_period()
+    {
+        let mut ai_toggle = AiToggle { mode: AiToggleMode::Auto, tolerance: 0.05f32, ..Default::default() };
+        ai_toggle.schedule = vec![ScheduleEntry { threshold: 4.95f32, button: 0x0008, frames: 1 }];
+
+        //The timer wraps at 4.9 and never reaches the threshold, the wrap that toggles also sets the period to 4.9.
+        //Measured against that period the late toggle would look 0.03 early.
+        for timer in [4.8f32, 4.9f32, 0.02f32]
+        {
+            poll(&mut ai_toggle, timer, 0);
+        }
+        assert_eq!(Some(4.9f32), ai_toggle.period());
+        assert_eq!((0, 1), (ai_toggle.stats(0).hits, ai_toggle.stats(0).misses));
+    }
+
+    #[test]
+    fn practice_measures_presses()
+    {
+        let mut ai_toggle = AiToggle { mode: AiToggleMode::Practice, tolerance: 0.05f32, ..Default::default() };
+        ai_toggle.schedule = vec![ScheduleEntry { threshold: 0.1f32, button: 0x0008, frames: 1 }];
+
+        //The timer wraps after 4.99, a press at 4.98 is early by 0.11
+        for (timer, buttons) in [(4.99f32, 0), (0.0f32, 0), (4.98f32, 0x0008), (4.99f32, 0x0008), (0.12f32, 0), (0.11f32, 0x0008)]
+        {
+            assert_eq!(buttons, poll(&mut ai_toggle, timer, buttons));
+        }
+
+        let stats = ai_toggle.stats(0);
+        assert_eq!((1, 1), (stats.hits, stats.misses));
+        assert!((stats.last_error.unwrap() - 0.01f32).abs() < 0.001f32);
+    }
+}
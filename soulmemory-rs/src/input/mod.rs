@@ -0,0 +1,25 @@
+// This file is part of the soulmemory-rs distribution (https://github.com/FrankvdStam/soulmemory-rs).
+// Copyright (c) 2022 Frank van der Stam.
+// https://github.com/FrankvdStam/soulmemory-rs/blob/main/LICENSE
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+pub mod modifier;
+pub mod ai_toggle;
+pub mod remap;
+pub mod tas;
+pub(crate) mod xinput;
+
+pub use modifier::{InputChain, InputContext, InputFrame, InputModifier};
+pub use ai_toggle::{AiToggle, AiToggleMode, ScheduleEntry, ToggleStats, DEFAULT_PERIOD};
+pub use remap::Remap;
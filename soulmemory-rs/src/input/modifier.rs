@@ -0,0 +1,107 @@
+// This file is part of the soulmemory-rs distribution (https://github.com/FrankvdStam/soulmemory-rs).
+// Copyright (c) 2022 Frank van der Stam.
+// https://github.com/FrankvdStam/soulmemory-rs/blob/main/LICENSE
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::sync::{Arc, Mutex};
+use soulmemory_tas::TasInput;
+
+///A single read of a controller as it passes through the chain
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct InputFrame
+{
+    pub user_index: u32,
+    ///Modifiers may set this to drive a controller that isn't plugged in
+    pub connected: bool,
+    pub input: TasInput,
+}
+
+///Game state the modifiers may react to, gathered once per read
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct InputContext
+{
+    pub ai_timer: Option<f32>,
+}
+
+pub trait InputModifier
+{
+    fn modify(&mut self, context: &InputContext, frame: &mut InputFrame);
+}
+
+///Lets a widget and the chain share a modifier
+impl<T: InputModifier> InputModifier for Arc<Mutex<T>>
+{
+    fn modify(&mut self, context: &InputContext, frame: &mut InputFrame)
+    {
+        self.lock().unwrap().modify(context, frame);
+    }
+}
+
+///Modifiers run in the order they were pushed, each one sees the result of the previous one
+#[derive(Default)]
+pub struct InputChain
+{
+    modifiers: Vec<Box<dyn InputModifier>>,
+}
+
+impl InputChain
+{
+    pub fn push<T: InputModifier + 'static>(&mut self, modifier: T)
+    {
+        self.modifiers.push(Box::new(modifier));
+    }
+
+    pub fn modify(&mut self, context: &InputContext, frame: &mut InputFrame)
+    {
+        for modifier in &mut self.modifiers
+        {
+            modifier.modify(context, frame);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use std::sync::{Arc, Mutex};
+    use crate::input::*;
+    use soulmemory_tas::{TasInput, TasPlayer, TasScript};
+
+    #[test]
+    fn chain_order()
+    {
+        let tas_player = Arc::new(Mutex::new(TasPlayer::default()));
+        tas_player.lock().unwrap().load(&TasScript::parse("0 1 a").unwrap());
+
+        let mut chain = InputChain::default();
+        chain.push(Remap { mappings: vec![(0x1000, 0x2000)] });
+        chain.push(Arc::clone(&tas_player));
+        let mut ai_toggle = AiToggle::default();
+        ai_toggle.mode = AiToggleMode::Auto;
+        chain.push(ai_toggle);
+
+        //Playback replaces the remapped input, the ai toggle presses d-pad right on top of the script
+        chain.modify(&InputContext { ai_timer: Some(4.7f32) }, &mut InputFrame::default());
+        tas_player.lock().unwrap().start().unwrap();
+        let mut frame = InputFrame { user_index: 0, connected: false, input: TasInput { buttons: 0x1000, ..Default::default() } };
+        chain.modify(&InputContext { ai_timer: Some(4.9f32) }, &mut frame);
+        assert!(frame.connected);
+        assert_eq!(0x1008, frame.input.buttons);
+
+        //Script finished, the player's a is remapped to b
+        let mut frame = InputFrame { user_index: 0, connected: true, input: TasInput { buttons: 0x1000, ..Default::default() } };
+        chain.modify(&InputContext::default(), &mut frame);
+        assert_eq!(0x2000, frame.input.buttons);
+    }
+}
@@ -0,0 +1,44 @@
+// This file is part of the soulmemory-rs distribution (https://github.com/FrankvdStam/soulmemory-rs).
+// Copyright (c) 2022 Frank van der Stam.
+// https://github.com/FrankvdStam/soulmemory-rs/blob/main/LICENSE
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use crate::input::modifier::{InputContext, InputFrame, InputModifier};
+
+///Swaps buttons, each mapping moves the from bit to the to bit. Mappings are applied to the original buttons,
+///so a and b can be swapped with two mappings.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Remap
+{
+    pub mappings: Vec<(u16, u16)>,
+}
+
+impl InputModifier for Remap
+{
+    fn modify(&mut self, _context: &InputContext, frame: &mut InputFrame)
+    {
+        let buttons = frame.input.buttons;
+        for (from, _) in &self.mappings
+        {
+            frame.input.buttons &= !from;
+        }
+        for (from, to) in &self.mappings
+        {
+            if buttons & from != 0
+            {
+                frame.input.buttons |= to;
+            }
+        }
+    }
+}
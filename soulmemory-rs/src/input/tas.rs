@@ -0,0 +1,42 @@
+// This file is part of the soulmemory-rs distribution (https://github.com/FrankvdStam/soulmemory-rs).
+// Copyright (c) 2022 Frank van der Stam.
+// https://github.com/FrankvdStam/soulmemory-rs/blob/main/LICENSE
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use crate::input::modifier::{InputContext, InputFrame, InputModifier};
+use soulmemory_tas::{TasInput, TasPlayer, TasRecording};
+
+impl InputModifier for TasPlayer
+{
+    fn modify(&mut self, _context: &InputContext, frame: &mut InputFrame)
+    {
+        if let Some(input) = self.poll()
+        {
+            frame.input = input;
+            frame.connected = true;
+        }
+    }
+}
+
+///Records what reaches it, so it goes before anything that changes the player's input
+impl InputModifier for TasRecording
+{
+    fn modify(&mut self, _context: &InputContext, frame: &mut InputFrame)
+    {
+        if self.is_recording()
+        {
+            self.record(if frame.connected { frame.input } else { TasInput::default() });
+        }
+    }
+}
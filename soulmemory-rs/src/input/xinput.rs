@@ -0,0 +1,184 @@
+// This file is part of the soulmemory-rs distribution (https://github.com/FrankvdStam/soulmemory-rs).
+// Copyright (c) 2022 Frank van der Stam.
+// https://github.com/FrankvdStam/soulmemory-rs/blob/main/LICENSE
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+#![allow(dead_code)]
+
+use std::ffi::c_void;
+use ::log::info;
+use windows::Win32::Foundation::HINSTANCE;
+use windows::Win32::UI::Input::XboxController::XINPUT_STATE;
+use soulmemory_tas::TasInput;
+use crate::error::Error;
+
+#[cfg(target_arch = "x86_64")]
+use std::mem;
+#[cfg(target_arch = "x86_64")]
+use windows::Win32::Foundation::ERROR_SUCCESS;
+#[cfg(target_arch = "x86_64")]
+use ilhook::x64::{CallbackOption, Hooker, HookFlags, HookPoint, HookType, Registers};
+#[cfg(target_arch = "x86_64")]
+use crate::App;
+#[cfg(target_arch = "x86_64")]
+use crate::input::InputFrame;
+
+
+//TODO: convert these to windows-rs
+#[link(name = "kernel32")]
+extern "stdcall" {
+    //fn LoadLibraryA(lp_proc_name: *const u8) -> HMODULE;
+    fn GetProcAddress(h_module: *const c_void, lp_proc_name: *const u8) -> *const c_void;
+    fn GetModuleHandleA(lp_module_name: *const u8) -> HINSTANCE;
+}
+
+
+pub type XInputGetState = unsafe extern "system" fn(dw_user_index: u32, p_state: *mut XINPUT_STATE) -> u32;
+
+
+///Replaces the controller state. The packet number is bumped, games skip states that didn't change it.
+pub fn write_input(input: &TasInput, xinput_state: *mut XINPUT_STATE)
+{
+    unsafe
+    {
+        (*xinput_state).dwPacketNumber = (*xinput_state).dwPacketNumber.wrapping_add(1);
+        let gamepad = &mut (*xinput_state).Gamepad;
+        gamepad.wButtons.0 = input.buttons;
+        gamepad.bLeftTrigger = input.left_trigger;
+        gamepad.bRightTrigger = input.right_trigger;
+        gamepad.sThumbLX = input.thumb_lx;
+        gamepad.sThumbLY = input.thumb_ly;
+        gamepad.sThumbRX = input.thumb_rx;
+        gamepad.sThumbRY = input.thumb_ry;
+    }
+}
+
+pub fn read_input(xinput_state: *const XINPUT_STATE) -> TasInput
+{
+    let gamepad = unsafe{ &(*xinput_state).Gamepad };
+    return TasInput
+    {
+        buttons: gamepad.wButtons.0,
+        left_trigger: gamepad.bLeftTrigger,
+        right_trigger: gamepad.bRightTrigger,
+        thumb_lx: gamepad.sThumbLX,
+        thumb_ly: gamepad.sThumbLY,
+        thumb_rx: gamepad.sThumbRX,
+        thumb_ry: gamepad.sThumbRY,
+    };
+}
+
+///None while the game hasn't loaded any xinput dll yet
+pub fn get_xinput_get_state_fn_address() -> Option<u64>
+{
+    unsafe
+    {
+        let xinput_versions =
+        [
+            "xinput1_3.dll\0",
+            "xinput1_4.dll\0",
+            "xinput9_1_0.dll\0",
+        ];
+
+        for xinput_version in xinput_versions
+        {
+            let hmodule = GetModuleHandleA(xinput_version.as_ptr());
+            if hmodule != HINSTANCE(std::ptr::null_mut())
+            {
+                let address = GetProcAddress(hmodule.0 as *const c_void, "XInputGetState\0".as_ptr());
+                info!("{} address 0x{:x}", xinput_version, address as u64);
+                return Some(address as u64);
+            }
+        }
+    }
+    return None;
+}
+
+///Hooks XInputGetState for games that opt in with Game::hooks_input, every read of the first controller is
+///passed through the app's input chain.
+#[derive(Default)]
+pub struct XInputHook
+{
+    #[cfg(target_arch = "x86_64")]
+    hook: Option<HookPoint>,
+    attempted: bool,
+}
+
+impl XInputHook
+{
+    ///Installs the hook once the game has loaded an xinput dll, a failed attempt isn't retried
+    #[cfg(target_arch = "x86_64")]
+    pub fn refresh(&mut self) -> Result<(), Error>
+    {
+        if self.attempted
+        {
+            return Ok(());
+        }
+
+        let address = match get_xinput_get_state_fn_address()
+        {
+            Some(address) => address,
+            None => return Ok(()),
+        };
+        self.attempted = true;
+
+        let h = Hooker::new(address as usize, HookType::Retn(xinput_get_state_hook_fn), CallbackOption::None, 0, HookFlags::empty());
+        self.hook = Some(unsafe{ h.hook() }.map_err(|e| Error::hook("XInputGetState", e))?);
+        info!("hooked XInputGetState");
+        return Ok(());
+    }
+
+    #[cfg(target_arch = "x86")]
+    pub fn refresh(&mut self) -> Result<(), Error>
+    {
+        if self.attempted
+        {
+            return Ok(());
+        }
+        self.attempted = true;
+        return Err(Error::Hook { name: String::from("XInputGetState"), message: String::from("not supported on x86") });
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+unsafe extern "win64" fn xinput_get_state_hook_fn(registers: *mut Registers, ori_func_ptr: usize, _: usize) -> usize
+{
+    let original_func: XInputGetState = mem::transmute(ori_func_ptr);
+
+    let dw_user_index = (*registers).rcx as u32;
+    let p_state = (*registers).rdx as *mut XINPUT_STATE;
+    let res = original_func(dw_user_index, p_state);
+
+    //Only the first controller goes through the chain
+    if dw_user_index != 0
+    {
+        return res as usize;
+    }
+
+    let connected = res == ERROR_SUCCESS.0;
+    let input = if connected { read_input(p_state) } else { TasInput::default() };
+    let mut frame = InputFrame { user_index: dw_user_index, connected, input };
+
+    let instance = App::get_instance();
+    let mut app = instance.lock().unwrap();
+    app.modify_input(&mut frame);
+
+    if frame.input != input || frame.connected != connected
+    {
+        write_input(&frame.input, p_state);
+    }
+
+    //A modifier can drive a controller that isn't plugged in
+    return if frame.connected { ERROR_SUCCESS.0 as usize } else { res as usize };
+}
@@ -0,0 +1,157 @@
+// This file is part of the soulmemory-rs distribution (https://github.com/FrankvdStam/soulmemory-rs).
+// Copyright (c) 2022 Frank van der Stam.
+// https://github.com/FrankvdStam/soulmemory-rs/blob/main/LICENSE
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use serde::{Deserialize, Serialize};
+
+pub const LAYOUT_FILE: &str = "layout.json";
+pub const MAIN_WINDOW: &str = "soulmemory-rs";
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct WindowLayout
+{
+    pub position: [f32; 2],
+    pub size: [f32; 2],
+    #[serde(default)]
+    pub collapsed: bool,
+    ///Only used for widgets, true when the widget has a window of its own instead of being part of the main window
+    #[serde(default)]
+    pub detached: bool,
+}
+
+impl Default for WindowLayout
+{
+    fn default() -> Self
+    {
+        WindowLayout { position: [50.0, 50.0], size: [350.0, 800.0], collapsed: false, detached: false }
+    }
+}
+
+///Overlay visibility and the windows the widgets are drawn in, stored in layout.json next to the dll
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Layout
+{
+    #[serde(default = "default_visible")]
+    pub overlay_visible: bool,
+    #[serde(default)]
+    pub windows: BTreeMap<String, WindowLayout>,
+}
+
+fn default_visible() -> bool
+{
+    return true;
+}
+
+impl Default for Layout
+{
+    fn default() -> Self
+    {
+        Layout { overlay_visible: true, windows: BTreeMap::new() }
+    }
+}
+
+impl Layout
+{
+    ///The defaults when the file doesn't exist yet
+    pub fn load(path: &Path) -> Result<Layout, String>
+    {
+        if !path.exists()
+        {
+            return Ok(Layout::default());
+        }
+
+        let text = fs::read_to_string(path).map_err(|e| format!("failed to read {}: {}", path.display(), e))?;
+        return serde_json::from_str::<Layout>(&text).map_err(|e| format!("{}: {}", path.display(), e));
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String>
+    {
+        let text = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        return fs::write(path, text).map_err(|e| format!("failed to write {}: {}", path.display(), e));
+    }
+
+    ///A window that was never seen before is placed next to the main window
+    pub fn window(&self, name: &str) -> WindowLayout
+    {
+        if let Some(window) = self.windows.get(name)
+        {
+            return *window;
+        }
+
+        let mut window = WindowLayout::default();
+        if name != MAIN_WINDOW
+        {
+            window.position = [420.0, 50.0 + 30.0 * self.windows.len() as f32];
+            window.size = [350.0, 300.0];
+        }
+        return window;
+    }
+
+    pub fn is_detached(&self, name: &str) -> bool
+    {
+        return self.windows.get(name).map(|w| w.detached).unwrap_or(false);
+    }
+
+    pub fn set_detached(&mut self, name: &str, detached: bool)
+    {
+        let mut window = self.window(name);
+        window.detached = detached;
+        self.windows.insert(String::from(name), window);
+    }
+
+    ///Stores where imgui put a window this frame. Collapsed windows keep their last known position and size.
+    pub fn update(&mut self, name: &str, position: Option<[f32; 2]>, size: Option<[f32; 2]>, collapsed: bool)
+    {
+        let mut window = self.window(name);
+        window.position = position.unwrap_or(window.position);
+        window.size = size.unwrap_or(window.size);
+        window.collapsed = collapsed;
+        self.windows.insert(String::from(name), window);
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use crate::layout::*;
+
+    #[test]
+    fn update_and_round_trip()
+    {
+        let mut layout = Layout::default();
+        assert_eq!(WindowLayout::default(), layout.window(MAIN_WINDOW));
+        assert!(!layout.is_detached("tas"));
+
+        layout.set_detached("tas", true);
+        layout.update("tas", Some([10.0, 20.0]), Some([200.0, 100.0]), false);
+        layout.update("tas", None, None, true);
+        let tas = layout.window("tas");
+        assert!(tas.detached && tas.collapsed);
+        assert_eq!([10.0, 20.0], tas.position);
+        assert_eq!([200.0, 100.0], tas.size);
+
+        layout.overlay_visible = false;
+        let path = std::env::temp_dir().join(format!("soulmemory-layout-test-{}.json", std::process::id()));
+        layout.save(&path).unwrap();
+        let loaded = Layout::load(&path);
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(layout, loaded.unwrap());
+        assert_eq!(Layout::default(), Layout::load(&path.with_extension("missing")).unwrap());
+        assert!(Layout::load(Path::new("does/not/exist.json")).unwrap().overlay_visible);
+    }
+}
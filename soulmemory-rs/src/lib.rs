@@ -17,18 +17,30 @@
 mod util;
 pub mod app;
 pub mod games;
-mod widgets;
-mod tas;
+pub mod widgets;
+mod input;
 mod render_hooks;
+pub mod recorder;
+pub mod flag_metadata;
+pub mod splits;
+pub mod positions;
+pub mod chr_dbg_flags;
+pub mod macros;
+pub mod hotkeys;
+pub mod layout;
+pub mod error;
+pub mod versions;
 
 use std::time::Duration;
 use std::ffi::c_void;
 use std::{panic, thread};
-use log::{error, info, LevelFilter};
+use log::{error, info};
 use mem_rs::prelude::*;
 use windows::Win32::Foundation::{BOOL, HINSTANCE};
 use windows::Win32::System::SystemServices::{DLL_PROCESS_ATTACH, DLL_PROCESS_DETACH};
 use crate::render_hooks::RenderHooks;
+use soulmemory_config::{Config, CONFIG_FILE};
+use crate::error::Error;
 
 
 pub use app::App;
@@ -62,7 +74,24 @@ pub unsafe extern "system" fn DllMain(
 fn dispatched_dll_main()
 {
     util::console::init_console();
-    util::log::init_log(LevelFilter::Info);
+
+    //Read before anything else, it decides where the log goes
+    let module_directory = util::get_module_directory(unsafe{ HMODULE });
+    let config_path = module_directory.as_ref().map(|d| d.join(CONFIG_FILE));
+    let (config, config_error) = match config_path.as_ref().map(|p| Config::load(p))
+    {
+        Some(Ok(config)) => (config, None),
+        Some(Err(e)) => (Config::default(), Some(e)),
+        None => (Config::default(), None),
+    };
+    if let Err(e) = util::log::init_log(&config.log.resolve_path(module_directory.as_deref()), config.log.level.to_level_filter())
+    {
+        error!("logging to the console only: {}", e);
+    }
+    if let Some(e) = &config_error
+    {
+        error!("invalid config, using the defaults: {}", e);
+    }
 
     //Redirect panics
     panic::set_hook(Box::new(|i| {
@@ -70,12 +99,32 @@ fn dispatched_dll_main()
         error!("{}", i);
     }));
 
-    let process_name = Process::get_current_process_name().unwrap();
+    let process_name = match Process::get_current_process_name()
+    {
+        Ok(process_name) => process_name,
+        Err(()) =>
+        {
+            error!("failed to get the process name");
+            return;
+        }
+    };
     info!("initializing, process: {}", process_name);
-    unsafe{ App::init(&process_name, HMODULE) };
+    if let Err(e) = unsafe{ App::init_with_config(&process_name, HMODULE, config) }
+    {
+        error!("{}", e);
+        return;
+    }
+
+    if let Some(e) = config_error
+    {
+        App::get_instance().lock().unwrap().report_error(Error::Config(e));
+    }
 
     info!("initializing render loop");
-    RenderHooks::init();
+    if let Err(e) = RenderHooks::init()
+    {
+        App::get_instance().lock().unwrap().report_error(e);
+    }
 
     info!("starting main loop");
     loop
@@ -96,9 +145,8 @@ fn main_loop()
     let instance = App::get_instance();
     let mut app = instance.lock().unwrap();
 
-    match app.refresh()
+    if let Err(e) = app.refresh()
     {
-        Ok(()) => {}
-        Err(e) => error!("{}", e),
+        app.report_error(e);
     }
 }
@@ -0,0 +1,149 @@
+// This file is part of the soulmemory-rs distribution (https://github.com/FrankvdStam/soulmemory-rs).
+// Copyright (c) 2022 Frank van der Stam.
+// https://github.com/FrankvdStam/soulmemory-rs/blob/main/LICENSE
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+pub mod runner;
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+use soulmemory_config::Binding;
+use crate::games::game_id::GameId;
+use soulmemory_tas::{TasEntry, TasInput, TasScript};
+use soulmemory_tas::script::end_frame;
+use soulmemory_tas::timeline::Timeline;
+
+pub use runner::MacroRunner;
+
+pub const MACROS_DIRECTORY: &str = "macros";
+
+///Holds the input for a number of frames, an empty input is a delay
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MacroStep
+{
+    ///Buttons and axes in the tas script syntax, "a rt=255"
+    pub input: String,
+    pub frames: u32,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Macro
+{
+    pub name: String,
+    ///Default binding of the macro's hotkey action, "f5" or "ctrl+numpad1". The hotkeys tab can rebind it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hotkey: Option<String>,
+    ///Buttons that run the macro when they are all held, "lb rb up"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub chord: Option<String>,
+    pub steps: Vec<MacroStep>,
+}
+
+impl Macro
+{
+    pub fn new(name: String) -> Self
+    {
+        Macro { name, hotkey: None, chord: None, steps: Vec::new() }
+    }
+
+    ///The steps played one after the other
+    pub fn timeline(&self) -> Result<Timeline, String>
+    {
+        let mut script = TasScript::default();
+        let mut frame = 0;
+        for step in &self.steps
+        {
+            let input = TasInput::parse(&step.input).map_err(|e| format!("{}: {}", self.name, e))?;
+            script.entries.push(TasEntry { frame, hold: step.frames, time: None, input });
+            frame = end_frame(frame, step.frames).map_err(|e| format!("{}: {}", self.name, e))?;
+        }
+        return Ok(Timeline::new(&script));
+    }
+
+    ///The chord's button bits, 0 without a chord
+    pub fn chord_buttons(&self) -> Result<u16, String>
+    {
+        let chord = match &self.chord
+        {
+            Some(chord) => TasInput::parse(chord).map_err(|e| format!("{}: {}", self.name, e))?,
+            None => return Ok(0),
+        };
+        if chord != (TasInput { buttons: chord.buttons, ..Default::default() })
+        {
+            return Err(format!("{}: a chord can only contain buttons", self.name));
+        }
+        return Ok(chord.buttons);
+    }
+
+    ///The hotkey action that runs the macro
+    pub fn action_id(&self) -> String
+    {
+        return format!("macro_{}", self.name);
+    }
+
+    pub fn hotkey_binding(&self) -> Result<Option<Binding>, String>
+    {
+        return Binding::parse(self.hotkey.as_deref().unwrap_or_default()).map_err(|e| format!("{}: {}", self.name, e));
+    }
+}
+
+///The macros of a single game, stored as <directory>/<game>.json
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MacroList
+{
+    pub game: GameId,
+    #[serde(default)]
+    pub macros: Vec<Macro>,
+}
+
+impl MacroList
+{
+    pub fn new(game: GameId) -> Self
+    {
+        MacroList { game, macros: Vec::new() }
+    }
+
+    pub fn path(directory: &Path, game: GameId) -> PathBuf
+    {
+        return directory.join(format!("{}.json", game));
+    }
+
+    ///Returns an empty list when the file doesn't exist yet
+    pub fn load(path: &Path, game: GameId) -> Result<Self, String>
+    {
+        if !path.exists()
+        {
+            return Ok(MacroList::new(game));
+        }
+
+        let text = fs::read_to_string(path).map_err(|e| format!("failed to read {}: {}", path.display(), e))?;
+        let list = serde_json::from_str::<MacroList>(&text).map_err(|e| format!("{}: {}", path.display(), e))?;
+        if list.game != game
+        {
+            return Err(format!("{} contains macros for {}, not {}", path.display(), list.game, game));
+        }
+        return Ok(list);
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String>
+    {
+        if let Some(directory) = path.parent()
+        {
+            fs::create_dir_all(directory).map_err(|e| format!("failed to create {}: {}", directory.display(), e))?;
+        }
+        let text = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        return fs::write(path, text).map_err(|e| format!("failed to write {}: {}", path.display(), e));
+    }
+}
@@ -0,0 +1,164 @@
+// This file is part of the soulmemory-rs distribution (https://github.com/FrankvdStam/soulmemory-rs).
+// Copyright (c) 2022 Frank van der Stam.
+// https://github.com/FrankvdStam/soulmemory-rs/blob/main/LICENSE
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use crate::input::{InputContext, InputFrame, InputModifier};
+use crate::macros::Macro;
+use soulmemory_tas::timeline::Timeline;
+
+struct CompiledMacro
+{
+    name: String,
+    timeline: Timeline,
+    chord: u16,
+}
+
+///Plays macros on top of the player's input. Hotkey actions queue a macro from the overlay, chords are detected in the
+///input itself. The chord's buttons are released while its macro runs, so they don't leak into the macro.
+///Macros are triggered by name, the overlay's list can be edited without the runner knowing until it is saved.
+#[derive(Default)]
+pub struct MacroRunner
+{
+    macros: Vec<CompiledMacro>,
+    ///Index and frame of the macro that is playing
+    running: Option<(usize, u32)>,
+    pending: Option<usize>,
+    chord_held: bool,
+}
+
+impl MacroRunner
+{
+    ///Replaces the macros, nothing changes when one of them is invalid
+    pub fn load(&mut self, macros: &[Macro]) -> Result<(), String>
+    {
+        let mut compiled = Vec::new();
+        for m in macros
+        {
+            if compiled.iter().any(|c: &CompiledMacro| c.name == m.name)
+            {
+                return Err(format!("there is more than one macro named {}", m.name));
+            }
+            m.hotkey_binding()?;
+            compiled.push(CompiledMacro { name: m.name.clone(), timeline: m.timeline()?, chord: m.chord_buttons()? });
+        }
+        self.macros = compiled;
+        self.running = None;
+        self.pending = None;
+        return Ok(());
+    }
+
+    ///Runs the macro from the next frame on, cutting off the one that is playing. Macros that weren't loaded are ignored.
+    pub fn trigger(&mut self, name: &str)
+    {
+        if let Some(index) = self.macros.iter().position(|m| m.name == name)
+        {
+            self.pending = Some(index);
+        }
+    }
+
+    ///Name of the macro that is playing
+    pub fn running(&self) -> Option<&str>
+    {
+        return self.running.map(|(index, _)| self.macros[index].name.as_str());
+    }
+}
+
+impl InputModifier for MacroRunner
+{
+    fn modify(&mut self, _context: &InputContext, frame: &mut InputFrame)
+    {
+        let buttons = frame.input.buttons;
+        let chord = self.macros.iter().position(|m| m.chord != 0 && buttons & m.chord == m.chord);
+        if let Some(index) = self.pending.take()
+        {
+            self.running = Some((index, 0));
+        }
+        else if self.running.is_none() && !self.chord_held
+        {
+            self.running = chord.map(|index| (index, 0));
+        }
+        self.chord_held = chord.is_some();
+
+        if let Some((index, macro_frame)) = self.running
+        {
+            let compiled = &self.macros[index];
+            match compiled.timeline.input_at(macro_frame)
+            {
+                Some(input) =>
+                {
+                    frame.input.buttons &= !compiled.chord;
+                    frame.input.merge(&input);
+                    frame.connected = true;
+                    self.running = Some((index, macro_frame + 1));
+                }
+                None => self.running = None,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use crate::input::{InputContext, InputFrame, InputModifier};
+    use crate::macros::{Macro, MacroRunner, MacroStep};
+    use soulmemory_tas::TasInput;
+
+    fn frame(buttons: u16) -> InputFrame
+    {
+        InputFrame { user_index: 0, connected: true, input: TasInput { buttons, ..Default::default() } }
+    }
+
+    #[test]
+    fn chord_and_hotkey()
+    {
+        let mut deflect = Macro::new(String::from("deflect"));
+        deflect.chord = Some(String::from("lb up"));
+        deflect.steps = vec![MacroStep { input: String::from("lb"), frames: 1 }, MacroStep { input: String::new(), frames: 2 }, MacroStep { input: String::from("lb"), frames: 1 }];
+
+        let mut runner = MacroRunner::default();
+        runner.load(&[deflect]).unwrap();
+
+        //Holding the chord runs the macro once, with the chord itself released
+        let mut buttons = Vec::new();
+        for _ in 0..6
+        {
+            let mut f = frame(0x0101);
+            runner.modify(&InputContext::default(), &mut f);
+            buttons.push(f.input.buttons);
+        }
+        assert_eq!(vec![0x0100, 0, 0, 0x0100, 0x0101, 0x0101], buttons);
+        assert_eq!(None, runner.running());
+
+        runner.trigger("unsaved");
+        runner.trigger("deflect");
+        let mut f = frame(0x1000);
+        runner.modify(&InputContext::default(), &mut f);
+        assert_eq!(0x1100, f.input.buttons);
+        assert_eq!(Some("deflect"), runner.running());
+
+        let mut duplicate = Macro::new(String::from("deflect"));
+        duplicate.steps = vec![MacroStep { input: String::from("a"), frames: 1 }];
+        assert!(runner.load(&[duplicate.clone(), duplicate]).unwrap_err().contains("deflect"));
+
+        let mut invalid = Macro::new(String::from("invalid"));
+        invalid.chord = Some(String::from("lx=100"));
+        assert!(runner.load(&[invalid]).is_err());
+
+        let mut invalid = Macro::new(String::from("invalid"));
+        invalid.hotkey = Some(String::from("hyper+f5"));
+        assert!(runner.load(&[invalid]).unwrap_err().contains("hyper"));
+    }
+}
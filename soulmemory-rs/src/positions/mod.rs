@@ -0,0 +1,165 @@
+// This file is part of the soulmemory-rs distribution (https://github.com/FrankvdStam/soulmemory-rs).
+// Copyright (c) 2022 Frank van der Stam.
+// https://github.com/FrankvdStam/soulmemory-rs/blob/main/LICENSE
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+use crate::games::game_id::GameId;
+use crate::util::vector3f::Vector3f;
+
+pub const POSITIONS_DIRECTORY: &str = "positions";
+pub const SLOT_COUNT: usize = 9;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SavedPosition
+{
+    pub name: String,
+    pub position: Vector3f,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub angle: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub map_id: Option<u32>,
+}
+
+impl SavedPosition
+{
+    ///Positions in map-local coordinates only make sense in the map they were saved in
+    pub fn check_map(&self, current_map_id: Option<u32>) -> Result<(), String>
+    {
+        return match (self.map_id, current_map_id)
+        {
+            (Some(saved), Some(current)) if saved != current => Err(format!("{} was saved in map {}, not in the current map {}", self.name, saved, current)),
+            (Some(saved), None) => Err(format!("{} was saved in map {}, the current map is unknown", self.name, saved)),
+            _ => Ok(()),
+        };
+    }
+}
+
+/// Named positions and quick slots of a single game, stored as <directory>/<game>.json.
+/// The file is read at injection, so a shared practice route can be dropped in place of it.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PositionList
+{
+    pub game: GameId,
+    #[serde(default)]
+    pub positions: Vec<SavedPosition>,
+    #[serde(default)]
+    pub slots: Vec<Option<SavedPosition>>,
+}
+
+impl PositionList
+{
+    pub fn new(game: GameId) -> Self
+    {
+        PositionList { game, positions: Vec::new(), slots: vec![None; SLOT_COUNT] }
+    }
+
+    pub fn path(directory: &Path, game: GameId) -> PathBuf
+    {
+        return directory.join(format!("{}.json", game));
+    }
+
+    ///Returns an empty list when the file doesn't exist yet
+    pub fn load(path: &Path, game: GameId) -> Result<Self, String>
+    {
+        if !path.exists()
+        {
+            return Ok(PositionList::new(game));
+        }
+
+        let text = fs::read_to_string(path).map_err(|e| format!("failed to read {}: {}", path.display(), e))?;
+        let mut list = serde_json::from_str::<PositionList>(&text).map_err(|e| format!("{}: {}", path.display(), e))?;
+        if list.game != game
+        {
+            return Err(format!("{} contains positions for {}, not {}", path.display(), list.game, game));
+        }
+        list.slots.resize(SLOT_COUNT, None);
+        return Ok(list);
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String>
+    {
+        if let Some(directory) = path.parent()
+        {
+            fs::create_dir_all(directory).map_err(|e| format!("failed to create {}: {}", directory.display(), e))?;
+        }
+        let text = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        return fs::write(path, text).map_err(|e| format!("failed to write {}: {}", path.display(), e));
+    }
+
+    ///Appends the positions of another file that aren't in this list yet, returns how many were added
+    pub fn import(&mut self, path: &Path) -> Result<usize, String>
+    {
+        if !path.exists()
+        {
+            return Err(format!("{} does not exist", path.display()));
+        }
+
+        let other = PositionList::load(path, self.game)?;
+        let mut count = 0;
+        for position in other.positions
+        {
+            if !self.positions.contains(&position)
+            {
+                self.positions.push(position);
+                count += 1;
+            }
+        }
+        return Ok(count);
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use crate::games::game_id::GameId;
+    use crate::positions::*;
+
+    #[test]
+    fn check_map()
+    {
+        let mut saved = SavedPosition { name: String::from("a"), position: Vector3f::default(), angle: None, map_id: None };
+        assert!(saved.check_map(None).is_ok() && saved.check_map(Some(10)).is_ok());
+
+        saved.map_id = Some(10);
+        assert!(saved.check_map(Some(10)).is_ok());
+        assert!(saved.check_map(Some(11)).unwrap_err().contains("map 10"));
+        assert!(saved.check_map(None).is_err());
+    }
+
+    #[test]
+    fn save_load_and_import()
+    {
+        let directory = std::env::temp_dir().join("soulmemory-positions-test");
+        let _ = fs::remove_dir_all(&directory);
+
+        let mut list = PositionList::new(GameId::Sekiro);
+        list.positions.push(SavedPosition { name: String::from("a"), position: Vector3f::new(1.0, 2.0, 3.0), angle: Some(0.5), map_id: None });
+        list.slots[2] = Some(list.positions[0].clone());
+        let path = PositionList::path(&directory, GameId::Sekiro);
+        list.save(&path).unwrap();
+        assert_eq!(list, PositionList::load(&path, GameId::Sekiro).unwrap());
+        assert!(PositionList::load(&path, GameId::EldenRing).is_err());
+
+        let mut other = PositionList::new(GameId::Sekiro);
+        other.positions.push(SavedPosition { name: String::from("b"), position: Vector3f::default(), angle: None, map_id: Some(10) });
+        assert_eq!(1, other.import(&path).unwrap());
+        assert_eq!(0, other.import(&path).unwrap());
+        assert_eq!(2, other.positions.len());
+
+        let _ = fs::remove_dir_all(&directory);
+    }
+}
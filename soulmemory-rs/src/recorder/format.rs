@@ -0,0 +1,198 @@
+// This file is part of the soulmemory-rs distribution (https://github.com/FrankvdStam/soulmemory-rs).
+// Copyright (c) 2022 Frank van der Stam.
+// https://github.com/FrankvdStam/soulmemory-rs/blob/main/LICENSE
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use chrono::{DateTime, Local, TimeZone};
+use serde::{Deserialize, Serialize};
+use crate::games::game_id::GameId;
+use crate::games::traits::buffered_event_flags::EventFlag;
+
+//Binary layout:
+//header: magic (4 bytes), version (u8), game id (u8)
+//record: time in unix milliseconds (i64 LE), flag (u32 LE), state (u8)
+pub const BINARY_MAGIC: &[u8; 4] = b"SMFR";
+pub const BINARY_VERSION: u8 = 1;
+pub const BINARY_HEADER_SIZE: usize = 6;
+pub const BINARY_RECORD_SIZE: usize = 13;
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum RecordFormat
+{
+    JsonLines,
+    Binary,
+}
+
+impl RecordFormat
+{
+    pub fn extension(&self) -> &'static str
+    {
+        return match self
+        {
+            RecordFormat::JsonLines => "jsonl",
+            RecordFormat::Binary => "smfr",
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RecordedEventFlag
+{
+    pub time: i64,
+    pub game: GameId,
+    pub flag: u32,
+    pub state: bool,
+}
+
+impl RecordedEventFlag
+{
+    pub fn new(game: GameId, event_flag: &EventFlag) -> Self
+    {
+        RecordedEventFlag { time: event_flag.time.timestamp_millis(), game, flag: event_flag.flag, state: event_flag.state }
+    }
+
+    pub fn to_event_flag(&self) -> EventFlag
+    {
+        let time = match Local.timestamp_millis_opt(self.time).single()
+        {
+            Some(time) => time,
+            None => DateTime::default(),
+        };
+        EventFlag::new(time, self.flag, self.state)
+    }
+}
+
+pub fn binary_header(game: GameId) -> [u8; BINARY_HEADER_SIZE]
+{
+    let mut header = [0u8; BINARY_HEADER_SIZE];
+    header[..4].copy_from_slice(BINARY_MAGIC);
+    header[4] = BINARY_VERSION;
+    header[5] = game as u8;
+    return header;
+}
+
+pub fn encode_binary(record: &RecordedEventFlag) -> [u8; BINARY_RECORD_SIZE]
+{
+    let mut buffer = [0u8; BINARY_RECORD_SIZE];
+    buffer[0..8].copy_from_slice(&record.time.to_le_bytes());
+    buffer[8..12].copy_from_slice(&record.flag.to_le_bytes());
+    buffer[12] = record.state as u8;
+    return buffer;
+}
+
+pub fn encode_json_line(record: &RecordedEventFlag) -> Result<String, String>
+{
+    let mut line = serde_json::to_string(record).map_err(|e| e.to_string())?;
+    line.push('\n');
+    return Ok(line);
+}
+
+pub fn is_binary(data: &[u8]) -> bool
+{
+    return data.len() >= BINARY_HEADER_SIZE && &data[..4] == BINARY_MAGIC;
+}
+
+pub fn decode_binary(data: &[u8]) -> Result<(GameId, Vec<RecordedEventFlag>), String>
+{
+    if !is_binary(data)
+    {
+        return Err(String::from("missing binary recording header"));
+    }
+    if data[4] != BINARY_VERSION
+    {
+        return Err(format!("unsupported binary recording version {}", data[4]));
+    }
+    let game = GameId::from_u8(data[5]).ok_or(format!("unknown game id {}", data[5]))?;
+
+    let body = &data[BINARY_HEADER_SIZE..];
+    if body.len() % BINARY_RECORD_SIZE != 0
+    {
+        return Err(format!("truncated binary recording, {} trailing bytes", body.len() % BINARY_RECORD_SIZE));
+    }
+
+    let mut records = Vec::with_capacity(body.len() / BINARY_RECORD_SIZE);
+    for chunk in body.chunks_exact(BINARY_RECORD_SIZE)
+    {
+        records.push(RecordedEventFlag
+        {
+            time: i64::from_le_bytes(chunk[0..8].try_into().unwrap()),
+            game,
+            flag: u32::from_le_bytes(chunk[8..12].try_into().unwrap()),
+            state: chunk[12] != 0,
+        });
+    }
+    return Ok((game, records));
+}
+
+pub fn decode_json_lines(data: &str) -> Result<Vec<RecordedEventFlag>, String>
+{
+    let mut records = Vec::new();
+    for (index, line) in data.lines().enumerate()
+    {
+        let line = line.trim();
+        if line.is_empty()
+        {
+            continue;
+        }
+        let record = serde_json::from_str::<RecordedEventFlag>(line).map_err(|e| format!("line {}: {}", index + 1, e))?;
+        records.push(record);
+    }
+    return Ok(records);
+}
+
+#[cfg(test)]
+mod tests
+{
+    use crate::games::game_id::GameId;
+    use crate::recorder::format::*;
+
+    fn records() -> Vec<RecordedEventFlag>
+    {
+        vec!
+        {
+            RecordedEventFlag { time: 1_700_000_000_000, game: GameId::DarkSouls3, flag: 13000800, state: true },
+            RecordedEventFlag { time: 1_700_000_000_016, game: GameId::DarkSouls3, flag: 50006060, state: false },
+        }
+    }
+
+    #[test]
+    fn binary_round_trip()
+    {
+        let mut data = Vec::from(binary_header(GameId::DarkSouls3));
+        for r in records()
+        {
+            data.extend_from_slice(&encode_binary(&r));
+        }
+
+        let (game, decoded) = decode_binary(&data).unwrap();
+        assert_eq!(GameId::DarkSouls3, game);
+        assert_eq!(records(), decoded);
+
+        data.pop();
+        assert!(decode_binary(&data).is_err());
+    }
+
+    #[test]
+    fn json_lines_round_trip()
+    {
+        let mut data = String::new();
+        for r in records()
+        {
+            data.push_str(&encode_json_line(&r).unwrap());
+        }
+
+        assert!(!is_binary(data.as_bytes()));
+        assert_eq!(records(), decode_json_lines(&data).unwrap());
+    }
+}
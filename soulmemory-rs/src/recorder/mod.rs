@@ -14,6 +14,10 @@
 // You should have received a copy of the GNU General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
-pub mod toggle_mode;
-pub(crate) mod tas;
+pub mod format;
+pub mod recorder;
+pub mod recording;
 
+pub use format::{RecordFormat, RecordedEventFlag};
+pub use recorder::Recorder;
+pub use recording::{Recording, RouteDiff};
@@ -0,0 +1,230 @@
+// This file is part of the soulmemory-rs distribution (https://github.com/FrankvdStam/soulmemory-rs).
+// Copyright (c) 2022 Frank van der Stam.
+// https://github.com/FrankvdStam/soulmemory-rs/blob/main/LICENSE
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::VecDeque;
+use std::fs;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use log::info;
+use crate::games::game_id::GameId;
+use crate::games::traits::buffered_event_flags::EventFlag;
+use crate::recorder::format::*;
+
+///Next to the dll, unless the config says otherwise
+pub const RECORDING_DIRECTORY: &str = "recordings";
+pub const DEFAULT_MAX_FILE_SIZE: u64 = 16 * 1024 * 1024;
+pub const DEFAULT_MAX_FILES: usize = 16;
+pub const DEFAULT_MAX_SESSIONS: usize = 10;
+
+/// Streams every event flag to disk. A session is split into numbered parts once a part exceeds max_file_size,
+/// only the newest max_files parts of a session are kept. Of the game's older sessions only the newest
+/// max_sessions - 1 are kept.
+pub struct Recorder
+{
+    game: GameId,
+    directory: PathBuf,
+    format: RecordFormat,
+    max_file_size: u64,
+    max_files: usize,
+
+    session: String,
+    part: u32,
+    part_paths: VecDeque<PathBuf>,
+    writer: Option<BufWriter<File>>,
+    written: u64,
+}
+
+impl Recorder
+{
+    pub fn new(game: GameId, directory: PathBuf, format: RecordFormat, max_sessions: usize) -> Result<Self, String>
+    {
+        Recorder::with_rotation(game, directory, format, DEFAULT_MAX_FILE_SIZE, DEFAULT_MAX_FILES, max_sessions)
+    }
+
+    pub fn with_rotation(game: GameId, directory: PathBuf, format: RecordFormat, max_file_size: u64, max_files: usize, max_sessions: usize) -> Result<Self, String>
+    {
+        fs::create_dir_all(&directory).map_err(|e| format!("failed to create recording directory {}: {}", directory.display(), e))?;
+        Recorder::prune_sessions(&directory, game, max_sessions.max(1) - 1);
+
+        let mut recorder = Recorder
+        {
+            game,
+            directory,
+            format,
+            max_file_size,
+            max_files: max_files.max(1),
+
+            session: chrono::offset::Local::now().format("%Y%m%d_%H%M%S").to_string(),
+            part: 0,
+            part_paths: VecDeque::new(),
+            writer: None,
+            written: 0,
+        };
+        recorder.open_part()?;
+        return Ok(recorder);
+    }
+
+    ///Removes the parts of all but the newest keep sessions of the game
+    fn prune_sessions(directory: &Path, game: GameId, keep: usize)
+    {
+        let entries = match fs::read_dir(directory)
+        {
+            Ok(entries) => entries,
+            Err(e) =>
+            {
+                info!("failed to list recordings in {}: {}", directory.display(), e);
+                return;
+            }
+        };
+
+        //<game>_<yyyymmdd>_<hhmmss>_<part>.<extension>, the session sorts by time
+        let prefix = format!("{}_", game);
+        let mut parts: Vec<(String, PathBuf)> = Vec::new();
+        for path in entries.filter_map(|e| e.ok()).map(|e| e.path())
+        {
+            let is_recording = path.extension().is_some_and(|e| e == RecordFormat::JsonLines.extension() || e == RecordFormat::Binary.extension());
+            let session = path.file_stem().and_then(|s| s.to_str()).and_then(|s| s.strip_prefix(&prefix)).and_then(|s| s.rsplit_once('_')).map(|(session, _)| String::from(session));
+            if let (true, Some(session)) = (is_recording, session)
+            {
+                parts.push((session, path));
+            }
+        }
+
+        let mut sessions: Vec<&String> = parts.iter().map(|(session, _)| session).collect();
+        sessions.sort();
+        sessions.dedup();
+        let removed: Vec<String> = sessions.iter().rev().skip(keep).map(|s| (*s).clone()).collect();
+        for (session, path) in &parts
+        {
+            if removed.contains(session)
+            {
+                info!("removing old recording {}", path.display());
+                let _ = fs::remove_file(path);
+            }
+        }
+    }
+
+    pub fn get_current_path(&self) -> Option<&PathBuf>
+    {
+        return self.part_paths.back();
+    }
+
+    pub fn record(&mut self, event_flags: &[EventFlag]) -> Result<(), String>
+    {
+        for event_flag in event_flags
+        {
+            if self.written >= self.max_file_size
+            {
+                self.open_part()?;
+            }
+
+            let record = RecordedEventFlag::new(self.game, event_flag);
+            let writer = self.writer.as_mut().ok_or(String::from("recorder has no open file"))?;
+            self.written += match self.format
+            {
+                RecordFormat::JsonLines =>
+                {
+                    let line = encode_json_line(&record)?;
+                    writer.write_all(line.as_bytes()).map_err(|e| e.to_string())?;
+                    line.len() as u64
+                }
+                RecordFormat::Binary =>
+                {
+                    writer.write_all(&encode_binary(&record)).map_err(|e| e.to_string())?;
+                    BINARY_RECORD_SIZE as u64
+                }
+            };
+        }
+
+        //Flush every batch, so that a crashing game doesn't take the recording with it
+        if let Some(writer) = self.writer.as_mut()
+        {
+            writer.flush().map_err(|e| e.to_string())?;
+        }
+        Ok(())
+    }
+
+    fn open_part(&mut self) -> Result<(), String>
+    {
+        if let Some(mut writer) = self.writer.take()
+        {
+            writer.flush().map_err(|e| e.to_string())?;
+        }
+
+        let file_name = format!("{}_{}_{:03}.{}", self.game, self.session, self.part, self.format.extension());
+        let path = self.directory.join(file_name);
+        let file = File::create(&path).map_err(|e| format!("failed to create recording {}: {}", path.display(), e))?;
+        let mut writer = BufWriter::new(file);
+
+        self.written = 0;
+        if self.format == RecordFormat::Binary
+        {
+            writer.write_all(&binary_header(self.game)).map_err(|e| e.to_string())?;
+            self.written = BINARY_HEADER_SIZE as u64;
+        }
+
+        info!("recording event flags to {}", path.display());
+        self.writer = Some(writer);
+        self.part += 1;
+        self.part_paths.push_back(path);
+
+        while self.part_paths.len() > self.max_files
+        {
+            if let Some(oldest) = self.part_paths.pop_front()
+            {
+                let _ = fs::remove_file(oldest);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Drop for Recorder
+{
+    fn drop(&mut self)
+    {
+        if let Some(writer) = self.writer.as_mut()
+        {
+            let _ = writer.flush();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use crate::recorder::recorder::*;
+
+    #[test]
+    fn prune_old_sessions()
+    {
+        let directory = std::env::temp_dir().join(format!("soulmemory-recorder-test-{}", std::process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        let names = ["Sekiro_20240101_100000_000.jsonl", "Sekiro_20240101_100000_001.jsonl", "Sekiro_20240102_100000_000.smfr", "Sekiro_20240103_100000_000.jsonl", "EldenRing_20230101_100000_000.jsonl", "Sekiro_notes.txt"];
+        for name in names
+        {
+            fs::write(directory.join(name), "").unwrap();
+        }
+
+        Recorder::prune_sessions(&directory, GameId::Sekiro, 2);
+        let mut remaining: Vec<String> = fs::read_dir(&directory).unwrap().map(|e| e.unwrap().file_name().into_string().unwrap()).collect();
+        remaining.sort();
+        fs::remove_dir_all(&directory).unwrap();
+
+        assert_eq!(vec!["EldenRing_20230101_100000_000.jsonl", "Sekiro_20240102_100000_000.smfr", "Sekiro_20240103_100000_000.jsonl", "Sekiro_notes.txt"], remaining);
+    }
+}
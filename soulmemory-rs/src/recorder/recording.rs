@@ -0,0 +1,123 @@
+// This file is part of the soulmemory-rs distribution (https://github.com/FrankvdStam/soulmemory-rs).
+// Copyright (c) 2022 Frank van der Stam.
+// https://github.com/FrankvdStam/soulmemory-rs/blob/main/LICENSE
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+use crate::games::game_id::GameId;
+use crate::games::traits::buffered_event_flags::EventFlag;
+use crate::recorder::format::*;
+
+/// A recording loaded back from disk, ordered by time.
+pub struct Recording
+{
+    pub game: Option<GameId>,
+    pub records: Vec<RecordedEventFlag>,
+}
+
+/// Flags that were set to true in only one of two recordings.
+#[derive(Debug, PartialEq)]
+pub struct RouteDiff
+{
+    pub only_in_left: Vec<u32>,
+    pub only_in_right: Vec<u32>,
+}
+
+impl Recording
+{
+    /// Loads a single recording file. The format is detected from the file contents.
+    pub fn load(path: &Path) -> Result<Recording, String>
+    {
+        let data = fs::read(path).map_err(|e| format!("failed to read {}: {}", path.display(), e))?;
+        return Recording::parse(&data).map_err(|e| format!("{}: {}", path.display(), e));
+    }
+
+    /// Loads all parts of a rotated session into a single timeline.
+    pub fn load_parts<P: AsRef<Path>>(paths: &[P]) -> Result<Recording, String>
+    {
+        let mut result = Recording { game: None, records: Vec::new() };
+        for path in paths
+        {
+            let part = Recording::load(path.as_ref())?;
+            if result.game.is_some() && part.game.is_some() && result.game != part.game
+            {
+                return Err(format!("{} belongs to a different game", path.as_ref().display()));
+            }
+            result.game = result.game.or(part.game);
+            result.records.extend(part.records);
+        }
+        result.records.sort_by_key(|r| r.time);
+        return Ok(result);
+    }
+
+    pub fn parse(data: &[u8]) -> Result<Recording, String>
+    {
+        let (game, mut records) = if is_binary(data)
+        {
+            let (game, records) = decode_binary(data)?;
+            (Some(game), records)
+        }
+        else
+        {
+            let text = std::str::from_utf8(data).map_err(|e| e.to_string())?;
+            let records = decode_json_lines(text)?;
+            (records.first().map(|r| r.game), records)
+        };
+
+        records.sort_by_key(|r| r.time);
+        return Ok(Recording { game, records });
+    }
+
+    pub fn to_timeline(&self) -> Vec<EventFlag>
+    {
+        return self.records.iter().map(|r| r.to_event_flag()).collect();
+    }
+
+    /// Every flag that was set to true, in the order it was first set, with the time since the start of the recording.
+    pub fn first_activations(&self) -> Vec<(u32, i64)>
+    {
+        let start = match self.records.first()
+        {
+            Some(r) => r.time,
+            None => return Vec::new(),
+        };
+
+        let mut seen = HashSet::new();
+        let mut result = Vec::new();
+        for r in self.records.iter().filter(|r| r.state)
+        {
+            if seen.insert(r.flag)
+            {
+                result.push((r.flag, r.time - start));
+            }
+        }
+        return result;
+    }
+
+    pub fn diff(&self, other: &Recording) -> RouteDiff
+    {
+        let left: Vec<u32> = self.first_activations().into_iter().map(|(flag, _)| flag).collect();
+        let right: Vec<u32> = other.first_activations().into_iter().map(|(flag, _)| flag).collect();
+        let left_set: HashSet<u32> = left.iter().copied().collect();
+        let right_set: HashSet<u32> = right.iter().copied().collect();
+
+        RouteDiff
+        {
+            only_in_left: left.into_iter().filter(|f| !right_set.contains(f)).collect(),
+            only_in_right: right.into_iter().filter(|f| !left_set.contains(f)).collect(),
+        }
+    }
+}
@@ -23,12 +23,13 @@ use hudhook::hooks::dx9::ImguiDx9Hooks;
 use imgui::Ui;
 use crate::App;
 use crate::games::dx_version::DxVersion;
+use crate::error::Error;
 
 pub struct RenderHooks;
 
 impl RenderHooks
 {
-    pub fn init()
+    pub fn init() -> Result<(), Error>
     {
         let instance = App::get_instance();
         let app = instance.lock().unwrap();
@@ -45,10 +46,7 @@ impl RenderHooks
 
         //builder.with_hmodule(HUDHOOK_HINSTANCE(app.hmodule.0));
 
-        if let Err(e) = builder.build().apply()
-        {
-            panic!("{:?}", e)
-        }
+        return builder.build().apply().map_err(|e| Error::RenderHook(format!("{:?}", e)));
     }
 
     pub fn new() -> Self { RenderHooks {} }
@@ -0,0 +1,375 @@
+// This file is part of the soulmemory-rs distribution (https://github.com/FrankvdStam/soulmemory-rs).
+// Copyright (c) 2022 Frank van der Stam.
+// https://github.com/FrankvdStam/soulmemory-rs/blob/main/LICENSE
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::time::{Duration, Instant};
+use serde::{Deserialize, Serialize};
+use crate::games::traits::buffered_event_flags::EventFlag;
+use crate::splits::split_file::{SplitCondition, SplitFile};
+use crate::util::vector3f::Vector3f;
+
+///Everything the conditions are evaluated against, gathered once per refresh
+pub struct SplitInput<'a>
+{
+    pub event_flags: &'a [EventFlag],
+    pub position: Option<Vector3f>,
+    pub in_game_time: Option<u32>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SplitEvent
+{
+    Start,
+    Split { index: usize, name: String, real_time: u64, in_game_time: Option<u32> },
+    Reset,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum RunState
+{
+    NotRunning,
+    ///Index of the split that is up next
+    Running(usize),
+    Finished,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SplitTime
+{
+    pub real_time: Duration,
+    pub in_game_time: Option<u32>,
+}
+
+pub struct Autosplitter
+{
+    split_file: Option<SplitFile>,
+    state: RunState,
+    started: Option<Instant>,
+    split_times: Vec<SplitTime>,
+    previous_position: Option<Vector3f>,
+    ///A start condition only starts the run when it becomes true, not while it stays true
+    previous_start: bool,
+    manual_events: Vec<SplitEvent>,
+}
+
+impl Default for Autosplitter
+{
+    fn default() -> Self
+    {
+        Autosplitter
+        {
+            split_file: None,
+            state: RunState::NotRunning,
+            started: None,
+            split_times: Vec::new(),
+            previous_position: None,
+            previous_start: false,
+            manual_events: Vec::new(),
+        }
+    }
+}
+
+impl Autosplitter
+{
+    pub fn load(&mut self, split_file: SplitFile)
+    {
+        *self = Autosplitter { split_file: Some(split_file), ..Autosplitter::default() };
+    }
+
+    pub fn split_file(&self) -> Option<&SplitFile>
+    {
+        return self.split_file.as_ref();
+    }
+
+    pub fn state(&self) -> RunState
+    {
+        return self.state;
+    }
+
+    pub fn split_times(&self) -> &[SplitTime]
+    {
+        return &self.split_times;
+    }
+
+    pub fn elapsed(&self) -> Duration
+    {
+        return match (self.state, self.started)
+        {
+            (RunState::Finished, _) => self.split_times.last().map(|t| t.real_time).unwrap_or_default(),
+            (_, Some(started)) => started.elapsed(),
+            _ => Duration::ZERO,
+        }
+    }
+
+    ///Evaluates the conditions against the current game state, returns the events that happened since the last update,
+    ///including the ones triggered by hand from the overlay.
+    pub fn update(&mut self, input: &SplitInput) -> Vec<SplitEvent>
+    {
+        let mut events = std::mem::take(&mut self.manual_events);
+        let split_file = match &self.split_file
+        {
+            Some(split_file) => split_file,
+            None => return events,
+        };
+
+        let reset = split_file.reset.as_ref().map(|c| self.is_met(c, input)).unwrap_or(false);
+        let start = split_file.start.as_ref().map(|c| self.is_met(c, input)).unwrap_or(false);
+        let started = start && !self.previous_start;
+
+        //Flags are only seen in the update they arrive in, so a reset, a start and a split can all happen in one:
+        //a new game flag resets the run and starts the next, a split flag can come with the start flag. The start is
+        //evaluated against the state after the reset, the split against the state after both.
+        if reset && self.state != RunState::NotRunning
+        {
+            events.push(self.reset_run());
+        }
+        if started && self.state == RunState::NotRunning
+        {
+            events.push(self.start_run());
+        }
+        if let RunState::Running(index) = self.state
+        {
+            let split_file = self.split_file.as_ref().unwrap();
+            if self.is_met(&split_file.splits[index].condition, input)
+            {
+                events.push(self.split_run(input.in_game_time));
+            }
+        }
+
+        self.previous_start = start;
+        if input.position.is_some()
+        {
+            self.previous_position = input.position;
+        }
+        return events;
+    }
+
+    pub fn start(&mut self)
+    {
+        if self.split_file.is_some() && self.state == RunState::NotRunning
+        {
+            let event = self.start_run();
+            self.manual_events.push(event);
+        }
+    }
+
+    pub fn reset(&mut self)
+    {
+        if self.state != RunState::NotRunning
+        {
+            let event = self.reset_run();
+            self.manual_events.push(event);
+        }
+    }
+
+    pub fn skip(&mut self)
+    {
+        if let RunState::Running(_) = self.state
+        {
+            let event = self.split_run(None);
+            self.manual_events.push(event);
+        }
+    }
+
+    pub fn undo(&mut self)
+    {
+        match self.state
+        {
+            RunState::Running(index) if index > 0 => self.state = RunState::Running(index - 1),
+            RunState::Finished if !self.split_times.is_empty() => self.state = RunState::Running(self.split_times.len() - 1),
+            _ => return,
+        }
+        self.split_times.pop();
+    }
+
+    fn start_run(&mut self) -> SplitEvent
+    {
+        self.started = Some(Instant::now());
+        self.split_times.clear();
+        self.state = if self.split_file.as_ref().map(|f| f.splits.is_empty()).unwrap_or(true) { RunState::Finished } else { RunState::Running(0) };
+        return SplitEvent::Start;
+    }
+
+    fn reset_run(&mut self) -> SplitEvent
+    {
+        self.started = None;
+        self.split_times.clear();
+        self.state = RunState::NotRunning;
+        return SplitEvent::Reset;
+    }
+
+    fn split_run(&mut self, in_game_time: Option<u32>) -> SplitEvent
+    {
+        let index = match self.state
+        {
+            RunState::Running(index) => index,
+            _ => unreachable!("split_run called while not running"),
+        };
+        let split_file = self.split_file.as_ref().unwrap();
+
+        let real_time = self.started.map(|s| s.elapsed()).unwrap_or_default();
+        self.split_times.push(SplitTime { real_time, in_game_time });
+        self.state = if index + 1 >= split_file.splits.len() { RunState::Finished } else { RunState::Running(index + 1) };
+
+        return SplitEvent::Split { index, name: split_file.splits[index].name.clone(), real_time: real_time.as_millis() as u64, in_game_time };
+    }
+
+    fn is_met(&self, condition: &SplitCondition, input: &SplitInput) -> bool
+    {
+        return match condition
+        {
+            SplitCondition::EventFlag { flag, state } => input.event_flags.iter().any(|f| f.flag == *flag && f.state == *state),
+            SplitCondition::Position { min, max } => match (self.previous_position, input.position)
+            {
+                //Only entering the box counts, standing in it when the condition comes up does not
+                (Some(previous), Some(current)) => !Self::is_inside(min, max, &previous) && Self::is_inside(min, max, &current),
+                _ => false,
+            },
+            SplitCondition::InGameTime { milliseconds } => input.in_game_time.map(|igt| igt >= *milliseconds).unwrap_or(false),
+        }
+    }
+
+    fn is_inside(a: &Vector3f, b: &Vector3f, position: &Vector3f) -> bool
+    {
+        let within = |value: f32, a: f32, b: f32| a.min(b) <= value && value <= a.max(b);
+        return within(position.x, a.x, b.x) && within(position.y, a.y, b.y) && within(position.z, a.z, b.z);
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use chrono::Local;
+    use crate::games::traits::buffered_event_flags::EventFlag;
+    use crate::splits::autosplitter::*;
+    use crate::splits::split_file::{Split, SplitCondition, SplitFile};
+
+    fn split_file() -> SplitFile
+    {
+        return serde_json::from_str(r#"
+        {
+            "name": "test",
+            "start": { "type": "in_game_time", "milliseconds": 1 },
+            "reset": { "type": "event_flag", "flag": 1, "state": false },
+            "splits": [
+                { "name": "boss", "condition": { "type": "event_flag", "flag": 13000800 } },
+                { "name": "bonfire", "condition": { "type": "position", "min": { "x": 0, "y": 0, "z": 0 }, "max": { "x": 10, "y": 10, "z": 10 } } }
+            ]
+        }"#).unwrap();
+    }
+
+    fn input<'a>(event_flags: &'a [EventFlag], position: Vector3f, in_game_time: u32) -> SplitInput<'a>
+    {
+        return SplitInput { event_flags, position: Some(position), in_game_time: Some(in_game_time) };
+    }
+
+    #[test]
+    fn run_through_splits()
+    {
+        let mut autosplitter = Autosplitter::default();
+        autosplitter.load(split_file());
+        let outside = Vector3f::new(20.0, 5.0, 5.0);
+        let inside = Vector3f::new(5.0, 5.0, 5.0);
+
+        assert!(autosplitter.update(&input(&[], inside, 0)).is_empty());
+        assert_eq!(vec![SplitEvent::Start], autosplitter.update(&input(&[], inside, 16)));
+
+        //Standing in the box doesn't split, the flag is up first anyway
+        assert!(autosplitter.update(&input(&[], inside, 32)).is_empty());
+        let boss = [EventFlag::new(Local::now(), 13000800, true)];
+        assert!(matches!(autosplitter.update(&input(&boss, inside, 48))[..], [SplitEvent::Split { index: 0, .. }]));
+        assert_eq!(RunState::Running(1), autosplitter.state());
+
+        assert!(autosplitter.update(&input(&[], inside, 64)).is_empty());
+        assert!(autosplitter.update(&input(&[], outside, 80)).is_empty());
+        assert!(matches!(autosplitter.update(&input(&[], inside, 96))[..], [SplitEvent::Split { index: 1, in_game_time: Some(96), .. }]));
+        assert_eq!(RunState::Finished, autosplitter.state());
+        assert_eq!(2, autosplitter.split_times().len());
+
+        //The start condition stays true, it takes the in game time going back to 0 to start again
+        let reset = [EventFlag::new(Local::now(), 1, false)];
+        assert_eq!(vec![SplitEvent::Reset], autosplitter.update(&input(&reset, outside, 112)));
+        assert!(autosplitter.update(&input(&[], outside, 128)).is_empty());
+        assert!(autosplitter.update(&input(&[], outside, 0)).is_empty());
+        assert_eq!(vec![SplitEvent::Start], autosplitter.update(&input(&[], outside, 16)));
+    }
+
+    #[test]
+    fn reset_and_split_together()
+    {
+        let mut autosplitter = Autosplitter::default();
+        autosplitter.load(split_file());
+        let outside = Vector3f::new(20.0, 5.0, 5.0);
+
+        assert_eq!(vec![SplitEvent::Start], autosplitter.update(&input(&[], outside, 16)));
+        let flags = [EventFlag::new(Local::now(), 1, false), EventFlag::new(Local::now(), 13000800, true)];
+        assert_eq!(vec![SplitEvent::Reset], autosplitter.update(&input(&flags, outside, 32)));
+        assert_eq!(RunState::NotRunning, autosplitter.state());
+        assert!(autosplitter.split_times().is_empty());
+    }
+
+    #[test]
+    fn start_with_flags()
+    {
+        let mut autosplitter = Autosplitter::default();
+        autosplitter.load(serde_json::from_str(r#"
+        {
+            "name": "new game",
+            "start": { "type": "event_flag", "flag": 2 },
+            "reset": { "type": "event_flag", "flag": 1, "state": false },
+            "splits": [
+                { "name": "boss", "condition": { "type": "event_flag", "flag": 13000800 } },
+                { "name": "boss 2", "condition": { "type": "event_flag", "flag": 13000801 } }
+            ]
+        }"#).unwrap());
+        let outside = Vector3f::new(20.0, 5.0, 5.0);
+
+        //The split flag arrives with the start flag
+        let flags = [EventFlag::new(Local::now(), 2, true), EventFlag::new(Local::now(), 13000800, true)];
+        assert!(matches!(autosplitter.update(&input(&flags, outside, 16))[..], [SplitEvent::Start, SplitEvent::Split { index: 0, .. }]));
+        assert_eq!(RunState::Running(1), autosplitter.state());
+
+        //A new game resets the run and starts the next one in the same update
+        assert!(autosplitter.update(&input(&[], outside, 32)).is_empty());
+        let flags = [EventFlag::new(Local::now(), 1, false), EventFlag::new(Local::now(), 2, true)];
+        assert_eq!(vec![SplitEvent::Reset, SplitEvent::Start], autosplitter.update(&input(&flags, outside, 0)));
+        assert_eq!(RunState::Running(0), autosplitter.state());
+        assert!(autosplitter.split_times().is_empty());
+    }
+
+    #[test]
+    fn manual_controls()
+    {
+        let mut autosplitter = Autosplitter::default();
+        autosplitter.load(SplitFile
+        {
+            name: String::new(),
+            start: None,
+            reset: None,
+            splits: vec![Split { name: String::from("a"), condition: SplitCondition::InGameTime { milliseconds: u32::MAX } }],
+        });
+
+        autosplitter.start();
+        autosplitter.skip();
+        assert_eq!(RunState::Finished, autosplitter.state());
+        autosplitter.undo();
+        assert_eq!(RunState::Running(0), autosplitter.state());
+
+        let events = autosplitter.update(&SplitInput { event_flags: &[], position: None, in_game_time: None });
+        assert_eq!(2, events.len());
+        assert_eq!(SplitEvent::Start, events[0]);
+    }
+}
@@ -14,10 +14,10 @@
 // You should have received a copy of the GNU General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
-#[derive(PartialEq, Eq, Copy, Clone)]
-pub enum ToggleMode
-{
-    None,
-    Left,
-    Right
-}
\ No newline at end of file
+pub mod split_file;
+pub mod autosplitter;
+
+pub use split_file::{Split, SplitCondition, SplitFile};
+pub use autosplitter::{Autosplitter, RunState, SplitEvent, SplitInput, SplitTime};
+
+pub const SPLITS_DIRECTORY: &str = "splits";
@@ -0,0 +1,76 @@
+// This file is part of the soulmemory-rs distribution (https://github.com/FrankvdStam/soulmemory-rs).
+// Copyright (c) 2022 Frank van der Stam.
+// https://github.com/FrankvdStam/soulmemory-rs/blob/main/LICENSE
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::fs;
+use std::path::Path;
+use serde::{Deserialize, Serialize};
+use crate::util::vector3f::Vector3f;
+
+/// A json file with ordered split conditions:
+/// {
+///     "name": "any%",
+///     "start": { "type": "in_game_time", "milliseconds": 1 },
+///     "splits": [
+///         { "name": "Gundyr", "condition": { "type": "event_flag", "flag": 14000800 } },
+///         { "name": "Firelink", "condition": { "type": "position", "min": {...}, "max": {...} } }
+///     ]
+/// }
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SplitFile
+{
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub start: Option<SplitCondition>,
+    #[serde(default)]
+    pub reset: Option<SplitCondition>,
+    pub splits: Vec<Split>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Split
+{
+    pub name: String,
+    pub condition: SplitCondition,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SplitCondition
+{
+    ///The flag is set to the given state
+    EventFlag
+    {
+        flag: u32,
+        #[serde(default = "default_state")]
+        state: bool,
+    },
+    ///The player moves from outside into the box, min and max are opposing corners
+    Position { min: Vector3f, max: Vector3f },
+    ///In game time reached the threshold
+    InGameTime { milliseconds: u32 },
+}
+
+fn default_state() -> bool { true }
+
+impl SplitFile
+{
+    pub fn load(path: &Path) -> Result<SplitFile, String>
+    {
+        let text = fs::read_to_string(path).map_err(|e| format!("failed to read {}: {}", path.display(), e))?;
+        return serde_json::from_str::<SplitFile>(&text).map_err(|e| format!("{}: {}", path.display(), e));
+    }
+}
@@ -14,45 +14,56 @@
 // You should have received a copy of the GNU General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
-use std::fs;
+use std::path::Path;
 use log::*;
 use log4rs::*;
 use log4rs::append::console::ConsoleAppender;
 use log4rs::append::file::FileAppender;
 use log4rs::encode::pattern::PatternEncoder;
 use log4rs::config::{Appender, Config, Logger, Root};
+use crate::error::Error;
 
-pub fn init_log(level: LevelFilter)
+///Logs to the console and to logfile_path. The log file comes from the config and can be unusable, that is returned
+///instead of panicking and logging continues on the console.
+pub fn init_log(logfile_path: &Path, level: LevelFilter) -> Result<(), Error>
 {
-    let logfile_path = r#"C:/temp/soulmemory.log"#;
-    let path = std::path::Path::new(logfile_path);
-
-    if path.exists()
-    {
-        fs::remove_file(logfile_path).unwrap();
-    }
-
     //Setup logger
     let stdout = ConsoleAppender::builder()
         .encoder(Box::new(PatternEncoder::new("{d(%Y-%m-%d %H:%M:%S%.3f)} - {m}{n}")))
         .build();
 
-    let requests = FileAppender::builder()
+    let (requests, log_file_error) = match FileAppender::builder()
         .encoder(Box::new(PatternEncoder::new("{d(%Y-%m-%d %H:%M:%S%.3f)} - {m}{n}")))
+        .append(false)
         .build(logfile_path)
-        .unwrap();
+    {
+        Ok(requests) => (Some(requests), None),
+        Err(e) => (None, Some(Error::Config(format!("failed to open log file {}: {}", logfile_path.display(), e)))),
+    };
 
-    let config = Config::builder()
+    let mut config = Config::builder()
         .appender(Appender::builder().build("stdout", Box::new(stdout)))
-        .appender(Appender::builder().build("log_file", Box::new(requests)))
-        .logger(Logger::builder()
-            .build("soulmemory-rs", level))
         .logger(Logger::builder()
-            .appender("log_file")
-            .additive(false)
-            .build("soulmemory-rs::log_file", level))
-        .build(Root::builder().appender("stdout").appender("log_file").build(level))
-        .unwrap();
+            .build("soulmemory-rs", level));
+    let mut root = Root::builder().appender("stdout");
 
-    let _handle = init_config(config).unwrap();
+    if let Some(requests) = requests
+    {
+        config = config
+            .appender(Appender::builder().build("log_file", Box::new(requests)))
+            .logger(Logger::builder()
+                .appender("log_file")
+                .additive(false)
+                .build("soulmemory-rs::log_file", level));
+        root = root.appender("log_file");
+    }
+
+    let config = config.build(root.build(level)).map_err(|e| Error::Config(format!("invalid log config: {}", e)))?;
+    init_config(config).map_err(|e| Error::Config(format!("failed to init logging: {}", e)))?;
+
+    return match log_file_error
+    {
+        Some(e) => Err(e),
+        None => Ok(()),
+    };
 }
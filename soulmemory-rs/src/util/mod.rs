@@ -17,8 +17,45 @@
 pub(crate) mod log;
 pub(crate) mod console;
 pub(crate) mod server;
+pub(crate) mod protocol;
 pub mod vector3f;
 
+use std::path::PathBuf;
+use std::time::Duration;
+use windows::Win32::Foundation::{HINSTANCE, HMODULE};
+use windows::core::PCWSTR;
+use windows::Win32::System::LibraryLoader::{GetModuleFileNameW, GetModuleHandleW};
+
+///Directory that contains the given module, used to locate data files that ship next to the dll
+pub fn get_module_directory(hmodule: HINSTANCE) -> Option<PathBuf>
+{
+    let mut buffer = [0u16; 1024];
+    let length = unsafe{ GetModuleFileNameW(HMODULE(hmodule.0), &mut buffer) } as usize;
+    if length == 0 || length >= buffer.len()
+    {
+        return None;
+    }
+
+    let mut path = PathBuf::from(String::from_utf16_lossy(&buffer[..length]));
+    path.pop();
+    return Some(path);
+}
+
+///The first page of the game's executable, it holds the PE headers
+pub fn read_main_module_header() -> Option<Vec<u8>>
+{
+    let hmodule = unsafe{ GetModuleHandleW(PCWSTR::null()) }.ok()?;
+    let header = unsafe{ std::slice::from_raw_parts(hmodule.0 as *const u8, 0x1000) };
+    return Some(header.to_vec());
+}
+
+///h:mm:ss.mmm
+pub fn format_duration(duration: Duration) -> String
+{
+    let millis = duration.as_millis();
+    return format!("{}:{:02}:{:02}.{:03}", millis / 3_600_000, (millis / 60_000) % 60, (millis / 1000) % 60, millis % 1000);
+}
+
 pub unsafe fn get_stack_u32(esp: u32, offset: usize) -> u32
 {
     *((esp as usize + offset) as usize as *mut u32)
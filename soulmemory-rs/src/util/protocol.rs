@@ -0,0 +1,283 @@
+// This file is part of the soulmemory-rs distribution (https://github.com/FrankvdStam/soulmemory-rs).
+// Copyright (c) 2022 Frank van der Stam.
+// https://github.com/FrankvdStam/soulmemory-rs/blob/main/LICENSE
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//Newline delimited json. Every line a client sends is a request, the server answers each request with exactly one
+//response line carrying the same id. Malformed lines are answered right away, so match responses by id.
+//
+//-> {"version":1,"id":1,"method":"get_event_flag","params":{"flag":13000800}}
+//<- {"version":1,"id":1,"result":"event_flag","data":{"flag":13000800,"state":true}}
+//
+//After subscribing, the server pushes notifications on the same connection. Notifications have no id.
+//-> {"version":1,"id":2,"method":"subscribe","params":{"ranges":[[13000000,13009999]]}}
+//<- {"version":1,"id":2,"result":"ok"}
+//<- {"version":1,"event":"event_flag","data":{"time":1700000000000,"flag":13000800,"state":true}}
+//<- {"version":1,"event":"split","data":{"kind":"split","index":0,"name":"Gundyr","real_time":61234,"in_game_time":60100}}
+
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use soulmemory_memory::event_flags::{EventFlagSnapshot, snapshot::BLOCK_FLAGS};
+use crate::games::game_id::GameId;
+use crate::games::traits::buffered_event_flags::EventFlag;
+use crate::splits::SplitEvent;
+use soulmemory_tas::PlaybackState;
+use crate::util::vector3f::Vector3f;
+use crate::versions::GameVersion;
+
+pub const PROTOCOL_VERSION: u32 = 1;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RequestEnvelope
+{
+    pub version: u32,
+    pub id: u64,
+    #[serde(flatten)]
+    pub request: Request,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "method", content = "params", rename_all = "snake_case")]
+pub enum Request
+{
+    GetVersion,
+    GetGame,
+    GetPosition,
+    GetEventFlag { flag: u32 },
+    ///Every flag in the game's flag storage, not just the ones the hook saw
+    GetEventFlagSnapshot,
+    SetEventFlag { flag: u32, state: bool },
+    GetChrDbgFlags,
+    SetChrDbgFlag { flag: u32, state: bool },
+    RequestQuitout,
+    ///Loads a tas script from the tas directory next to the dll, the path is relative to it and can't leave it
+    TasLoad { path: String },
+    TasStart,
+    TasStop,
+    TasPause,
+    TasResume,
+    GetTasStatus,
+    Subscribe(FlagFilter),
+    Unsubscribe,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FlagFilter
+{
+    All,
+    Flags(Vec<u32>),
+    ///Inclusive ranges
+    Ranges(Vec<(u32, u32)>),
+}
+
+impl FlagFilter
+{
+    pub fn matches(&self, flag: u32) -> bool
+    {
+        return match self
+        {
+            FlagFilter::All => true,
+            FlagFilter::Flags(flags) => flags.contains(&flag),
+            FlagFilter::Ranges(ranges) => ranges.iter().any(|(start, end)| *start <= flag && flag <= *end),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ResponseEnvelope
+{
+    pub version: u32,
+    pub id: u64,
+    #[serde(flatten)]
+    pub response: Response,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ChrDbgFlagState
+{
+    pub flag: u32,
+    pub name: String,
+    pub state: bool,
+}
+
+///Bit n of word w is flag index * block_size + w * 32 + n
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct EventFlagBlock
+{
+    pub index: u32,
+    pub words: Vec<u32>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "result", content = "data", rename_all = "snake_case")]
+pub enum Response
+{
+    Ok,
+    Error { message: String },
+    Version { protocol: u32, soulmemory: String },
+    Game { game: GameId, version: GameVersion },
+    Position(Vector3f),
+    EventFlag { flag: u32, state: bool },
+    ///Only blocks with a set flag are sent
+    EventFlagSnapshot { time: i64, block_size: u32, blocks: Vec<EventFlagBlock> },
+    ChrDbgFlags { flags: Vec<ChrDbgFlagState> },
+    TasStatus { state: PlaybackState, frame: u32, length: u32 },
+}
+
+impl Response
+{
+    pub fn error<S: Into<String>>(message: S) -> Self
+    {
+        Response::Error { message: message.into() }
+    }
+
+    pub fn unsupported(request: &Request) -> Self
+    {
+        Response::error(format!("{:?} is not supported by the current game", request))
+    }
+
+    pub fn event_flag_snapshot(time: DateTime<Local>, snapshot: &EventFlagSnapshot) -> Self
+    {
+        Response::EventFlagSnapshot
+        {
+            time: time.timestamp_millis(),
+            block_size: BLOCK_FLAGS,
+            blocks: snapshot.blocks().map(|(index, words)| EventFlagBlock { index, words: words.to_vec() }).collect(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct NotificationEnvelope
+{
+    pub version: u32,
+    #[serde(flatten)]
+    pub notification: Notification,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "event", content = "data", rename_all = "snake_case")]
+pub enum Notification
+{
+    EventFlag { time: i64, flag: u32, state: bool },
+    ///Sent before the next notification when the client didn't keep up and notifications had to be dropped
+    Lagged { dropped: u64 },
+    ///Autosplitter events, sent to every subscriber regardless of the flag filter
+    Split(SplitEvent),
+}
+
+impl Notification
+{
+    pub fn event_flag(event_flag: &EventFlag) -> Self
+    {
+        Notification::EventFlag { time: event_flag.time.timestamp_millis(), flag: event_flag.flag, state: event_flag.state }
+    }
+}
+
+pub fn parse_request(line: &str) -> Result<RequestEnvelope, ResponseEnvelope>
+{
+    let envelope = serde_json::from_str::<RequestEnvelope>(line).map_err(|e|
+    {
+        //Still try to recover the id, so the client can match the error to its request
+        let id = serde_json::from_str::<serde_json::Value>(line).ok().and_then(|v| v["id"].as_u64()).unwrap_or(0);
+        ResponseEnvelope { version: PROTOCOL_VERSION, id, response: Response::error(format!("invalid request: {}", e)) }
+    })?;
+
+    if envelope.version != PROTOCOL_VERSION
+    {
+        return Err(ResponseEnvelope
+        {
+            version: PROTOCOL_VERSION,
+            id: envelope.id,
+            response: Response::error(format!("unsupported protocol version {}, expected {}", envelope.version, PROTOCOL_VERSION)),
+        });
+    }
+    return Ok(envelope);
+}
+
+pub fn to_line<T: Serialize>(message: &T) -> String
+{
+    let mut line = serde_json::to_string(message).unwrap_or_default();
+    line.push('\n');
+    return line;
+}
+
+#[cfg(test)]
+mod tests
+{
+    use crate::util::protocol::*;
+
+    #[test]
+    fn parse_requests()
+    {
+        let request = parse_request(r#"{"version":1,"id":7,"method":"get_game"}"#).unwrap();
+        assert_eq!(RequestEnvelope { version: 1, id: 7, request: Request::GetGame }, request);
+
+        let request = parse_request(r#"{"version":1,"id":8,"method":"set_event_flag","params":{"flag":13000800,"state":true}}"#).unwrap();
+        assert_eq!(Request::SetEventFlag { flag: 13000800, state: true }, request.request);
+
+        let request = parse_request(r#"{"version":1,"id":9,"method":"tas_load","params":{"path":"any%.tas"}}"#).unwrap();
+        assert_eq!(Request::TasLoad { path: String::from("any%.tas") }, request.request);
+    }
+
+    #[test]
+    fn reject_invalid_requests()
+    {
+        let error = parse_request(r#"{"version":2,"id":3,"method":"get_game"}"#).unwrap_err();
+        assert_eq!(3, error.id);
+
+        let error = parse_request(r#"{"version":1,"id":4,"method":"launch_missiles"}"#).unwrap_err();
+        assert_eq!(4, error.id);
+        assert!(matches!(error.response, Response::Error { .. }));
+    }
+
+    #[test]
+    fn flag_filters()
+    {
+        let request = parse_request(r#"{"version":1,"id":1,"method":"subscribe","params":{"ranges":[[100,199],[300,300]]}}"#).unwrap();
+        let filter = match request.request
+        {
+            Request::Subscribe(filter) => filter,
+            _ => panic!("expected subscribe"),
+        };
+        assert!(filter.matches(100) && filter.matches(199) && filter.matches(300));
+        assert!(!filter.matches(200) && !filter.matches(301));
+
+        assert!(FlagFilter::Flags(vec![5, 7]).matches(7));
+        assert!(!FlagFilter::Flags(vec![5, 7]).matches(6));
+        assert!(FlagFilter::All.matches(6));
+    }
+
+    #[test]
+    fn serialize_response()
+    {
+        let response = ResponseEnvelope { version: 1, id: 1, response: Response::EventFlag { flag: 13000800, state: true } };
+        assert_eq!("{\"version\":1,\"id\":1,\"result\":\"event_flag\",\"data\":{\"flag\":13000800,\"state\":true}}\n", to_line(&response));
+
+        let mut snapshot = EventFlagSnapshot::new();
+        snapshot.set(1024 + 33, true);
+        let time = DateTime::from_timestamp_millis(1000).unwrap().with_timezone(&Local);
+        match Response::event_flag_snapshot(time, &snapshot)
+        {
+            Response::EventFlagSnapshot { time, block_size, blocks } =>
+            {
+                assert_eq!((1000, 1024, 1), (time, block_size, blocks.len()));
+                assert_eq!(1, blocks[0].index);
+                assert_eq!(&[0, 2], &blocks[0].words[..2]);
+            }
+            _ => panic!("expected a snapshot"),
+        }
+    }
+}
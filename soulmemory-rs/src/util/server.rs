@@ -14,19 +14,55 @@
 // You should have received a copy of the GNU General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
-use std::io::Read;
+use std::collections::{HashMap, VecDeque};
+use std::io::{BufRead, BufReader, Write};
 use std::sync::{Arc, Mutex};
-use std::net::TcpListener;
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{sync_channel, SyncSender, TrySendError};
 use std::thread;
 use std::thread::JoinHandle;
 use log::info;
-use serde::{Deserialize, Serialize};
+use crate::games::traits::buffered_event_flags::EventFlag;
+use crate::util::protocol::*;
+
+///Lines that can be queued for a single client before notifications are dropped
+const CLIENT_QUEUE_SIZE: usize = 4096;
+
+/// A request received from a client, answered from the main loop via reply.
+pub struct PendingRequest
+{
+    pub envelope: RequestEnvelope,
+    reply: SyncSender<String>,
+}
+
+impl PendingRequest
+{
+    pub fn reply(&self, response: Response)
+    {
+        let envelope = ResponseEnvelope { version: PROTOCOL_VERSION, id: self.envelope.id, response };
+        //The client might have disconnected or stopped reading in the meantime, nothing to do about that
+        if let Err(TrySendError::Full(_)) = self.reply.try_send(to_line(&envelope))
+        {
+            info!("client queue full, dropped response to request {}", self.envelope.id);
+        }
+    }
+}
+
+struct Subscription
+{
+    filter: FlagFilter,
+    sender: SyncSender<String>,
+    dropped: u64,
+}
+
+type Subscriptions = Arc<Mutex<HashMap<u64, Subscription>>>;
 
 #[allow(dead_code)]
 pub struct Server
 {
-    messages: Arc<Mutex<Vec<Message>>>,
+    requests: Arc<Mutex<VecDeque<PendingRequest>>>,
+    subscriptions: Subscriptions,
     shutdown: Arc<AtomicBool>,
     handle: Option<JoinHandle<()>>
 }
@@ -37,7 +73,8 @@ impl Default for Server
     {
         Server
         {
-            messages: Arc::new(Mutex::new(Vec::new())),
+            requests: Arc::new(Mutex::new(VecDeque::new())),
+            subscriptions: Arc::new(Mutex::new(HashMap::new())),
             shutdown: Arc::new(AtomicBool::new(false)),
             handle: None,
         }
@@ -48,7 +85,7 @@ impl Drop for Server
 {
     fn drop(&mut self)
     {
-        if !self.shutdown.load(Ordering::Relaxed)
+        if !self.shutdown.load(Ordering::Relaxed) && self.handle.is_some()
         {
             self.shutdown();
         }
@@ -58,10 +95,66 @@ impl Drop for Server
 #[allow(dead_code)]
 impl Server
 {
-    pub fn get_message(&mut self) -> Option<Message>
+    pub fn get_request(&mut self) -> Option<PendingRequest>
+    {
+        let mut guard = self.requests.lock().unwrap();
+        return guard.pop_front();
+    }
+
+    ///Pushes event flags to every subscribed client whose filter matches. Never blocks, clients that can't keep up
+    ///lose notifications and are told how many in a lagged notification. Disconnected clients are removed.
+    pub fn publish_event_flags(&self, event_flags: &[EventFlag])
+    {
+        let mut guard = self.subscriptions.lock().unwrap();
+        guard.retain(|_, subscription|
+        {
+            for event_flag in event_flags
+            {
+                if subscription.filter.matches(event_flag.flag) && !Server::push(subscription, Notification::event_flag(event_flag))
+                {
+                    return false;
+                }
+            }
+            return true;
+        });
+    }
+
+    ///Pushes a notification to every subscribed client, regardless of their flag filter
+    pub fn publish_notification(&self, notification: Notification)
     {
-        let mut guard = self.messages.lock().unwrap();
-        return guard.pop();
+        let mut guard = self.subscriptions.lock().unwrap();
+        guard.retain(|_, subscription| Server::push(subscription, notification.clone()));
+    }
+
+    ///Returns false when the client is gone
+    fn push(subscription: &mut Subscription, notification: Notification) -> bool
+    {
+        if subscription.dropped > 0
+        {
+            let lagged = NotificationEnvelope { version: PROTOCOL_VERSION, notification: Notification::Lagged { dropped: subscription.dropped } };
+            match subscription.sender.try_send(to_line(&lagged))
+            {
+                Ok(()) => subscription.dropped = 0,
+                Err(TrySendError::Full(_)) =>
+                {
+                    subscription.dropped += 1;
+                    return true;
+                }
+                Err(TrySendError::Disconnected(_)) => return false,
+            }
+        }
+
+        let envelope = NotificationEnvelope { version: PROTOCOL_VERSION, notification };
+        match subscription.sender.try_send(to_line(&envelope))
+        {
+            Ok(()) => true,
+            Err(TrySendError::Full(_)) =>
+            {
+                subscription.dropped += 1;
+                true
+            }
+            Err(TrySendError::Disconnected(_)) => false,
+        }
     }
 
     pub fn shutdown(&mut self)
@@ -74,14 +167,26 @@ impl Server
     pub fn new(addr: String) -> Self
     {
         let shutdown = Arc::new(AtomicBool::new(false));
-        let messages = Arc::new(Mutex::new(Vec::new()));
+        let requests = Arc::new(Mutex::new(VecDeque::new()));
+        let subscriptions: Subscriptions = Arc::new(Mutex::new(HashMap::new()));
 
         let thread_shutdown = Arc::clone(&shutdown);
-        let thread_messages = Arc::clone(&messages);
+        let thread_requests = Arc::clone(&requests);
+        let thread_subscriptions = Arc::clone(&subscriptions);
 
         let handle = thread::spawn(move ||
         {
-            let listener = TcpListener::bind(addr).unwrap();
+            let listener = match TcpListener::bind(&addr)
+            {
+                Ok(listener) => listener,
+                Err(e) =>
+                {
+                    info!("server failed to bind {}: {}", addr, e);
+                    return;
+                }
+            };
+
+            let connection_ids = AtomicU64::new(0);
             for stream in listener.incoming()
             {
                 if thread_shutdown.load(Ordering::Relaxed)
@@ -91,23 +196,12 @@ impl Server
 
                 match stream
                 {
-                    Ok(mut s) =>
+                    Ok(s) =>
                     {
-                        //Receive json data
-                        let mut buffer = [0; 1024];
-                        s.read(&mut buffer).unwrap();
-                        let command = String::from_utf8(Vec::from(buffer)).unwrap().trim_matches(char::from(0)).to_string();
-
-                        //Try to parse a received string as json
-                        match serde_json::from_str::<Message>(command.as_str())
-                        {
-                            Ok(message) =>
-                            {
-                                let mut guard = thread_messages.lock().unwrap();
-                                guard.push(message);
-                            }
-                            Err(e) => info!("Parsing incoming message failed. {} Raw massage:\n{}", e, command)
-                        }
+                        let connection_id = connection_ids.fetch_add(1, Ordering::Relaxed);
+                        let connection_requests = Arc::clone(&thread_requests);
+                        let connection_subscriptions = Arc::clone(&thread_subscriptions);
+                        thread::spawn(move || Server::handle_connection(connection_id, s, connection_requests, connection_subscriptions));
                     },
                     Err(e) => info!("connection failed {}", e)
                 }
@@ -116,64 +210,81 @@ impl Server
 
         Server
         {
-            messages,
+            requests,
+            subscriptions,
             shutdown,
             handle: Some(handle),
         }
     }
-}
-
-
-
 
-#[allow(non_snake_case)]
-#[derive(Serialize, Deserialize)]
-pub struct Message
-{
-    pub MessageType: String,
-    pub TasInputsFilePath: String
-}
-
-#[cfg(test)]
-mod tests
-{
-    use crate::util::server::Message;
-
-    #[test]
-    pub fn json_test()
+    fn handle_connection(connection_id: u64, stream: TcpStream, requests: Arc<Mutex<VecDeque<PendingRequest>>>, subscriptions: Subscriptions)
     {
-        let json = r#"{
-      "MessageType": "TasStart",
-      "TasInputsFilePath": ""
-    } "#;
+        let peer = stream.peer_addr().map(|a| a.to_string()).unwrap_or_default();
+        info!("client connected: {}", peer);
 
-        let result = serde_json::from_str::<Message>(json);
-        match result
+        let mut writer = match stream.try_clone()
         {
-            Ok(message) =>
+            Ok(writer) => writer,
+            Err(e) =>
             {
-                println!("{}", message.MessageType);
+                info!("failed to clone client stream: {}", e);
+                return;
             }
-            Err(_) => {}
-        }
-    }
+        };
 
-    #[test]
-    pub fn json_test2()
-    {
-        let json = r#"{
-          "MessageType": "TasReadInputFromFile",
-          "TasInputsFilePath": "C:\\temp\\tas_inputs.json"
-        }"#;
+        //Responses and notifications are written from their own thread, the main loop only ever pushes lines into the channel
+        let (sender, receiver) = sync_channel::<String>(CLIENT_QUEUE_SIZE);
+        thread::spawn(move ||
+        {
+            for line in receiver
+            {
+                if writer.write_all(line.as_bytes()).is_err()
+                {
+                    return;
+                }
+            }
+        });
 
-        let result = serde_json::from_str::<Message>(json);
-        match result
+        for line in BufReader::new(stream).lines()
         {
-            Ok(message) =>
+            let line = match line
+            {
+                Ok(line) => line,
+                Err(_) => break,
+            };
+
+            if line.trim().is_empty()
+            {
+                continue;
+            }
+
+            match parse_request(&line)
+            {
+                //Subscriptions belong to the connection, the main loop doesn't need to see them
+                Ok(envelope) if matches!(envelope.request, Request::Subscribe(_) | Request::Unsubscribe) =>
                 {
-                    println!("{}", message.MessageType);
+                    let mut guard = subscriptions.lock().unwrap();
+                    match &envelope.request
+                    {
+                        Request::Subscribe(filter) => { guard.insert(connection_id, Subscription { filter: filter.clone(), sender: sender.clone(), dropped: 0 }); }
+                        _ => { guard.remove(&connection_id); }
+                    }
+                    PendingRequest { envelope, reply: sender.clone() }.reply(Response::Ok);
                 }
-            Err(_) => {}
+                Ok(envelope) =>
+                {
+                    let mut guard = requests.lock().unwrap();
+                    guard.push_back(PendingRequest { envelope, reply: sender.clone() });
+                }
+                Err(error) =>
+                {
+                    info!("Parsing incoming request failed. Raw request:\n{}", line);
+                    let _ = sender.try_send(to_line(&error));
+                }
+            }
         }
+
+        subscriptions.lock().unwrap().remove(&connection_id);
+        info!("client disconnected: {}", peer);
     }
-}
\ No newline at end of file
+}
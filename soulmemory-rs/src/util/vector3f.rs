@@ -16,8 +16,9 @@
 
 use std::fmt;
 use std::fmt::Display;
+use serde::{Deserialize, Serialize};
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Vector3f
 {
     pub x: f32,
@@ -0,0 +1,267 @@
+// This file is part of the soulmemory-rs distribution (https://github.com/FrankvdStam/soulmemory-rs).
+// Copyright (c) 2022 Frank van der Stam.
+// https://github.com/FrankvdStam/soulmemory-rs/blob/main/LICENSE
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fmt::Display;
+use std::fs;
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+use soulmemory_patterns::Symbol;
+use crate::games::game_id::GameId;
+
+pub const VERSIONS_DIRECTORY: &str = "versions";
+
+///Identifies a build of the game's executable, read from the PE headers of its main module
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PeInfo
+{
+    pub timestamp: u32,
+    pub checksum: u32,
+    pub image_size: u32,
+}
+
+impl PeInfo
+{
+    ///Parses the DOS, COFF and optional header at the start of a module
+    pub fn parse(header: &[u8]) -> Result<PeInfo, String>
+    {
+        let read_u16 = |offset: usize| header.get(offset..offset + 2).map(|b| u16::from_le_bytes([b[0], b[1]]));
+        let read_u32 = |offset: usize| header.get(offset..offset + 4).map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]));
+
+        if read_u16(0) != Some(0x5a4d)
+        {
+            return Err(String::from("missing MZ signature"));
+        }
+
+        let pe = read_u32(0x3c).ok_or("truncated DOS header")? as usize;
+        if read_u32(pe) != Some(0x4550)
+        {
+            return Err(String::from("missing PE signature"));
+        }
+
+        //The fields used here are at the same offsets in PE32 and PE32+ optional headers
+        let optional = pe + 24;
+        return match read_u16(optional)
+        {
+            Some(0x10b) | Some(0x20b) => Ok(PeInfo
+            {
+                timestamp: read_u32(pe + 8).ok_or("truncated COFF header")?,
+                image_size: read_u32(optional + 56).ok_or("truncated optional header")?,
+                checksum: read_u32(optional + 64).ok_or("truncated optional header")?,
+            }),
+            _ => Err(String::from("unknown optional header magic")),
+        };
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct KnownVersion
+{
+    pub name: String,
+    pub timestamp: u32,
+    ///Only compared when present, some builds share a timestamp
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub checksum: Option<u32>,
+    ///Symbols that moved in this build, replacing the ones the backend has built in
+    #[serde(default)]
+    pub symbols: BTreeMap<String, Symbol>,
+    ///Field offsets that moved in this build, by the name the backend reads them under
+    #[serde(default)]
+    pub offsets: BTreeMap<String, usize>,
+}
+
+///The builds of a game we know about, stored as <directory>/<game>.toml. No tables are shipped, every build is
+///unknown and uses the backend's built in patterns and offsets until a user adds it. The timestamp and checksum
+///to add are logged at startup as "unknown build (...)". The values below only show the layout:
+///
+///[[versions]]
+///name = "1.06"
+///timestamp = 0x5cbf3e38
+///
+///[versions.symbols.SprjEventFlagMan]
+///pattern = "48 8b 0d ? ? ? ? 48 89 5c 24 50"
+///offset = 3
+///instruction_length = 7
+///chain = [0]
+///
+///[versions.offsets]
+///igt = 0xa4
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct VersionTable
+{
+    #[serde(default)]
+    pub versions: Vec<KnownVersion>,
+}
+
+impl VersionTable
+{
+    pub fn path(directory: &Path, game: GameId) -> PathBuf
+    {
+        return directory.join(format!("{}.toml", game));
+    }
+
+    ///An empty table when the file doesn't exist
+    pub fn load(path: &Path) -> Result<VersionTable, String>
+    {
+        if !path.exists()
+        {
+            return Ok(VersionTable::default());
+        }
+
+        let text = fs::read_to_string(path).map_err(|e| format!("failed to read {}: {}", path.display(), e))?;
+        return VersionTable::parse(&text).map_err(|e| format!("{}: {}", path.display(), e));
+    }
+
+    pub fn parse(text: &str) -> Result<VersionTable, String>
+    {
+        let table = toml::from_str::<VersionTable>(text).map_err(|e| e.message().to_string())?;
+        for version in &table.versions
+        {
+            for (name, symbol) in &version.symbols
+            {
+                symbol.validate().map_err(|e| format!("{} {}: {}", version.name, name, e))?;
+            }
+        }
+        return Ok(table);
+    }
+
+    pub fn detect(&self, pe: PeInfo) -> GameVersion
+    {
+        let known = self.versions.iter().find(|v| v.timestamp == pe.timestamp && v.checksum.map(|c| c == pe.checksum).unwrap_or(true));
+        return GameVersion
+        {
+            name: known.map(|v| v.name.clone()),
+            pe,
+            symbols: known.map(|v| v.symbols.clone()).unwrap_or_default(),
+            offsets: known.map(|v| v.offsets.clone()).unwrap_or_default(),
+        };
+    }
+}
+
+///The build the app is running in, known or not
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct GameVersion
+{
+    pub name: Option<String>,
+    #[serde(flatten)]
+    pub pe: PeInfo,
+    #[serde(skip)]
+    symbols: BTreeMap<String, Symbol>,
+    #[serde(skip)]
+    offsets: BTreeMap<String, usize>,
+}
+
+impl GameVersion
+{
+    pub fn is_known(&self) -> bool
+    {
+        return self.name.is_some();
+    }
+
+    ///The symbol this build overrides, backends fall back to their own when there is none
+    pub fn symbol(&self, name: &str) -> Option<&Symbol>
+    {
+        return self.symbols.get(name);
+    }
+
+    ///The offset this build overrides, or the backend's own
+    pub fn offset(&self, name: &str, default: usize) -> usize
+    {
+        return self.offsets.get(name).copied().unwrap_or(default);
+    }
+}
+
+impl Display for GameVersion
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        match &self.name
+        {
+            Some(name) => write!(f, "{}", name),
+            None => write!(f, "unknown build (timestamp 0x{:08x}, checksum 0x{:08x})", self.pe.timestamp, self.pe.checksum),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use crate::versions::*;
+
+    fn header(timestamp: u32, checksum: u32, magic: u16) -> Vec<u8>
+    {
+        let mut header = vec![0u8; 0x400];
+        header[0..2].copy_from_slice(b"MZ");
+        header[0x3c..0x40].copy_from_slice(&0x100u32.to_le_bytes());
+        header[0x100..0x104].copy_from_slice(b"PE\0\0");
+        header[0x108..0x10c].copy_from_slice(&timestamp.to_le_bytes());
+        header[0x118..0x11a].copy_from_slice(&magic.to_le_bytes());
+        header[0x118 + 56..0x118 + 60].copy_from_slice(&0x4000000u32.to_le_bytes());
+        header[0x118 + 64..0x118 + 68].copy_from_slice(&checksum.to_le_bytes());
+        return header;
+    }
+
+    #[test]
+    fn parse_pe_header()
+    {
+        assert_eq!(PeInfo { timestamp: 0x5cbf3e38, checksum: 0x1234, image_size: 0x4000000 }, PeInfo::parse(&header(0x5cbf3e38, 0x1234, 0x20b)).unwrap());
+        assert_eq!(0x1234, PeInfo::parse(&header(1, 0x1234, 0x10b)).unwrap().checksum);
+        assert!(PeInfo::parse(&header(1, 1, 0x999)).is_err());
+        assert!(PeInfo::parse(&header(1, 1, 0x20b)[..0x120]).is_err());
+        assert!(PeInfo::parse(b"not a module").is_err());
+    }
+
+    #[test]
+    fn detect_version()
+    {
+        let table = VersionTable::parse(r#"
+            [[versions]]
+            name = "1.05"
+            timestamp = 0x10
+
+            [[versions]]
+            name = "1.06"
+            timestamp = 0x20
+            checksum = 0x99
+
+            [versions.symbols.SprjEventFlagMan]
+            pattern = "48 8b 0d ? ? ? ?"
+            offset = 3
+            instruction_length = 7
+            chain = [0]
+
+            [versions.offsets]
+            igt = 0xa8
+        "#).unwrap();
+
+        let version = table.detect(PeInfo { timestamp: 0x20, checksum: 0x99, image_size: 0 });
+        assert_eq!(Some(String::from("1.06")), version.name);
+        assert_eq!(Some(7), version.symbol("SprjEventFlagMan").unwrap().instruction_length);
+        assert!(version.symbol("igt").is_none());
+        assert_eq!(0xa8, version.offset("igt", 0xa4));
+        assert_eq!(0x80, version.offset("position", 0x80));
+
+        assert_eq!("1.05", table.detect(PeInfo { timestamp: 0x10, checksum: 0x5, image_size: 0 }).to_string());
+        let unknown = table.detect(PeInfo { timestamp: 0x20, checksum: 0x98, image_size: 0 });
+        assert!(!unknown.is_known());
+        assert_eq!("unknown build (timestamp 0x00000020, checksum 0x00000098)", unknown.to_string());
+        assert!(VersionTable::parse("[[versions]]\nname = \"1.0\"\ntimestamp = 1\nsize = 2").is_err());
+        assert!(VersionTable::parse("[[versions]]\nname = \"1.0\"\ntimestamp = 1\n[versions.symbols.igt]\npattern = \"48 8b 05\"\noffset = 3\ninstruction_length = 7").is_err());
+    }
+}
@@ -14,53 +14,134 @@
 // You should have received a copy of the GNU General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
-use std::ops::DerefMut;
+use std::borrow::Cow;
+use std::sync::{Arc, Mutex};
 use imgui::{TreeNodeFlags, Ui};
 use crate::games::*;
 use crate::widgets::widget::Widget;
-use crate::tas::toggle_mode::ToggleMode;
+use crate::input::{AiToggle, AiToggleMode, ScheduleEntry, DEFAULT_PERIOD};
+use soulmemory_tas::script::BUTTONS;
 
 pub struct AiToggleWidget
 {
+    ai_toggle: Arc<Mutex<AiToggle>>,
     selected_toggle_mode_index: u32,
 }
 
 impl AiToggleWidget
 {
-    pub fn new() -> Self
+    pub fn new(ai_toggle: Arc<Mutex<AiToggle>>) -> Self
     {
-        AiToggleWidget { selected_toggle_mode_index: 0}
+        AiToggleWidget { ai_toggle, selected_toggle_mode_index: 0}
+    }
+
+    fn render_schedule(ui: &Ui, ai_toggle: &mut AiToggle)
+    {
+        let mut delete_index = None;
+        for index in 0..ai_toggle.schedule.len()
+        {
+            let id = ui.push_id(format!("entry{}", index));
+            let stats = ai_toggle.stats(index);
+            let entry = &mut ai_toggle.schedule[index];
+
+            let _a = ui.push_item_width(60.0f32);
+            ui.input_float("at", &mut entry.threshold).build();
+            ui.same_line();
+            let mut button_index = BUTTONS.iter().position(|(_, bit)| *bit == entry.button).unwrap_or(0);
+            if ui.combo("press", &mut button_index, BUTTONS, |button| Cow::Borrowed(button.0))
+            {
+                entry.button = BUTTONS[button_index].1;
+            }
+            ui.same_line();
+            let mut frames = entry.frames as i32;
+            if ui.input_int("frames", &mut frames).build()
+            {
+                entry.frames = frames.max(1) as u32;
+            }
+            ui.same_line();
+            if ui.button("x")
+            {
+                delete_index = Some(index);
+            }
+
+            let attempts = stats.hits + stats.misses;
+            if attempts > 0
+            {
+                ui.text(format!("hits {}/{} ({:.0}%)", stats.hits, attempts, stats.hits as f32 * 100.0f32 / attempts as f32));
+                ui.same_line();
+                ui.text(format!("last {:+.3} mean {:+.3}", stats.last_error.unwrap_or_default(), stats.mean_error().unwrap_or_default()));
+            }
+            else
+            {
+                ui.text_disabled("no attempts yet");
+            }
+            id.end();
+        }
+
+        if let Some(index) = delete_index
+        {
+            ai_toggle.schedule.remove(index);
+            ai_toggle.reset_stats();
+        }
+        if ui.button("add toggle")
+        {
+            ai_toggle.schedule.push(ScheduleEntry { threshold: 0.0f32, button: 0x0008, frames: 1 });
+        }
+        ui.same_line();
+        if ui.button("reset stats")
+        {
+            ai_toggle.reset_stats();
+        }
     }
 }
 
 impl Widget for AiToggleWidget
 {
+    fn name(&self) -> &str { "ai_timer" }
+
     fn render(&mut self, game: &mut Box<dyn Game>, ui: &Ui)
     {
-        if let Some(dsr) = GameExt::get_game_mut::<DarkSoulsRemastered>(game.deref_mut())
+        if let Some(ai_timer) = game.ai_timer()
         {
             if ui.collapsing_header("AI timer", TreeNodeFlags::FRAMED)
             {
+                let mut ai_toggle = self.ai_toggle.lock().unwrap();
+
                 ui.text("ai timer value: ");
                 ui.same_line();
-                ui.text(format!("{}", dsr.get_ai_timer_value()));
+                ui.text(format!("{}", ai_timer.get_ai_timer_value()));
+                if let Some(period) = ai_toggle.period()
+                {
+                    ui.same_line();
+                    ui.text_disabled(format!("period {:.3}", period));
+                }
 
-                let _a = ui.push_item_width(100.0f32);
-                ui.input_float("auto toggle timing", &mut dsr.ai_timer_toggle_threshold).build();
+                let history: Vec<f32> = ai_toggle.history().iter().copied().collect();
+                ui.plot_lines("##ai timer", &history)
+                    .scale_min(0.0f32)
+                    .scale_max(ai_toggle.period().unwrap_or(DEFAULT_PERIOD))
+                    .graph_size([ui.content_region_avail()[0], 60.0f32])
+                    .build();
 
                 ui.text("Auto toggle mode:");
-                ui.radio_button("None", &mut self.selected_toggle_mode_index, 0);
-                ui.radio_button("Right hand", &mut self.selected_toggle_mode_index, 1);
-                ui.radio_button("Left hand", &mut self.selected_toggle_mode_index, 2);
+                ui.radio_button("Off", &mut self.selected_toggle_mode_index, 0);
+                ui.same_line();
+                ui.radio_button("Auto", &mut self.selected_toggle_mode_index, 1);
+                ui.same_line();
+                ui.radio_button("Practice", &mut self.selected_toggle_mode_index, 2);
 
-                dsr.ai_timer_toggle_mode = match self.selected_toggle_mode_index
+                ai_toggle.mode = match self.selected_toggle_mode_index
                 {
-                    0 => ToggleMode::None,
-                    1 => ToggleMode::Right,
-                    2 => ToggleMode::Left,
+                    0 => AiToggleMode::Off,
+                    1 => AiToggleMode::Auto,
+                    2 => AiToggleMode::Practice,
                     _ => panic!("unsupported toggle mode: {}", self.selected_toggle_mode_index),
                 };
+
+                let _a = ui.push_item_width(100.0f32);
+                ui.input_float("tolerance", &mut ai_toggle.tolerance).build();
+                Self::render_schedule(ui, &mut ai_toggle);
             }
         }
     }
-}
\ No newline at end of file
+}
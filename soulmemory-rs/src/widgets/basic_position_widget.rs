@@ -1,25 +1,150 @@
+use std::path::PathBuf;
 use imgui::{TreeNodeFlags, Ui};
-use log::info;
+use log::{error, info, warn};
 use crate::games::*;
+use crate::games::game_id::GameId;
+use crate::games::traits::player_position::PlayerPosition;
+use crate::positions::{PositionList, SavedPosition, SLOT_COUNT};
 use crate::widgets::widget::Widget;
+use crate::error::Error;
+use crate::hotkeys::HotkeyManager;
 use crate::util::vector3f::Vector3f;
 
 pub struct PlayerPositionWidget
 {
     position_input_vec: Vector3f,
     position_input_text: String,
-    positions: Vec<(String, Vector3f)>,
+    positions: PositionList,
+    path: Option<PathBuf>,
+    import_export_path: String,
+    status: Option<String>,
 }
 
 impl PlayerPositionWidget
 {
-    pub fn new() -> Self
+    pub fn new(game: GameId, directory: Option<PathBuf>) -> Self
     {
+        let path = directory.map(|d| PositionList::path(&d, game));
+        let positions = match &path
+        {
+            Some(path) => PositionList::load(path, game).unwrap_or_else(|e|
+            {
+                error!("failed to load positions: {}", e);
+                PositionList::new(game)
+            }),
+            None => PositionList::new(game),
+        };
+        info!("loaded {} positions", positions.positions.len());
+
         PlayerPositionWidget
         {
             position_input_vec: Vector3f::default(),
             position_input_text: String::new(),
-            positions: Vec::new(),
+            positions,
+            path,
+            import_export_path: String::new(),
+            status: None,
+        }
+    }
+
+    fn persist(&mut self)
+    {
+        if let Some(path) = &self.path
+        {
+            if let Err(e) = self.positions.save(path)
+            {
+                error!("failed to save positions: {}", e);
+                self.status = Some(e);
+            }
+        }
+    }
+
+    fn capture(name: String, position: &Box<&mut dyn PlayerPosition>) -> SavedPosition
+    {
+        SavedPosition { name, position: position.get_position(), angle: position.get_angle(), map_id: position.get_map_id() }
+    }
+
+    fn restore(saved: &SavedPosition, position: &Box<&mut dyn PlayerPosition>) -> Result<(), String>
+    {
+        saved.check_map(position.get_map_id())?;
+        position.set_position(&saved.position);
+        if let Some(angle) = saved.angle
+        {
+            position.set_angle(angle);
+        }
+        return Ok(());
+    }
+
+    fn report_restore(&mut self, result: Result<(), String>)
+    {
+        if let Err(e) = result
+        {
+            warn!("not restoring position: {}", e);
+            self.status = Some(e);
+        }
+    }
+
+    fn render_slots(&mut self, ui: &Ui, position: &Box<&mut dyn PlayerPosition>)
+    {
+        let mut changed = false;
+        let mut restored = Ok(());
+        for slot in 0..SLOT_COUNT
+        {
+            let id = ui.push_id(format!("slot{}", slot));
+            ui.text(format!("{}", slot + 1));
+            ui.same_line();
+            if ui.button("save")
+            {
+                self.positions.slots[slot] = Some(Self::capture(format!("slot {}", slot + 1), position));
+                changed = true;
+            }
+            ui.same_line();
+            match &self.positions.slots[slot]
+            {
+                Some(saved) =>
+                {
+                    if ui.button("restore")
+                    {
+                        restored = Self::restore(saved, position);
+                    }
+                    ui.same_line();
+                    ui.text(format!("{:.2} {:.2} {:.2}", saved.position.x, saved.position.y, saved.position.z));
+                }
+                None => ui.text_disabled("empty"),
+            }
+            id.end();
+        }
+
+        self.report_restore(restored);
+        if changed
+        {
+            self.persist();
+        }
+    }
+
+    fn render_import_export(&mut self, ui: &Ui)
+    {
+        ui.input_text("file", &mut self.import_export_path).hint("path to a positions .json").build();
+        if ui.button("import")
+        {
+            self.status = Some(match self.positions.import(&PathBuf::from(&self.import_export_path))
+            {
+                Ok(count) =>
+                {
+                    self.persist();
+                    format!("imported {} positions", count)
+                }
+                Err(e) => e,
+            });
+        }
+        ui.same_line();
+        if ui.button("export")
+        {
+            self.status = Some(match self.positions.save(&PathBuf::from(&self.import_export_path))
+            {
+                Ok(()) => format!("exported {} positions", self.positions.positions.len()),
+                Err(e) => e,
+            });
         }
     }
 }
@@ -27,6 +152,52 @@ impl PlayerPositionWidget
 
 impl Widget for PlayerPositionWidget
 {
+    fn name(&self) -> &str { "positions" }
+
+    fn register_hotkeys(&self, hotkeys: &mut HotkeyManager) -> Vec<Error>
+    {
+        let mut errors = Vec::new();
+        for slot in 1..=SLOT_COUNT
+        {
+            errors.extend(hotkeys.register(&format!("save_position_{}", slot), &format!("save position slot {}", slot), &format!("ctrl+shift+{}", slot)).err());
+            errors.extend(hotkeys.register(&format!("restore_position_{}", slot), &format!("restore position slot {}", slot), &format!("ctrl+{}", slot)).err());
+        }
+        return errors;
+    }
+
+    fn on_hotkeys(&mut self, game: &mut Box<dyn Game>, hotkeys: &HotkeyManager)
+    {
+        let position = match game.player_position()
+        {
+            Some(position) => position,
+            None => return,
+        };
+
+        let mut changed = false;
+        let mut restored = Ok(());
+        for slot in 0..SLOT_COUNT
+        {
+            if hotkeys.is_pressed(&format!("save_position_{}", slot + 1))
+            {
+                self.positions.slots[slot] = Some(Self::capture(format!("slot {}", slot + 1), &position));
+                changed = true;
+            }
+            else if hotkeys.is_pressed(&format!("restore_position_{}", slot + 1))
+            {
+                if let Some(saved) = &self.positions.slots[slot]
+                {
+                    restored = Self::restore(saved, &position);
+                }
+            }
+        }
+
+        self.report_restore(restored);
+        if changed
+        {
+            self.persist();
+        }
+    }
+
     fn render(&mut self, game: &mut Box<dyn Game>, ui: &Ui)
     {
         if let Some(position) = game.player_position()
@@ -42,6 +213,31 @@ impl Widget for PlayerPositionWidget
                 ui.text(format!("{:.2}", current_position.y));
                 ui.same_line();
                 ui.text(format!("{:.2}", current_position.z));
+                if let Some(angle) = position.get_angle()
+                {
+                    ui.same_line();
+                    ui.text(format!("angle {:.2}", angle));
+                }
+                if let Some(map_id) = position.get_map_id()
+                {
+                    ui.same_line();
+                    ui.text(format!("map {}", map_id));
+                }
+                if let Some(status) = &self.status
+                {
+                    ui.text(status);
+                }
+
+                if let Some(_slots) = ui.tree_node("slots")
+                {
+                    ui.text_disabled("ctrl+shift+1..9 to save, ctrl+1..9 to restore");
+                    self.render_slots(ui, &position);
+                }
+
+                if let Some(_import_export) = ui.tree_node("import/export")
+                {
+                    self.render_import_export(ui);
+                }
 
                 //Add positions to list
                 if ui.button("import")
@@ -53,9 +249,12 @@ impl Widget for PlayerPositionWidget
                 ui.same_line();
                 if ui.button("add")
                 {
-                    self.positions.push((self.position_input_text.clone(), self.position_input_vec.clone()));
+                    //Typed in, so only the map is taken from the game
+                    let saved = SavedPosition { name: self.position_input_text.clone(), position: self.position_input_vec.clone(), angle: None, map_id: position.get_map_id() };
+                    self.positions.positions.push(saved);
                     self.position_input_text.clear();
                     self.position_input_vec = Vector3f::default();
+                    self.persist();
                 }
 
                 ui.input_text("description: ", &mut self.position_input_text).build();
@@ -69,18 +268,21 @@ impl Widget for PlayerPositionWidget
                 ui.input_float("z", &mut self.position_input_vec.z).build();
 
                 //Display list of positions
+                let mut restored = Ok(());
                 ui.child_window("positions_scrollable")
                     .size([ui.content_region_avail()[0], 400.0f32])
                     .build(||
                         {
                             let mut delete_index = None;
-                            for i in 0..self.positions.len()
+                            for i in 0..self.positions.positions.len()
                             {
-                                ui.text(&self.positions[i].0);
+                                let saved = &self.positions.positions[i];
+                                let id = ui.push_id(i.to_string());
+                                ui.text(&saved.name);
                                 if ui.button("restore")
                                 {
                                     info!("restore {}", i);
-                                    position.set_position(&self.positions[i].1);
+                                    restored = Self::restore(saved, &position);
                                 }
                                 ui.same_line();
                                 if ui.button("delete")
@@ -88,19 +290,27 @@ impl Widget for PlayerPositionWidget
                                     delete_index = Some(i);
                                 }
 
-                                ui.text(format!("{:.2}", &self.positions[i].1.x));
+                                ui.text(format!("{:.2}", saved.position.x));
                                 ui.same_line();
-                                ui.text(format!("{:.2}", &self.positions[i].1.y));
+                                ui.text(format!("{:.2}", saved.position.y));
                                 ui.same_line();
-                                ui.text(format!("{:.2}", &self.positions[i].1.z));
+                                ui.text(format!("{:.2}", saved.position.z));
+                                if let Some(map_id) = saved.map_id
+                                {
+                                    ui.same_line();
+                                    ui.text_disabled(format!("map {}", map_id));
+                                }
+                                id.end();
                             }
 
                             if let Some(index) = delete_index
                             {
-                                self.positions.remove(index);
+                                self.positions.positions.remove(index);
+                                self.persist();
                             }
                         });
+                self.report_restore(restored);
             }
         }
     }
-}
\ No newline at end of file
+}
@@ -14,43 +14,76 @@
 // You should have received a copy of the GNU General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
-use std::ops::DerefMut;
+use std::sync::{Arc, Mutex};
 use imgui::{TreeNodeFlags, Ui};
-use crate::games::{GetSetChrDbgFlags};
+use log::error;
+use crate::chr_dbg_flags::ChrDbgFlagSettings;
 use crate::games::*;
+use crate::error::Error;
+use crate::hotkeys::HotkeyManager;
 use crate::widgets::widget::Widget;
 
 pub struct ChrDbgFlagsWidget
 {
-    flags: Vec<(u32, String, bool)>,
-    init: bool,
+    settings: Arc<Mutex<ChrDbgFlagSettings>>,
 }
 
+///Every game that has chr dbg flags names its no death flag the same
+const PLAYER_NO_DEAD: &str = "Player No Dead";
+
 impl ChrDbgFlagsWidget
 {
-    pub fn new() -> Self{ ChrDbgFlagsWidget { flags: Vec::new(), init: false} }
+    pub fn new(settings: Arc<Mutex<ChrDbgFlagSettings>>) -> Self{ ChrDbgFlagsWidget { settings } }
+
+    fn save(&self, flag: u32, state: bool)
+    {
+        if let Err(e) = self.settings.lock().unwrap().set(flag, state)
+        {
+            error!("failed to save chr dbg flags: {}", e);
+        }
+    }
 }
 
 impl Widget for ChrDbgFlagsWidget
 {
-    fn render(&mut self, game: &mut Box<dyn Game>, ui: &Ui)
+    fn name(&self) -> &str { "chr_dbg_flags" }
+
+    fn register_hotkeys(&self, hotkeys: &mut HotkeyManager) -> Vec<Error>
+    {
+        return hotkeys.register("toggle_no_death", "toggle player no dead", "").err().into_iter().collect();
+    }
+
+    fn on_hotkeys(&mut self, game: &mut Box<dyn Game>, hotkeys: &HotkeyManager)
     {
-        if let Some(sekiro) = GameExt::get_game_mut::<Sekiro>(game.deref_mut())
+        if !hotkeys.is_pressed("toggle_no_death")
         {
-            if !self.init
+            return;
+        }
+
+        if let Some(chr_dbg_flags) = game.chr_dbg_flags()
+        {
+            if let Some((flag, _, state)) = chr_dbg_flags.get_flags().into_iter().find(|(_, name, _)| name == PLAYER_NO_DEAD)
             {
-                self.flags = sekiro.get_flags();
-                self.init = true;
+                chr_dbg_flags.set_flag(flag, !state);
+                self.save(flag, !state);
             }
+        }
+    }
 
-            if ui.collapsing_header("chr dbg", TreeNodeFlags::FRAMED)
+    fn render(&mut self, game: &mut Box<dyn Game>, ui: &Ui)
+    {
+        if let Some(chr_dbg_flags) = game.chr_dbg_flags()
+        {
+            //Empty when the flags weren't found in this build
+            let flags = chr_dbg_flags.get_flags();
+            if !flags.is_empty() && ui.collapsing_header("chr dbg", TreeNodeFlags::FRAMED)
             {
-                for f in self.flags.iter_mut()
+                for (flag, name, mut state) in flags
                 {
-
-                    if ui.checkbox(&f.1, &mut f.2)
+                    if ui.checkbox(&name, &mut state)
                     {
-                        sekiro.set_flag(f.0, f.2);
+                        chr_dbg_flags.set_flag(flag, state);
+                        self.save(flag, state);
                     }
                 }
             }
@@ -14,9 +14,13 @@
 // You should have received a copy of the GNU General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
+use chrono::{DateTime, Local};
 use imgui::{TableFlags, TreeNodeFlags, Ui};
+use soulmemory_memory::event_flags::{EventFlagSnapshot, FlagChange};
 use crate::games::traits::buffered_event_flags::{BufferedEventFlags, EventFlag};
 use crate::games::*;
+use crate::flag_metadata::FlagMetadataDatabase;
+use soulmemory_config::LogMode;
 use crate::widgets::widget::Widget;
 
 const EVENT_FLAG_SCROLL_REGION_HEIGHT: f32 = 400.0f32;
@@ -25,6 +29,8 @@ pub struct EventFlagWidget
 {
     copy_fade: f32,
     selected_log_mode_index: u32,
+    flag_metadata: FlagMetadataDatabase,
+    log_search_input: String,
     unique_event_flags: Vec<EventFlag>,
 
     event_flags: Vec<EventFlag>,
@@ -34,15 +40,28 @@ pub struct EventFlagWidget
 
     watched_flags: Vec<u32>,
     watch_flag_input: String,
+
+    snapshot_a: Option<(DateTime<Local>, EventFlagSnapshot)>,
+    snapshot_b: Option<(DateTime<Local>, EventFlagSnapshot)>,
+    snapshot_diff: Vec<FlagChange>,
+    snapshot_error: Option<String>,
+    snapshot_search_input: String,
 }
 
 impl EventFlagWidget
 {
-    pub fn new() -> Self{
+    pub fn new(flag_metadata: FlagMetadataDatabase, log_mode: LogMode) -> Self{
         EventFlagWidget
         {
             copy_fade: 0.0f32,
-            selected_log_mode_index: 1, //Select unqiue flags by default
+            selected_log_mode_index: match log_mode
+            {
+                LogMode::All => 0,
+                LogMode::Unique => 1,
+                LogMode::Blacklist => 2,
+            },
+            flag_metadata,
+            log_search_input: String::new(),
             unique_event_flags: Vec::new(),
             event_flags: Vec::new(),
 
@@ -51,6 +70,12 @@ impl EventFlagWidget
 
             watched_flags: Vec::new(),
             watch_flag_input: String::new(),
+
+            snapshot_a: None,
+            snapshot_b: None,
+            snapshot_diff: Vec::new(),
+            snapshot_error: None,
+            snapshot_search_input: String::new(),
         }
     }
 
@@ -62,20 +87,23 @@ impl EventFlagWidget
             {
                 self.event_flags.clear();
             }
+            ui.same_line();
+            ui.input_text("search", &mut self.log_search_input).hint("flag or name").build();
 
             ui.child_window("log_event_flags_scrollable")
                 .size([ui.content_region_avail()[0], EVENT_FLAG_SCROLL_REGION_HEIGHT])
                 .build(||
             {
-                if let Some(_table_token) = ui.begin_table_with_flags("event flags", 3, TableFlags::HIDEABLE | TableFlags::RESIZABLE)
+                if let Some(_table_token) = ui.begin_table_with_flags("event flags", 4, TableFlags::HIDEABLE | TableFlags::RESIZABLE)
                 {
                     ui.table_setup_column("time");
                     ui.table_setup_column("flag");
+                    ui.table_setup_column("name");
                     ui.table_setup_column("value");
                     ui.table_headers_row();
 
                     let mut index = 0;
-                    for f in self.event_flags.iter()
+                    for f in self.event_flags.iter().filter(|f| self.flag_metadata.matches(f.flag, &self.log_search_input))
                     {
 
                         //display time + setup selectable
@@ -92,6 +120,10 @@ impl EventFlagWidget
                         ui.table_next_column();
                         ui.text(f.flag.to_string());
 
+                        //name
+                        ui.table_next_column();
+                        Self::flag_name(ui, &self.flag_metadata, f.flag);
+
                         //flag val
                         ui.table_next_column();
                         if f.state
@@ -133,6 +165,8 @@ impl EventFlagWidget
                 {
                     ui.text(format!("{: >10}", self.blacklisted_flags[i].to_string()));
                     ui.same_line();
+                    Self::flag_name(ui, &self.flag_metadata, self.blacklisted_flags[i]);
+                    ui.same_line();
 
                     let id = ui.push_id(i.to_string());
                     if ui.button("delete")
@@ -168,14 +202,24 @@ impl EventFlagWidget
                 {
                     ui.text(format!("{: >10}", self.watched_flags[i].to_string()));
                     ui.same_line();
+                    Self::flag_name(ui, &self.flag_metadata, self.watched_flags[i]);
+                    ui.same_line();
 
                     if let Some(buffered_event_flags) = game.event_flags()
                     {
                         let flag_val = buffered_event_flags.get_event_flag_state(self.watched_flags[i]);
-                        ui.text(format!("{: >5}", flag_val));
-                        ui.same_line();
 
                         let id = ui.push_id(i.to_string());
+                        if ui.button(format!("{: >5}", flag_val))
+                        {
+                            buffered_event_flags.set_event_flag_state(self.watched_flags[i], !flag_val);
+                        }
+                        if ui.is_item_hovered()
+                        {
+                            ui.tooltip_text("Click to flip the flag.");
+                        }
+                        ui.same_line();
+
                         if ui.button("delete")
                         {
                             delete_flag_index = Some(i);
@@ -195,6 +239,113 @@ impl EventFlagWidget
         }
     }
 
+    fn tab_snapshots(&mut self, ui: &Ui, game: &mut Box<dyn Game>)
+    {
+        if let Some(snapshots) = ui.tab_item("snapshots")
+        {
+            if let Some(event_flags) = game.event_flags()
+            {
+                let taken_a = Self::snapshot_button(ui, "take A", &**event_flags, &mut self.snapshot_a, &mut self.snapshot_error);
+                let taken_b = Self::snapshot_button(ui, "take B", &**event_flags, &mut self.snapshot_b, &mut self.snapshot_error);
+                if taken_a || taken_b
+                {
+                    if let (Some((_, a)), Some((_, b))) = (&self.snapshot_a, &self.snapshot_b)
+                    {
+                        self.snapshot_diff = a.diff(b);
+                    }
+                }
+            }
+
+            if let Some(error) = &self.snapshot_error
+            {
+                ui.text_colored([1.0f32, 0.0f32, 0.0f32, 1.0f32], error);
+            }
+
+            if self.snapshot_a.is_some() && self.snapshot_b.is_some()
+            {
+                ui.text(format!("{} flags changed from A to B", self.snapshot_diff.len()));
+                ui.input_text("search", &mut self.snapshot_search_input).hint("flag or name").build();
+
+                ui.child_window("snapshot_event_flags_scrollable")
+                    .size([ui.content_region_avail()[0], EVENT_FLAG_SCROLL_REGION_HEIGHT])
+                    .build(||
+                {
+                    if let Some(_table_token) = ui.begin_table_with_flags("snapshot diff", 3, TableFlags::HIDEABLE | TableFlags::RESIZABLE)
+                    {
+                        ui.table_setup_column("flag");
+                        ui.table_setup_column("name");
+                        ui.table_setup_column("B");
+                        ui.table_headers_row();
+
+                        for change in self.snapshot_diff.iter().filter(|c| self.flag_metadata.matches(c.flag, &self.snapshot_search_input))
+                        {
+                            ui.table_next_column();
+                            ui.text(change.flag.to_string());
+
+                            ui.table_next_column();
+                            Self::flag_name(ui, &self.flag_metadata, change.flag);
+
+                            ui.table_next_column();
+                            if change.state
+                            {
+                                ui.text_colored([0.0f32, 1.0f32, 0.0f32, 1.0f32], "true")
+                            }
+                            else
+                            {
+                                ui.text_colored([1.0f32, 0.0f32, 0.0f32, 1.0f32], "false")
+                            }
+                        }
+                    }
+                });
+            }
+
+            snapshots.end();
+        }
+    }
+
+    ///Draws a button that replaces the snapshot, and when the current one was taken. Returns true when a new one was taken.
+    fn snapshot_button(ui: &Ui, label: &str, event_flags: &dyn BufferedEventFlags, snapshot: &mut Option<(DateTime<Local>, EventFlagSnapshot)>, error: &mut Option<String>) -> bool
+    {
+        let mut taken = false;
+        if ui.button(label)
+        {
+            match event_flags.snapshot_event_flags()
+            {
+                Ok(s) =>
+                {
+                    *snapshot = Some((chrono::offset::Local::now(), s));
+                    *error = None;
+                    taken = true;
+                }
+                Err(e) => *error = Some(e.to_string()),
+            }
+        }
+        ui.same_line();
+        match snapshot.as_ref()
+        {
+            Some((time, s)) => ui.text(format!("{}, {} flags set", time.format("%H:%M:%S"), s.count())),
+            None => ui.text_disabled("not taken"),
+        }
+        return taken;
+    }
+
+    ///Draws the name of a flag, with the category and description as tooltip
+    fn flag_name(ui: &Ui, flag_metadata: &FlagMetadataDatabase, flag: u32)
+    {
+        if let Some(metadata) = flag_metadata.get(flag)
+        {
+            ui.text(&metadata.name);
+            if ui.is_item_hovered() && !(metadata.category.is_empty() && metadata.description.is_empty())
+            {
+                ui.tooltip_text(format!("{}\n{}", metadata.category, metadata.description));
+            }
+        }
+        else
+        {
+            ui.text_disabled("-");
+        }
+    }
+
     fn flag_input_to_vec(ui: &Ui, input_string: &mut String, vec: &mut Vec<u32>)
     {
         ui.input_text("flag", input_string).build();
@@ -221,10 +372,9 @@ impl EventFlagWidget
         });
     }
 
-    fn buffer_flags(&mut self, event_flags: &mut Box<&mut dyn BufferedEventFlags>)
+    fn buffer_flags(&mut self, event_flags: &[EventFlag])
     {
-        let new_flags = event_flags.get_buffered_flags();
-        for f in new_flags
+        for &f in event_flags
         {
             match self.selected_log_mode_index
             {
@@ -265,12 +415,12 @@ impl EventFlagWidget
 
 impl Widget for EventFlagWidget
 {
+    fn name(&self) -> &str { "event_flags" }
+
     fn render(&mut self, game: &mut Box<dyn Game>, ui: &Ui)
     {
-        if let Some(mut event_flags) = game.event_flags()
+        if game.event_flags().is_some()
         {
-            self.buffer_flags(&mut event_flags);
-
             if ui.collapsing_header("event flags", TreeNodeFlags::FRAMED)
             {
                 ui.text("Log mode:");
@@ -303,9 +453,15 @@ impl Widget for EventFlagWidget
                     self.tab_event_flag_log(ui, game);
                     self.tab_blacklist(ui, game);
                     self.tab_watch_event_flags(ui, game);
+                    self.tab_snapshots(ui, game);
                     tab_bar.end();
                 };
             }
         }
     }
+
+    fn on_event_flags(&mut self, event_flags: &[EventFlag])
+    {
+        self.buffer_flags(event_flags);
+    }
 }
\ No newline at end of file
@@ -0,0 +1,99 @@
+// This file is part of the soulmemory-rs distribution (https://github.com/FrankvdStam/soulmemory-rs).
+// Copyright (c) 2022 Frank van der Stam.
+// https://github.com/FrankvdStam/soulmemory-rs/blob/main/LICENSE
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::sync::{Arc, Mutex};
+use imgui::{TreeNodeFlags, Ui};
+use log::error;
+use crate::games::Game;
+use crate::hotkeys::HotkeyManager;
+use crate::widgets::widget::Widget;
+
+pub struct HotkeyWidget
+{
+    hotkeys: Arc<Mutex<HotkeyManager>>,
+}
+
+impl HotkeyWidget
+{
+    pub fn new(hotkeys: Arc<Mutex<HotkeyManager>>) -> Self { HotkeyWidget { hotkeys } }
+}
+
+impl Widget for HotkeyWidget
+{
+    fn name(&self) -> &str { "hotkeys" }
+
+    fn render(&mut self, _game: &mut Box<dyn Game>, ui: &Ui)
+    {
+        if ui.collapsing_header("hotkeys", TreeNodeFlags::FRAMED)
+        {
+            let mut hotkeys = self.hotkeys.lock().unwrap();
+            let conflicts = hotkeys.conflicts();
+            let mut rebind = None;
+            let mut clear = None;
+
+            for action in hotkeys.actions()
+            {
+                let id = ui.push_id(&action.id);
+                let binding = match hotkeys.capturing()
+                {
+                    Some(capturing) if capturing == action.id => String::from("press a key or chord, escape cancels"),
+                    _ => action.binding.map(|b| b.to_text()).unwrap_or(String::from("-")),
+                };
+
+                if ui.button("bind")
+                {
+                    rebind = Some(action.id.clone());
+                }
+                ui.same_line();
+                if ui.button("clear")
+                {
+                    clear = Some(action.id.clone());
+                }
+                ui.same_line();
+                if conflicts.iter().any(|(a, b)| *a == action.id || *b == action.id)
+                {
+                    ui.text_colored([1.0f32, 0.0f32, 0.0f32, 1.0f32], format!("{}: {}", action.description, binding));
+                }
+                else
+                {
+                    ui.text(format!("{}: {}", action.description, binding));
+                }
+                id.pop();
+            }
+
+            if let Some(id) = rebind
+            {
+                hotkeys.start_capture(&id);
+            }
+            if let Some(id) = clear
+            {
+                if let Err(e) = hotkeys.rebind(&id, None)
+                {
+                    error!("failed to save hotkeys: {}", e);
+                }
+            }
+
+            for (a, b) in &conflicts
+            {
+                ui.text_colored([1.0f32, 0.0f32, 0.0f32, 1.0f32], format!("{} and {} share a binding", a, b));
+            }
+            if let Some(e) = hotkeys.error()
+            {
+                ui.text_colored([1.0f32, 0.0f32, 0.0f32, 1.0f32], e);
+            }
+        }
+    }
+}
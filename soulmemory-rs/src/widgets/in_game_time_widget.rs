@@ -0,0 +1,183 @@
+// This file is part of the soulmemory-rs distribution (https://github.com/FrankvdStam/soulmemory-rs).
+// Copyright (c) 2022 Frank van der Stam.
+// https://github.com/FrankvdStam/soulmemory-rs/blob/main/LICENSE
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::time::{Duration, Instant};
+use imgui::{TreeNodeFlags, Ui};
+use crate::games::Game;
+use crate::util::format_duration;
+use crate::widgets::widget::Widget;
+
+///IGT has to stand still for at least this long to count as a load, it can skip a sample when the overlay renders faster than the game.
+const LOAD_THRESHOLD: Duration = Duration::from_millis(100);
+
+///Tracks real time next to IGT. While IGT stands still the game is loading, that time is left out of the load removed time.
+#[derive(Default)]
+struct LoadDetector
+{
+    previous_igt: u32,
+    real_time: Duration,
+    load_removed_time: Duration,
+    current_load: Duration,
+    loads: Vec<Duration>,
+}
+
+impl LoadDetector
+{
+    fn update(&mut self, igt: u32, delta: Duration)
+    {
+        let previous_igt = self.previous_igt;
+        self.previous_igt = igt;
+
+        //Main menu
+        if igt == 0
+        {
+            return;
+        }
+
+        self.real_time += delta;
+        if igt == previous_igt
+        {
+            self.current_load += delta;
+            return;
+        }
+
+        let stall = std::mem::take(&mut self.current_load);
+        if stall >= LOAD_THRESHOLD
+        {
+            self.loads.push(stall);
+        }
+        else
+        {
+            self.load_removed_time += stall;
+        }
+        self.load_removed_time += delta;
+    }
+
+    fn is_loading(&self) -> bool
+    {
+        return self.current_load >= LOAD_THRESHOLD;
+    }
+
+    fn load_removed_time(&self) -> Duration
+    {
+        return if self.is_loading() { self.load_removed_time } else { self.load_removed_time + self.current_load };
+    }
+
+    fn total_load_time(&self) -> Duration
+    {
+        return self.loads.iter().sum::<Duration>() + if self.is_loading() { self.current_load } else { Duration::ZERO };
+    }
+}
+
+pub struct InGameTimeWidget
+{
+    load_detector: LoadDetector,
+    previous_sample: Option<Instant>,
+}
+
+impl InGameTimeWidget
+{
+    pub fn new() -> Self
+    {
+        InGameTimeWidget
+        {
+            load_detector: LoadDetector::default(),
+            previous_sample: None,
+        }
+    }
+}
+
+impl Widget for InGameTimeWidget
+{
+    fn name(&self) -> &str { "in_game_time" }
+
+    ///Samples IGT every frame, also while the overlay is hidden, which is when a runner is most likely to be timing a run
+    fn on_frame(&mut self, game: &mut Box<dyn Game>)
+    {
+        if let Some(in_game_time) = game.in_game_time()
+        {
+            let igt = in_game_time.get_in_game_time_milliseconds();
+            let now = Instant::now();
+            let delta = self.previous_sample.map(|p| now - p).unwrap_or_default();
+            self.previous_sample = Some(now);
+            self.load_detector.update(igt, delta);
+        }
+    }
+
+    fn render(&mut self, game: &mut Box<dyn Game>, ui: &Ui)
+    {
+        if let Some(in_game_time) = game.in_game_time()
+        {
+            let igt = in_game_time.get_in_game_time_milliseconds();
+            if ui.collapsing_header("in game time", TreeNodeFlags::FRAMED)
+            {
+                ui.text(format!("igt:          {}", format_duration(Duration::from_millis(igt as u64))));
+                ui.text(format!("real time:    {}", format_duration(self.load_detector.real_time)));
+                ui.text(format!("load removed: {}", format_duration(self.load_detector.load_removed_time())));
+                ui.text(format!("loads:        {} ({})", self.load_detector.loads.len(), format_duration(self.load_detector.total_load_time())));
+                if self.load_detector.is_loading()
+                {
+                    ui.text_colored([1.0f32, 1.0f32, 0.0f32, 1.0f32], "loading");
+                }
+
+                if ui.button("reset")
+                {
+                    self.load_detector = LoadDetector { previous_igt: igt, ..LoadDetector::default() };
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use std::time::Duration;
+    use crate::widgets::in_game_time_widget::LoadDetector;
+
+    #[test]
+    fn detect_loads()
+    {
+        let frame = Duration::from_millis(16);
+        let mut load_detector = LoadDetector::default();
+        let mut igt = 0;
+
+        //Main menu doesn't count
+        load_detector.update(igt, frame);
+        for _ in 0..10
+        {
+            igt += 16;
+            load_detector.update(igt, frame);
+        }
+        //A single skipped sample is not a load
+        load_detector.update(igt, frame);
+        igt += 32;
+        load_detector.update(igt, frame);
+        //Loading screen
+        for _ in 0..20
+        {
+            load_detector.update(igt, frame);
+        }
+        assert!(load_detector.is_loading());
+        igt += 16;
+        load_detector.update(igt, frame);
+
+        assert_eq!(1, load_detector.loads.len());
+        assert_eq!(frame * 33, load_detector.real_time);
+        assert_eq!(frame * 20, load_detector.total_load_time());
+        assert_eq!(frame * 13, load_detector.load_removed_time());
+    }
+}
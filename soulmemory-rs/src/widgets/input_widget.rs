@@ -0,0 +1,85 @@
+// This file is part of the soulmemory-rs distribution (https://github.com/FrankvdStam/soulmemory-rs).
+// Copyright (c) 2022 Frank van der Stam.
+// https://github.com/FrankvdStam/soulmemory-rs/blob/main/LICENSE
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::borrow::Cow;
+use std::sync::{Arc, Mutex};
+use imgui::{TreeNodeFlags, Ui};
+use crate::games::Game;
+use crate::input::Remap;
+use soulmemory_tas::script::BUTTONS;
+use crate::widgets::widget::Widget;
+
+pub struct InputWidget
+{
+    remap: Arc<Mutex<Remap>>,
+    from_index: usize,
+    to_index: usize,
+}
+
+impl InputWidget
+{
+    pub fn new(remap: Arc<Mutex<Remap>>) -> Self
+    {
+        InputWidget { remap, from_index: 0, to_index: 0 }
+    }
+
+    fn button_name(bit: u16) -> &'static str
+    {
+        return BUTTONS.iter().find(|(_, b)| *b == bit).map(|(name, _)| *name).unwrap_or("?");
+    }
+}
+
+impl Widget for InputWidget
+{
+    fn name(&self) -> &str { "input_remap" }
+
+    fn render(&mut self, _game: &mut Box<dyn Game>, ui: &Ui)
+    {
+        if ui.collapsing_header("input remap", TreeNodeFlags::FRAMED)
+        {
+            let mut remap = self.remap.lock().unwrap();
+
+            let mut delete_index = None;
+            for (i, (from, to)) in remap.mappings.iter().enumerate()
+            {
+                let id = ui.push_id(i.to_string());
+                ui.text(format!("{} -> {}", Self::button_name(*from), Self::button_name(*to)));
+                ui.same_line();
+                if ui.button("delete")
+                {
+                    delete_index = Some(i);
+                }
+                id.end();
+            }
+            if let Some(index) = delete_index
+            {
+                remap.mappings.remove(index);
+            }
+
+            let _width = ui.push_item_width(80.0f32);
+            ui.combo("from", &mut self.from_index, BUTTONS, |button| Cow::Borrowed(button.0));
+            ui.same_line();
+            ui.combo("to", &mut self.to_index, BUTTONS, |button| Cow::Borrowed(button.0));
+            ui.same_line();
+            if ui.button("add")
+            {
+                let from = BUTTONS[self.from_index].1;
+                remap.mappings.retain(|(f, _)| *f != from);
+                remap.mappings.push((from, BUTTONS[self.to_index].1));
+            }
+        }
+    }
+}
@@ -0,0 +1,231 @@
+// This file is part of the soulmemory-rs distribution (https://github.com/FrankvdStam/soulmemory-rs).
+// Copyright (c) 2022 Frank van der Stam.
+// https://github.com/FrankvdStam/soulmemory-rs/blob/main/LICENSE
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use imgui::{TreeNodeFlags, Ui};
+use log::{error, info};
+use crate::games::Game;
+use crate::games::game_id::GameId;
+use crate::error::Error;
+use crate::hotkeys::HotkeyManager;
+use crate::macros::{Macro, MacroList, MacroRunner, MacroStep};
+use crate::widgets::widget::Widget;
+
+pub struct MacroWidget
+{
+    runner: Arc<Mutex<MacroRunner>>,
+    hotkeys: Arc<Mutex<HotkeyManager>>,
+    macros: MacroList,
+    ///What the runner has and the hotkey actions are registered for, the list above is edited until it is saved
+    saved: Vec<Macro>,
+    path: Option<PathBuf>,
+    new_macro_name: String,
+    status: Option<String>,
+}
+
+impl MacroWidget
+{
+    pub fn new(game: GameId, directory: Option<PathBuf>, runner: Arc<Mutex<MacroRunner>>, hotkeys: Arc<Mutex<HotkeyManager>>) -> Self
+    {
+        let path = directory.map(|d| MacroList::path(&d, game));
+        let macros = match &path
+        {
+            Some(path) => MacroList::load(path, game).unwrap_or_else(|e|
+            {
+                error!("failed to load macros: {}", e);
+                MacroList::new(game)
+            }),
+            None => MacroList::new(game),
+        };
+
+        let (saved, status) = match runner.lock().unwrap().load(&macros.macros)
+        {
+            Ok(()) => (macros.macros.clone(), None),
+            Err(e) => (Vec::new(), Some(e)),
+        };
+        info!("loaded {} macros", macros.macros.len());
+
+        MacroWidget
+        {
+            runner,
+            hotkeys,
+            macros,
+            saved,
+            path,
+            new_macro_name: String::new(),
+            status,
+        }
+    }
+
+    ///Hands the macros to the runner and writes them to disk, invalid macros are neither
+    fn save(&mut self)
+    {
+        if let Err(e) = self.runner.lock().unwrap().load(&self.macros.macros)
+        {
+            self.status = Some(e);
+            return;
+        }
+
+        let mut hotkeys = self.hotkeys.lock().unwrap();
+        for m in &self.saved
+        {
+            hotkeys.unregister(&m.action_id());
+        }
+        self.saved = self.macros.macros.clone();
+        let errors = self.register_hotkeys(&mut hotkeys);
+        drop(hotkeys);
+        if let Some(e) = errors.first()
+        {
+            self.status = Some(e.to_string());
+            return;
+        }
+
+        self.status = Some(match &self.path
+        {
+            Some(path) => match self.macros.save(path)
+            {
+                Ok(()) => format!("saved {} macros", self.macros.macros.len()),
+                Err(e) => e,
+            },
+            None => String::from("macros applied, but there is no directory to save them to"),
+        });
+    }
+
+    fn render_macro(ui: &Ui, m: &mut Macro) -> bool
+    {
+        let mut delete_step = None;
+        let _width = ui.push_item_width(120.0f32);
+
+        let mut hotkey = m.hotkey.clone().unwrap_or_default();
+        if ui.input_text("hotkey", &mut hotkey).hint("f5").build()
+        {
+            m.hotkey = (!hotkey.is_empty()).then_some(hotkey);
+        }
+        let mut chord = m.chord.clone().unwrap_or_default();
+        if ui.input_text("chord", &mut chord).hint("lb rb up").build()
+        {
+            m.chord = (!chord.is_empty()).then_some(chord);
+        }
+
+        for (index, step) in m.steps.iter_mut().enumerate()
+        {
+            let id = ui.push_id(format!("step{}", index));
+            ui.input_text("input", &mut step.input).hint("a rt=255, empty to wait").build();
+            ui.same_line();
+            let mut frames = step.frames as i32;
+            let _frames_width = ui.push_item_width(60.0f32);
+            if ui.input_int("frames", &mut frames).build()
+            {
+                step.frames = frames.max(0) as u32;
+            }
+            ui.same_line();
+            if ui.button("x")
+            {
+                delete_step = Some(index);
+            }
+            id.end();
+        }
+
+        if let Some(index) = delete_step
+        {
+            m.steps.remove(index);
+        }
+        if ui.button("add step")
+        {
+            m.steps.push(MacroStep { input: String::new(), frames: 1 });
+        }
+        ui.same_line();
+        return ui.button("delete macro");
+    }
+}
+
+impl Widget for MacroWidget
+{
+    fn name(&self) -> &str { "macros" }
+
+    fn register_hotkeys(&self, hotkeys: &mut HotkeyManager) -> Vec<Error>
+    {
+        return self.saved.iter()
+            .filter_map(|m| hotkeys.register(&m.action_id(), &format!("run macro {}", m.name), m.hotkey.as_deref().unwrap_or_default()).err())
+            .collect();
+    }
+
+    ///Hotkeys work with the header closed
+    fn on_hotkeys(&mut self, _game: &mut Box<dyn Game>, hotkeys: &HotkeyManager)
+    {
+        for m in &self.saved
+        {
+            if hotkeys.is_pressed(&m.action_id())
+            {
+                self.runner.lock().unwrap().trigger(&m.name);
+            }
+        }
+    }
+
+    fn render(&mut self, _game: &mut Box<dyn Game>, ui: &Ui)
+    {
+        if ui.collapsing_header("macros", TreeNodeFlags::FRAMED)
+        {
+            let running = self.runner.lock().unwrap().running().map(String::from);
+            let mut delete_index = None;
+            let mut run_name = None;
+            for (index, m) in self.macros.macros.iter_mut().enumerate()
+            {
+                let id = ui.push_id(format!("macro{}", index));
+                let label = if running.as_ref() == Some(&m.name) { format!("{} (running)###macro", m.name) } else { format!("{}###macro", m.name) };
+                if let Some(_node) = ui.tree_node(label)
+                {
+                    if ui.button("run")
+                    {
+                        run_name = Some(m.name.clone());
+                    }
+                    if Self::render_macro(ui, m)
+                    {
+                        delete_index = Some(index);
+                    }
+                }
+                id.end();
+            }
+
+            //Runs what was saved last under that name, edits only count after saving
+            if let Some(name) = run_name
+            {
+                self.runner.lock().unwrap().trigger(&name);
+            }
+            if let Some(index) = delete_index
+            {
+                self.macros.macros.remove(index);
+            }
+
+            ui.input_text("##new macro", &mut self.new_macro_name).hint("name").build();
+            ui.same_line();
+            if ui.button("add macro") && !self.new_macro_name.is_empty()
+            {
+                self.macros.macros.push(Macro::new(std::mem::take(&mut self.new_macro_name)));
+            }
+
+            if ui.button("save")
+            {
+                self.save();
+            }
+            if let Some(status) = &self.status
+            {
+                ui.text(status);
+            }
+        }
+    }
+}
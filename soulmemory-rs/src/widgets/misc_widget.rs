@@ -1,8 +1,8 @@
 use crate::widgets::widget::Widget;
 use imgui::{TreeNodeFlags, Ui};
-use windows::Win32::UI::Input::KeyboardAndMouse::VK_OEM_5;
 use crate::games::*;
-use std::ops::DerefMut;
+use crate::error::Error;
+use crate::hotkeys::HotkeyManager;
 
 pub struct MiscWidget
 {
@@ -12,25 +12,38 @@ pub struct MiscWidget
 impl MiscWidget
 {
     pub fn new() -> Self { MiscWidget{}}
+}
+
+impl Widget for MiscWidget
+{
+    fn name(&self) -> &str { "misc" }
+
+    fn register_hotkeys(&self, hotkeys: &mut HotkeyManager) -> Vec<Error>
+    {
+        return hotkeys.register("quitout", "quitout", "oem_5").err().into_iter().collect();
+    }
 
-    fn render_misc_sekiro(&mut self, sekiro: &mut Sekiro, ui: &Ui)
+    fn on_hotkeys(&mut self, game: &mut Box<dyn Game>, hotkeys: &HotkeyManager)
     {
-        if ui.button("quitout") || ui.io().keys_down[VK_OEM_5.0 as usize]
+        if hotkeys.is_pressed("quitout")
         {
-            sekiro.request_quitout();
+            if let Some(quitout) = game.quitout()
+            {
+                quitout.request_quitout();
+            }
         }
     }
-}
 
-impl Widget for MiscWidget
-{
     fn render(&mut self, game: &mut Box<dyn Game>, ui: &Ui)
     {
-        if let Some(sekiro) = GameExt::get_game_mut::<Sekiro>(game.deref_mut())
+        if let Some(quitout) = game.quitout()
         {
             if ui.collapsing_header("misc", TreeNodeFlags::FRAMED)
             {
-                self.render_misc_sekiro(sekiro, ui);
+                if ui.button("quitout")
+                {
+                    quitout.request_quitout();
+                }
             }
         }
     }
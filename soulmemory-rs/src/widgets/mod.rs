@@ -14,9 +14,16 @@
 // You should have received a copy of the GNU General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
-pub(crate) mod widget;
+pub mod widget;
 pub(crate) mod event_flag_widget;
 pub(crate) mod ai_toggle_widget;
 pub(crate) mod basic_position_widget;
 pub(crate) mod chr_dbg_flags_widget;
-pub(crate) mod misc_widget;
\ No newline at end of file
+pub(crate) mod misc_widget;
+pub(crate) mod splits_widget;
+pub(crate) mod in_game_time_widget;
+pub(crate) mod tas_widget;
+pub(crate) mod input_widget;
+pub(crate) mod macro_widget;
+pub(crate) mod hotkey_widget;
+pub mod registry;
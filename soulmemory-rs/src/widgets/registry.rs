@@ -0,0 +1,155 @@
+// This file is part of the soulmemory-rs distribution (https://github.com/FrankvdStam/soulmemory-rs).
+// Copyright (c) 2022 Frank van der Stam.
+// https://github.com/FrankvdStam/soulmemory-rs/blob/main/LICENSE
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use log::{error, info, warn};
+use crate::chr_dbg_flags::ChrDbgFlagSettings;
+use soulmemory_config::Config;
+use crate::flag_metadata::{FlagMetadataDatabase, FLAG_METADATA_DIRECTORY};
+use crate::games::game_id::GameId;
+use crate::games::traits::capability::Capability;
+use crate::hotkeys::HotkeyManager;
+use crate::input::{AiToggle, Remap};
+use crate::macros::{MacroRunner, MACROS_DIRECTORY};
+use crate::positions::POSITIONS_DIRECTORY;
+use crate::splits::{Autosplitter, SPLITS_DIRECTORY};
+use soulmemory_tas::{TasPlayer, TasRecording};
+use crate::widgets::ai_toggle_widget::AiToggleWidget;
+use crate::widgets::basic_position_widget::PlayerPositionWidget;
+use crate::widgets::chr_dbg_flags_widget::ChrDbgFlagsWidget;
+use crate::widgets::event_flag_widget::EventFlagWidget;
+use crate::widgets::hotkey_widget::HotkeyWidget;
+use crate::widgets::in_game_time_widget::InGameTimeWidget;
+use crate::widgets::input_widget::InputWidget;
+use crate::widgets::macro_widget::MacroWidget;
+use crate::widgets::misc_widget::MiscWidget;
+use crate::widgets::splits_widget::SplitsWidget;
+use crate::widgets::tas_widget::TasWidget;
+use crate::widgets::widget::Widget;
+
+///Everything the app shares with widgets while they are being built
+pub struct WidgetContext<'a>
+{
+    pub game_id: GameId,
+    pub module_directory: Option<PathBuf>,
+    pub config: &'a Config,
+    pub autosplitter: Arc<Mutex<Autosplitter>>,
+    pub chr_dbg_flag_settings: Arc<Mutex<ChrDbgFlagSettings>>,
+    pub tas_player: Arc<Mutex<TasPlayer>>,
+    pub tas_recording: Arc<Mutex<TasRecording>>,
+    pub tas_directory: Option<PathBuf>,
+    pub remap: Arc<Mutex<Remap>>,
+    pub ai_toggle: Arc<Mutex<AiToggle>>,
+    pub macro_runner: Arc<Mutex<MacroRunner>>,
+    pub hotkeys: Arc<Mutex<HotkeyManager>>,
+}
+
+impl WidgetContext<'_>
+{
+    fn directory(&self, name: &str) -> Option<PathBuf>
+    {
+        return self.module_directory.as_ref().map(|d| d.join(name));
+    }
+}
+
+pub type WidgetFactory = Box<dyn FnOnce(&WidgetContext) -> Box<dyn Widget> + Send>;
+
+struct WidgetRegistration
+{
+    name: String,
+    requires: Vec<Capability>,
+    factory: WidgetFactory,
+}
+
+///The widgets the overlay can show, in the order they are drawn. A widget is only built when the game has every
+///capability it requires and it isn't disabled in the config.
+#[derive(Default)]
+pub struct WidgetRegistry
+{
+    registrations: Vec<WidgetRegistration>,
+}
+
+impl WidgetRegistry
+{
+    ///A registry with the built in widgets, register more at init to add them after these
+    pub fn new() -> Self
+    {
+        let mut registry = WidgetRegistry::default();
+        registry.register("event_flags", &[Capability::EventFlags], |context|
+        {
+            let flag_metadata = match context.directory(FLAG_METADATA_DIRECTORY)
+            {
+                Some(directory) => FlagMetadataDatabase::load(&directory, context.game_id).unwrap_or_else(|e|
+                {
+                    error!("failed to load event flag names: {}", e);
+                    FlagMetadataDatabase::default()
+                }),
+                None => FlagMetadataDatabase::default(),
+            };
+            info!("loaded {} event flag names", flag_metadata.len());
+            Box::new(EventFlagWidget::new(flag_metadata, context.config.event_flags.log_mode))
+        });
+        registry.register("ai_timer",      &[Capability::AiTimer, Capability::Input], |context| Box::new(AiToggleWidget::new(Arc::clone(&context.ai_toggle))));
+        registry.register("positions",     &[Capability::PlayerPosition], |context| Box::new(PlayerPositionWidget::new(context.game_id, context.directory(POSITIONS_DIRECTORY))));
+        registry.register("chr_dbg_flags", &[Capability::ChrDbgFlags], |context| Box::new(ChrDbgFlagsWidget::new(Arc::clone(&context.chr_dbg_flag_settings))));
+        registry.register("misc",          &[Capability::Quitout], |_| Box::new(MiscWidget::new()));
+        registry.register("in_game_time",  &[Capability::InGameTime], |_| Box::new(InGameTimeWidget::new()));
+        registry.register("splits",        &[], |context| Box::new(SplitsWidget::new(Arc::clone(&context.autosplitter), context.directory(SPLITS_DIRECTORY))));
+        registry.register("tas",           &[Capability::Input], |context| Box::new(TasWidget::new(Arc::clone(&context.tas_player), Arc::clone(&context.tas_recording), context.tas_directory.clone())));
+        registry.register("input_remap",   &[Capability::Input], |context| Box::new(InputWidget::new(Arc::clone(&context.remap))));
+        registry.register("macros",        &[Capability::Input], |context| Box::new(MacroWidget::new(context.game_id, context.directory(MACROS_DIRECTORY), Arc::clone(&context.macro_runner), Arc::clone(&context.hotkeys))));
+        registry.register("hotkeys",       &[], |context| Box::new(HotkeyWidget::new(Arc::clone(&context.hotkeys))));
+        return registry;
+    }
+
+    pub fn register<F>(&mut self, name: &str, requires: &[Capability], factory: F)
+        where F: FnOnce(&WidgetContext) -> Box<dyn Widget> + Send + 'static
+    {
+        self.registrations.push(WidgetRegistration { name: String::from(name), requires: requires.to_vec(), factory: Box::new(factory) });
+    }
+
+    pub fn names(&self) -> Vec<&str>
+    {
+        return self.registrations.iter().map(|r| r.name.as_str()).collect();
+    }
+
+    pub fn build(self, context: &WidgetContext, capabilities: &[Capability]) -> Vec<Box<dyn Widget>>
+    {
+        for name in context.config.widgets.unknown(&self.names())
+        {
+            warn!("[widgets] {} in the config isn't a known widget", name);
+        }
+
+        let mut widgets = Vec::new();
+        for registration in self.registrations
+        {
+            if !context.config.widgets.is_enabled(&registration.name)
+            {
+                info!("widget {} disabled in the config", registration.name);
+                continue;
+            }
+
+            if let Some(missing) = registration.requires.iter().find(|c| !capabilities.contains(c))
+            {
+                info!("widget {} needs {:?}, which this game doesn't support", registration.name, missing);
+                continue;
+            }
+            widgets.push((registration.factory)(context));
+        }
+        return widgets;
+    }
+}
@@ -0,0 +1,146 @@
+// This file is part of the soulmemory-rs distribution (https://github.com/FrankvdStam/soulmemory-rs).
+// Copyright (c) 2022 Frank van der Stam.
+// https://github.com/FrankvdStam/soulmemory-rs/blob/main/LICENSE
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use imgui::{TableFlags, TreeNodeFlags, Ui};
+use log::info;
+use crate::games::Game;
+use crate::splits::{Autosplitter, RunState, SplitFile};
+use crate::util::format_duration;
+use crate::widgets::widget::Widget;
+
+pub struct SplitsWidget
+{
+    autosplitter: Arc<Mutex<Autosplitter>>,
+    path_input: String,
+    error: Option<String>,
+}
+
+impl SplitsWidget
+{
+    pub fn new(autosplitter: Arc<Mutex<Autosplitter>>, splits_directory: Option<PathBuf>) -> Self
+    {
+        SplitsWidget
+        {
+            autosplitter,
+            path_input: splits_directory.map(|d| d.to_string_lossy().to_string()).unwrap_or_default(),
+            error: None,
+        }
+    }
+}
+
+impl Widget for SplitsWidget
+{
+    fn name(&self) -> &str { "splits" }
+
+    fn render(&mut self, _game: &mut Box<dyn Game>, ui: &Ui)
+    {
+        if ui.collapsing_header("splits", TreeNodeFlags::FRAMED)
+        {
+            let mut autosplitter = self.autosplitter.lock().unwrap();
+
+            ui.input_text("split file", &mut self.path_input).build();
+            ui.same_line();
+            if ui.button("load")
+            {
+                match SplitFile::load(&PathBuf::from(&self.path_input))
+                {
+                    Ok(split_file) =>
+                    {
+                        info!("loaded {} splits from {}", split_file.splits.len(), self.path_input);
+                        autosplitter.load(split_file);
+                        self.error = None;
+                    }
+                    Err(e) => self.error = Some(e),
+                }
+            }
+            if let Some(error) = &self.error
+            {
+                ui.text_colored([1.0f32, 0.0f32, 0.0f32, 1.0f32], error);
+            }
+
+            let state = autosplitter.state();
+            let split_count = match autosplitter.split_file()
+            {
+                Some(split_file) =>
+                {
+                    ui.text(&split_file.name);
+                    split_file.splits.len()
+                }
+                None =>
+                {
+                    ui.text_disabled("no split file loaded");
+                    return;
+                }
+            };
+
+            ui.text(format_duration(autosplitter.elapsed()));
+
+            ui.disabled(state != RunState::NotRunning, || if ui.button("start") { autosplitter.start(); });
+            ui.same_line();
+            ui.disabled(state == RunState::NotRunning, || if ui.button("reset") { autosplitter.reset(); });
+            ui.same_line();
+            ui.disabled(!matches!(state, RunState::Running(_)), || if ui.button("skip") { autosplitter.skip(); });
+            ui.same_line();
+            ui.disabled(autosplitter.split_times().is_empty(), || if ui.button("undo") { autosplitter.undo(); });
+
+            if let Some(_table_token) = ui.begin_table_with_flags("splits", 3, TableFlags::RESIZABLE)
+            {
+                ui.table_setup_column("split");
+                ui.table_setup_column("time");
+                ui.table_setup_column("igt");
+                ui.table_headers_row();
+
+                for i in 0..split_count
+                {
+                    let name = &autosplitter.split_file().unwrap().splits[i].name;
+                    ui.table_next_column();
+                    if state == RunState::Running(i)
+                    {
+                        ui.text_colored([1.0f32, 1.0f32, 0.0f32, 1.0f32], name);
+                    }
+                    else
+                    {
+                        ui.text(name);
+                    }
+
+                    ui.table_next_column();
+                    match autosplitter.split_times().get(i)
+                    {
+                        Some(split_time) =>
+                        {
+                            ui.text(format_duration(split_time.real_time));
+                            ui.table_next_column();
+                            match split_time.in_game_time
+                            {
+                                Some(igt) => ui.text(format_duration(Duration::from_millis(igt as u64))),
+                                None => ui.text_disabled("-"),
+                            }
+                        }
+                        None =>
+                        {
+                            ui.text_disabled("-");
+                            ui.table_next_column();
+                            ui.text_disabled("-");
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,209 @@
+// This file is part of the soulmemory-rs distribution (https://github.com/FrankvdStam/soulmemory-rs).
+// Copyright (c) 2022 Frank van der Stam.
+// https://github.com/FrankvdStam/soulmemory-rs/blob/main/LICENSE
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use imgui::{ProgressBar, TreeNodeFlags, Ui};
+use log::info;
+use crate::games::Game;
+use crate::error::Error;
+use crate::hotkeys::HotkeyManager;
+use soulmemory_tas::{PlaybackState, TasPlayer, TasRecording, TasScript};
+use crate::widgets::widget::Widget;
+
+pub struct TasWidget
+{
+    tas_player: Arc<Mutex<TasPlayer>>,
+    tas_recording: Arc<Mutex<TasRecording>>,
+    tas_directory: Option<PathBuf>,
+    path_input: String,
+    recording_name: String,
+    status: Option<String>,
+}
+
+impl TasWidget
+{
+    pub fn new(tas_player: Arc<Mutex<TasPlayer>>, tas_recording: Arc<Mutex<TasRecording>>, tas_directory: Option<PathBuf>) -> Self
+    {
+        TasWidget
+        {
+            tas_player,
+            tas_recording,
+            tas_directory,
+            path_input: String::new(),
+            recording_name: String::new(),
+            status: None,
+        }
+    }
+
+    fn resolve(&self, path: &str) -> PathBuf
+    {
+        return match &self.tas_directory
+        {
+            Some(directory) => directory.join(path),
+            None => PathBuf::from(path),
+        };
+    }
+
+    fn load(&mut self, tas_player: &mut TasPlayer)
+    {
+        let path = self.resolve(&self.path_input);
+        self.status = Some(match TasScript::load(&path)
+        {
+            Ok(script) =>
+            {
+                info!("loaded tas script {} with {} entries", path.display(), script.entries.len());
+                tas_player.load(&script);
+                format!("loaded {} frames", tas_player.length())
+            }
+            Err(e) => e,
+        });
+    }
+
+    ///Saves the recording and puts it in the script input, ready to be played back
+    fn save_recording(&mut self, script: TasScript)
+    {
+        if self.recording_name.is_empty()
+        {
+            self.recording_name = format!("recording-{}.tas", chrono::Local::now().format("%Y%m%d-%H%M%S"));
+        }
+
+        let path = self.resolve(&self.recording_name);
+        self.status = Some(match script.save(&path)
+        {
+            Ok(()) =>
+            {
+                info!("saved tas recording {} with {} entries", path.display(), script.entries.len());
+                self.path_input = std::mem::take(&mut self.recording_name);
+                format!("saved {} frames to {}", script.length(), path.display())
+            }
+            Err(e) => e,
+        });
+    }
+
+    fn render_recording(&mut self, ui: &Ui)
+    {
+        let tas_recording = Arc::clone(&self.tas_recording);
+        let mut tas_recording = tas_recording.lock().unwrap();
+
+        ui.input_text("recording", &mut self.recording_name).hint("file name, generated when empty").build();
+        if tas_recording.is_recording()
+        {
+            if ui.button("stop recording")
+            {
+                let script = tas_recording.stop();
+                self.save_recording(script);
+            }
+            ui.same_line();
+            ui.text_colored([1.0f32, 0.0f32, 0.0f32, 1.0f32], format!("recording frame {}", tas_recording.frame()));
+        }
+        else if ui.button("record")
+        {
+            tas_recording.start();
+        }
+    }
+}
+
+impl Widget for TasWidget
+{
+    fn name(&self) -> &str { "tas" }
+
+    fn register_hotkeys(&self, hotkeys: &mut HotkeyManager) -> Vec<Error>
+    {
+        return
+        [
+            hotkeys.register("tas_start", "start the loaded tas script", ""),
+            hotkeys.register("tas_stop", "stop tas playback", ""),
+            hotkeys.register("tas_record", "start/stop tas recording", ""),
+        ].into_iter().filter_map(Result::err).collect();
+    }
+
+    fn on_hotkeys(&mut self, _game: &mut Box<dyn Game>, hotkeys: &HotkeyManager)
+    {
+        if hotkeys.is_pressed("tas_start")
+        {
+            if let Err(e) = self.tas_player.lock().unwrap().start()
+            {
+                self.status = Some(e);
+            }
+        }
+
+        if hotkeys.is_pressed("tas_stop")
+        {
+            self.tas_player.lock().unwrap().stop();
+        }
+
+        if hotkeys.is_pressed("tas_record")
+        {
+            let tas_recording = Arc::clone(&self.tas_recording);
+            let mut tas_recording = tas_recording.lock().unwrap();
+            if tas_recording.is_recording()
+            {
+                let script = tas_recording.stop();
+                self.save_recording(script);
+            }
+            else
+            {
+                tas_recording.start();
+            }
+        }
+    }
+
+    fn render(&mut self, _game: &mut Box<dyn Game>, ui: &Ui)
+    {
+        if ui.collapsing_header("tas", TreeNodeFlags::FRAMED)
+        {
+            let tas_player = Arc::clone(&self.tas_player);
+            let mut tas_player = tas_player.lock().unwrap();
+
+            ui.input_text("script", &mut self.path_input).hint("relative to the tas directory").build();
+            ui.same_line();
+            if ui.button("load")
+            {
+                self.load(&mut tas_player);
+            }
+            if let Some(status) = &self.status
+            {
+                ui.text(status);
+            }
+
+            let state = tas_player.state();
+            ui.disabled(tas_player.length() == 0, || if ui.button("start")
+            {
+                if let Err(e) = tas_player.start()
+                {
+                    self.status = Some(e);
+                }
+            });
+            ui.same_line();
+            ui.disabled(state == PlaybackState::Stopped, || if ui.button("stop") { tas_player.stop(); });
+            ui.same_line();
+            match state
+            {
+                PlaybackState::Paused => if ui.button("resume") { tas_player.resume(); },
+                _ => ui.disabled(state != PlaybackState::Playing, || if ui.button("pause") { tas_player.pause(); }),
+            }
+
+            let length = tas_player.length();
+            let fraction = if length == 0 { 0.0f32 } else { tas_player.frame() as f32 / length as f32 };
+            ProgressBar::new(fraction).overlay_text(format!("{:?} {}/{}", state, tas_player.frame(), length)).build(ui);
+            drop(tas_player);
+
+            ui.separator();
+            self.render_recording(ui);
+        }
+    }
+}
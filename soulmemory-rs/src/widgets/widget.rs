@@ -16,8 +16,27 @@
 
 use imgui::Ui;
 use crate::games::Game;
+use crate::games::traits::buffered_event_flags::EventFlag;
+use crate::error::Error;
+use crate::hotkeys::HotkeyManager;
 
 pub trait Widget
 {
+    /// Identifies the widget in the config and in layout.json, also the title of its window when detached.
+    fn name(&self) -> &str;
+
     fn render(&mut self, game: &mut Box<dyn Game>, ui: &Ui);
+
+    /// Called from App::refresh with every event flag that was captured since the previous refresh.
+    fn on_event_flags(&mut self, _event_flags: &[EventFlag]) {}
+
+    /// Called once when the widget is added, to register the hotkey actions it handles. Returns the bindings that
+    /// didn't parse, their actions are registered regardless.
+    fn register_hotkeys(&self, _hotkeys: &mut HotkeyManager) -> Vec<Error> { Vec::new() }
+
+    /// Called every frame before render, also when the overlay is hidden.
+    fn on_hotkeys(&mut self, _game: &mut Box<dyn Game>, _hotkeys: &HotkeyManager) {}
+
+    /// Called every frame before render, also when the overlay is hidden or the widget's window is collapsed.
+    fn on_frame(&mut self, _game: &mut Box<dyn Game>) {}
 }
\ No newline at end of file
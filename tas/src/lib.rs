@@ -0,0 +1,64 @@
+// This file is part of the soulmemory-rs distribution (https://github.com/FrankvdStam/soulmemory-rs).
+// Copyright (c) 2022 Frank van der Stam.
+// https://github.com/FrankvdStam/soulmemory-rs/blob/main/LICENSE
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+
+#![allow(clippy::needless_return)]
+
+//! TAS scripts, their playback and recording. Kept free of windows dependencies so scripts can be parsed and
+//! played back in tests on any platform, the dll only feeds the frames into the game's input.
+
+use std::path::{Component, Path, PathBuf};
+
+pub mod script;
+pub mod timeline;
+pub mod player;
+pub mod recording;
+
+pub use script::{TasEntry, TasInput, TasScript};
+pub use player::{PlaybackState, TasPlayer};
+pub use recording::TasRecording;
+
+pub const TAS_DIRECTORY: &str = "tas";
+
+///Resolves a script name from the server against the tas directory, names that would leave it are rejected
+pub fn script_path(directory: &Path, name: &str) -> Result<PathBuf, String>
+{
+    let relative = Path::new(name);
+    if name.trim().is_empty() || !relative.components().all(|c| matches!(c, Component::Normal(_) | Component::CurDir))
+    {
+        return Err(format!("{} is not a path inside the tas directory", name));
+    }
+    return Ok(directory.join(relative));
+}
+
+#[cfg(test)]
+mod tests
+{
+    use std::path::Path;
+    use crate::script_path;
+
+    #[test]
+    fn script_paths()
+    {
+        let directory = Path::new("game/tas");
+        assert_eq!(directory.join("any%.tas"), script_path(directory, "any%.tas").unwrap());
+        assert_eq!(directory.join("routes/./a.tas"), script_path(directory, "routes/./a.tas").unwrap());
+        assert!(script_path(directory, "../soulmemory.toml").is_err());
+        assert!(script_path(directory, "routes/../../a.tas").is_err());
+        assert!(script_path(directory, "/etc/passwd").is_err());
+        assert!(script_path(directory, "").is_err());
+    }
+}
@@ -0,0 +1,158 @@
+// This file is part of the soulmemory-rs distribution (https://github.com/FrankvdStam/soulmemory-rs).
+// Copyright (c) 2022 Frank van der Stam.
+// https://github.com/FrankvdStam/soulmemory-rs/blob/main/LICENSE
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use serde::{Deserialize, Serialize};
+use crate::script::{TasInput, TasScript};
+use crate::timeline::Timeline;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PlaybackState
+{
+    Stopped,
+    Playing,
+    ///The controller is handed back to the player until playback resumes at the same frame
+    Paused,
+}
+
+///Plays a script back one frame per poll. The xinput hook calls poll every time the game reads the first controller,
+///which the games do once per frame.
+pub struct TasPlayer
+{
+    timeline: Timeline,
+    state: PlaybackState,
+    frame: u32,
+}
+
+impl Default for TasPlayer
+{
+    fn default() -> Self
+    {
+        TasPlayer { timeline: Timeline::default(), state: PlaybackState::Stopped, frame: 0 }
+    }
+}
+
+impl TasPlayer
+{
+    ///Replaces the script, stopping playback
+    pub fn load(&mut self, script: &TasScript)
+    {
+        self.timeline = Timeline::new(script);
+        self.stop();
+    }
+
+    pub fn state(&self) -> PlaybackState
+    {
+        return self.state;
+    }
+
+    ///The next frame that will be played
+    pub fn frame(&self) -> u32
+    {
+        return self.frame;
+    }
+
+    pub fn length(&self) -> u32
+    {
+        return self.timeline.len();
+    }
+
+    ///Plays the script from the first frame
+    pub fn start(&mut self) -> Result<(), String>
+    {
+        if self.timeline.is_empty()
+        {
+            return Err(String::from("no tas script loaded"));
+        }
+        self.frame = 0;
+        self.state = PlaybackState::Playing;
+        return Ok(());
+    }
+
+    pub fn stop(&mut self)
+    {
+        self.frame = 0;
+        self.state = PlaybackState::Stopped;
+    }
+
+    pub fn pause(&mut self)
+    {
+        if self.state == PlaybackState::Playing
+        {
+            self.state = PlaybackState::Paused;
+        }
+    }
+
+    pub fn resume(&mut self)
+    {
+        if self.state == PlaybackState::Paused
+        {
+            self.state = PlaybackState::Playing;
+        }
+    }
+
+    ///The input to feed the game this frame, None when the real controller should be used
+    pub fn poll(&mut self) -> Option<TasInput>
+    {
+        if self.state != PlaybackState::Playing
+        {
+            return None;
+        }
+
+        match self.timeline.input_at(self.frame)
+        {
+            Some(input) =>
+            {
+                self.frame += 1;
+                return Some(input);
+            }
+            None =>
+            {
+                self.stop();
+                return None;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use crate::player::{PlaybackState, TasPlayer};
+    use crate::script::TasScript;
+
+    #[test]
+    fn playback()
+    {
+        let mut player = TasPlayer::default();
+        assert!(player.start().is_err());
+
+        player.load(&TasScript::parse("0 2 a\n2 1 b").unwrap());
+        assert_eq!(None, player.poll());
+        player.start().unwrap();
+        assert_eq!(0x1000, player.poll().unwrap().buttons);
+
+        player.pause();
+        assert_eq!(None, player.poll());
+        player.resume();
+        assert_eq!(0x1000, player.poll().unwrap().buttons);
+        assert_eq!(0x2000, player.poll().unwrap().buttons);
+
+        assert_eq!(None, player.poll());
+        assert_eq!(PlaybackState::Stopped, player.state());
+        assert_eq!(0, player.frame());
+    }
+}
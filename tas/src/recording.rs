@@ -0,0 +1,119 @@
+// This file is part of the soulmemory-rs distribution (https://github.com/FrankvdStam/soulmemory-rs).
+// Copyright (c) 2022 Frank van der Stam.
+// https://github.com/FrankvdStam/soulmemory-rs/blob/main/LICENSE
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::time::Instant;
+use crate::script::{TasEntry, TasInput, TasScript};
+
+///Captures the controller every poll and collapses identical consecutive frames into a single held entry.
+///Neutral frames are left out, a script plays gaps as a neutral controller anyway.
+#[derive(Default)]
+pub struct TasRecording
+{
+    started: Option<Instant>,
+    frame: u32,
+    entries: Vec<TasEntry>,
+}
+
+impl TasRecording
+{
+    pub fn is_recording(&self) -> bool
+    {
+        return self.started.is_some();
+    }
+
+    ///Frames recorded so far
+    pub fn frame(&self) -> u32
+    {
+        return self.frame;
+    }
+
+    ///Throws away anything that wasn't stopped yet
+    pub fn start(&mut self)
+    {
+        self.started = Some(Instant::now());
+        self.frame = 0;
+        self.entries.clear();
+    }
+
+    pub fn stop(&mut self) -> TasScript
+    {
+        self.started = None;
+        return TasScript { entries: std::mem::take(&mut self.entries) };
+    }
+
+    pub fn record(&mut self, input: TasInput)
+    {
+        if let Some(started) = self.started
+        {
+            self.record_at(input, started.elapsed().as_millis() as u64);
+        }
+    }
+
+    fn record_at(&mut self, input: TasInput, time: u64)
+    {
+        let frame = self.frame;
+        self.frame += 1;
+        if input.is_neutral()
+        {
+            return;
+        }
+
+        if let Some(last) = self.entries.last_mut()
+        {
+            if last.input == input && last.frame + last.hold == frame
+            {
+                last.hold += 1;
+                return;
+            }
+        }
+        self.entries.push(TasEntry { frame, hold: 1, time: Some(time), input });
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use crate::recording::TasRecording;
+    use crate::script::TasInput;
+    use crate::timeline::Timeline;
+
+    #[test]
+    fn record_and_replay()
+    {
+        let a = TasInput { buttons: 0x1000, ..Default::default() };
+        let run = TasInput { thumb_ly: 32767, ..Default::default() };
+        let inputs = [a, a, TasInput::default(), a, run, run, run];
+
+        let mut recording = TasRecording::default();
+        recording.start();
+        for (time, input) in inputs.iter().enumerate()
+        {
+            recording.record_at(*input, time as u64 * 16);
+        }
+        let script = recording.stop();
+        assert!(!recording.is_recording());
+
+        assert_eq!(3, script.entries.len());
+        assert_eq!((3, 1, Some(48)), (script.entries[1].frame, script.entries[1].hold, script.entries[1].time));
+        assert_eq!((4, 3), (script.entries[2].frame, script.entries[2].hold));
+
+        let timeline = Timeline::new(&script);
+        for (frame, input) in inputs.iter().enumerate()
+        {
+            assert_eq!(Some(*input), timeline.input_at(frame as u32));
+        }
+    }
+}
@@ -0,0 +1,266 @@
+// This file is part of the soulmemory-rs distribution (https://github.com/FrankvdStam/soulmemory-rs).
+// Copyright (c) 2022 Frank van der Stam.
+// https://github.com/FrankvdStam/soulmemory-rs/blob/main/LICENSE
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::fs;
+use std::path::Path;
+
+///Four hours at 60 fps, longer scripts are rejected so a typo can't make the timeline allocate gigabytes
+pub const MAX_FRAMES: u32 = 60 * 60 * 60 * 4;
+
+///The frame after an entry, checked against MAX_FRAMES
+pub fn end_frame(frame: u32, hold: u32) -> Result<u32, String>
+{
+    return frame.checked_add(hold).filter(|end| *end <= MAX_FRAMES).ok_or(format!("frame {} + {} is past the maximum of {} frames", frame, hold, MAX_FRAMES));
+}
+
+///Button bits, same values as XINPUT_GAMEPAD.wButtons
+pub const BUTTONS: &[(&str, u16)] = &[
+    ("up",    0x0001),
+    ("down",  0x0002),
+    ("left",  0x0004),
+    ("right", 0x0008),
+    ("start", 0x0010),
+    ("back",  0x0020),
+    ("ls",    0x0040),
+    ("rs",    0x0080),
+    ("lb",    0x0100),
+    ("rb",    0x0200),
+    ("a",     0x1000),
+    ("b",     0x2000),
+    ("x",     0x4000),
+    ("y",     0x8000),
+];
+
+///The state of a controller for a single frame, laid out like XINPUT_GAMEPAD but free of windows types
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TasInput
+{
+    pub buttons: u16,
+    pub left_trigger: u8,
+    pub right_trigger: u8,
+    pub thumb_lx: i16,
+    pub thumb_ly: i16,
+    pub thumb_rx: i16,
+    pub thumb_ry: i16,
+}
+
+impl TasInput
+{
+    ///Buttons are combined, axes that are set in other replace the ones in self
+    pub fn merge(&mut self, other: &TasInput)
+    {
+        self.buttons |= other.buttons;
+        if other.left_trigger != 0 { self.left_trigger = other.left_trigger; }
+        if other.right_trigger != 0 { self.right_trigger = other.right_trigger; }
+        if other.thumb_lx != 0 { self.thumb_lx = other.thumb_lx; }
+        if other.thumb_ly != 0 { self.thumb_ly = other.thumb_ly; }
+        if other.thumb_rx != 0 { self.thumb_rx = other.thumb_rx; }
+        if other.thumb_ry != 0 { self.thumb_ry = other.thumb_ry; }
+    }
+
+    ///Parses whitespace separated buttons and axes, "a rt=255 lx=-32768"
+    pub fn parse(text: &str) -> Result<TasInput, String>
+    {
+        let mut input = TasInput::default();
+        for token in text.split_whitespace()
+        {
+            input.parse_token(token)?;
+        }
+        return Ok(input);
+    }
+
+    pub fn is_neutral(&self) -> bool
+    {
+        return *self == TasInput::default();
+    }
+
+    ///The tokens that parse back into this input, empty for a neutral controller
+    pub fn to_tokens(&self) -> Vec<String>
+    {
+        let mut tokens: Vec<String> = BUTTONS.iter().filter(|(_, bit)| self.buttons & bit != 0).map(|(name, _)| String::from(*name)).collect();
+        let axes = [("lt", self.left_trigger as i32), ("rt", self.right_trigger as i32), ("lx", self.thumb_lx as i32), ("ly", self.thumb_ly as i32), ("rx", self.thumb_rx as i32), ("ry", self.thumb_ry as i32)];
+        tokens.extend(axes.iter().filter(|(_, value)| *value != 0).map(|(axis, value)| format!("{}={}", axis, value)));
+        return tokens;
+    }
+
+    fn parse_token(&mut self, token: &str) -> Result<(), String>
+    {
+        let token = token.to_lowercase();
+        if let Some((axis, value)) = token.split_once('=')
+        {
+            let parse_error = |e: std::num::ParseIntError| format!("invalid value for {}: {}", axis, e);
+            match axis
+            {
+                "lt" => self.left_trigger = value.parse::<u8>().map_err(parse_error)?,
+                "rt" => self.right_trigger = value.parse::<u8>().map_err(parse_error)?,
+                "lx" => self.thumb_lx = value.parse::<i16>().map_err(parse_error)?,
+                "ly" => self.thumb_ly = value.parse::<i16>().map_err(parse_error)?,
+                "rx" => self.thumb_rx = value.parse::<i16>().map_err(parse_error)?,
+                "ry" => self.thumb_ry = value.parse::<i16>().map_err(parse_error)?,
+                _ => return Err(format!("unknown axis {}", axis)),
+            }
+            return Ok(());
+        }
+
+        return match BUTTONS.iter().find(|(name, _)| *name == token)
+        {
+            Some((_, bit)) =>
+            {
+                self.buttons |= bit;
+                Ok(())
+            }
+            None => Err(format!("unknown button {}", token)),
+        };
+    }
+}
+
+///A single line of a script: hold the input for hold frames, starting at frame
+#[derive(Clone, Debug, PartialEq)]
+pub struct TasEntry
+{
+    pub frame: u32,
+    pub hold: u32,
+    ///Milliseconds since the start of a recording, informational only
+    pub time: Option<u64>,
+    pub input: TasInput,
+}
+
+///A text file with one entry per line, frames count from 0 and # starts a comment:
+///
+///# frame  hold  input
+///0        1     start
+///60       30    lx=32767 ly=-12000
+///90       5     a rt=255 @1500
+///
+///Buttons: a b x y lb rb ls rs start back up down left right. Axes: lt rt (0 to 255), lx ly rx ry (-32768 to 32767).
+///Recordings add @<milliseconds since the recording started>, playback ignores it.
+///Entries may overlap, overlapping buttons are combined. Frames no entry covers are played as a neutral controller.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TasScript
+{
+    pub entries: Vec<TasEntry>,
+}
+
+impl TasScript
+{
+    pub fn load(path: &Path) -> Result<TasScript, String>
+    {
+        let text = fs::read_to_string(path).map_err(|e| format!("failed to read {}: {}", path.display(), e))?;
+        return TasScript::parse(&text).map_err(|e| format!("{}: {}", path.display(), e));
+    }
+
+    pub fn parse(text: &str) -> Result<TasScript, String>
+    {
+        let mut entries = Vec::new();
+        for (index, line) in text.lines().enumerate()
+        {
+            let line = match line.split_once('#')
+            {
+                Some((content, _comment)) => content,
+                None => line,
+            };
+
+            let mut tokens = line.split_whitespace();
+            let frame = match tokens.next()
+            {
+                Some(frame) => frame.parse::<u32>().map_err(|e| format!("line {}: invalid frame: {}", index + 1, e))?,
+                None => continue,
+            };
+            let hold = match tokens.next()
+            {
+                Some(hold) => hold.parse::<u32>().map_err(|e| format!("line {}: invalid hold: {}", index + 1, e))?,
+                None => return Err(format!("line {}: missing hold", index + 1)),
+            };
+            end_frame(frame, hold).map_err(|e| format!("line {}: {}", index + 1, e))?;
+
+            let mut input = TasInput::default();
+            let mut time = None;
+            for token in tokens
+            {
+                match token.strip_prefix('@')
+                {
+                    Some(milliseconds) => time = Some(milliseconds.parse::<u64>().map_err(|e| format!("line {}: invalid time: {}", index + 1, e))?),
+                    None => input.parse_token(token).map_err(|e| format!("line {}: {}", index + 1, e))?,
+                }
+            }
+            entries.push(TasEntry { frame, hold, time, input });
+        }
+        return Ok(TasScript { entries });
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String>
+    {
+        if let Some(directory) = path.parent()
+        {
+            fs::create_dir_all(directory).map_err(|e| format!("failed to create {}: {}", directory.display(), e))?;
+        }
+        return fs::write(path, self.to_text()).map_err(|e| format!("failed to write {}: {}", path.display(), e));
+    }
+
+    pub fn to_text(&self) -> String
+    {
+        let mut text = String::from("# frame hold input\n");
+        for entry in &self.entries
+        {
+            let mut tokens = entry.input.to_tokens();
+            if let Some(time) = entry.time
+            {
+                tokens.push(format!("@{}", time));
+            }
+            text.push_str(&format!("{} {} {}\n", entry.frame, entry.hold, tokens.join(" ")));
+        }
+        return text;
+    }
+
+    ///The number of frames it takes to play the script
+    pub fn length(&self) -> u32
+    {
+        return self.entries.iter().map(|e| e.frame.saturating_add(e.hold)).max().unwrap_or(0);
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use crate::script::*;
+
+    #[test]
+    fn parse_script()
+    {
+        let script = TasScript::parse("# frame hold input\n0 1 start\n\n60 30 LX=32767 ly=-12000 # run\n90 5 a rt=255\n").unwrap();
+        assert_eq!(3, script.entries.len());
+        assert_eq!(TasEntry { frame: 0, hold: 1, time: None, input: TasInput { buttons: 0x0010, ..Default::default() } }, script.entries[0]);
+        assert_eq!(32767, script.entries[1].input.thumb_lx);
+        assert_eq!(-12000, script.entries[1].input.thumb_ly);
+        assert_eq!(TasInput { buttons: 0x1000, right_trigger: 255, ..Default::default() }, script.entries[2].input);
+        assert_eq!(95, script.length());
+
+        assert_eq!("line 2: unknown button jump", TasScript::parse("0 1 a\n1 1 jump").unwrap_err());
+        assert!(TasScript::parse("0 1 lt=256").is_err());
+        assert!(TasScript::parse("0").is_err());
+        assert!(TasScript::parse("4000000000 1 a").unwrap_err().starts_with("line 1: frame 4000000000 + 1"));
+        assert!(TasScript::parse("1 4294967295 a").is_err());
+        assert_eq!(MAX_FRAMES, TasScript::parse(&format!("{} 1 a", MAX_FRAMES - 1)).unwrap().length());
+    }
+
+    #[test]
+    fn round_trip()
+    {
+        let script = TasScript::parse("0 1 start\n60 30 a b lx=32767 ly=-12000 @1000\n90 5 rt=255").unwrap();
+        assert_eq!(Some(1000), script.entries[1].time);
+        assert_eq!(script, TasScript::parse(&script.to_text()).unwrap());
+    }
+}
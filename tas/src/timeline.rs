@@ -0,0 +1,91 @@
+// This file is part of the soulmemory-rs distribution (https://github.com/FrankvdStam/soulmemory-rs).
+// Copyright (c) 2022 Frank van der Stam.
+// https://github.com/FrankvdStam/soulmemory-rs/blob/main/LICENSE
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use crate::script::{TasInput, TasScript, MAX_FRAMES};
+
+///A script expanded to the input of every frame
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Timeline
+{
+    frames: Vec<TasInput>,
+}
+
+impl Timeline
+{
+    ///Scripts built in code aren't checked like parsed ones, anything past MAX_FRAMES is cut off
+    pub fn new(script: &TasScript) -> Self
+    {
+        let length = script.length().min(MAX_FRAMES);
+        let mut frames = vec![TasInput::default(); length as usize];
+        for entry in &script.entries
+        {
+            let start = entry.frame.min(length) as usize;
+            let end = entry.frame.saturating_add(entry.hold).min(length) as usize;
+            for frame in &mut frames[start..end]
+            {
+                frame.merge(&entry.input);
+            }
+        }
+        Timeline { frames }
+    }
+
+    pub fn len(&self) -> u32
+    {
+        return self.frames.len() as u32;
+    }
+
+    pub fn is_empty(&self) -> bool
+    {
+        return self.frames.is_empty();
+    }
+
+    ///None past the end of the script
+    pub fn input_at(&self, frame: u32) -> Option<TasInput>
+    {
+        return self.frames.get(frame as usize).copied();
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use crate::script::{TasEntry, TasInput, TasScript, MAX_FRAMES};
+    use crate::timeline::Timeline;
+
+    #[test]
+    fn overlapping_entries()
+    {
+        let timeline = Timeline::new(&TasScript::parse("0 3 a lx=100\n2 2 b lx=-100\n").unwrap());
+        assert_eq!(4, timeline.len());
+        assert_eq!(Some(TasInput { buttons: 0x1000, thumb_lx: 100, ..Default::default() }), timeline.input_at(1));
+        assert_eq!(Some(TasInput { buttons: 0x3000, thumb_lx: -100, ..Default::default() }), timeline.input_at(2));
+        assert_eq!(Some(TasInput { buttons: 0x2000, thumb_lx: -100, ..Default::default() }), timeline.input_at(3));
+        assert_eq!(None, timeline.input_at(4));
+
+        //Gaps are a neutral controller
+        let timeline = Timeline::new(&TasScript::parse("5 1 a").unwrap());
+        assert_eq!(Some(TasInput::default()), timeline.input_at(0));
+    }
+
+    #[test]
+    fn cut_off_at_max_frames()
+    {
+        let entry = TasEntry { frame: u32::MAX - 1, hold: 10, time: None, input: TasInput { buttons: 0x1000, ..Default::default() } };
+        let timeline = Timeline::new(&TasScript { entries: vec![entry] });
+        assert_eq!(MAX_FRAMES, timeline.len());
+        assert_eq!(Some(TasInput::default()), timeline.input_at(MAX_FRAMES - 1));
+    }
+}